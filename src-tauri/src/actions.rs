@@ -0,0 +1,478 @@
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use enigo::{Axis, Button};
+use crate::error::FieldError;
+use crate::displays::{MoveBounds, Rect};
+use crate::input::{InputCommand, MouseClick, MousePath};
+use crate::keyspec::KeySpec;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Largest weight an action may have, so the weights of every action still
+/// add up without overflowing
+pub const MAX_WEIGHT: u32 = 1000;
+
+/// Largest mouse movement range in pixels, well beyond any single display
+pub const MAX_MOVE_RANGE: i32 = 10_000;
+
+/// Most notches one scroll may turn the wheel by
+const MAX_SCROLL_AMOUNT: i32 = 100;
+
+/// A configured action together with how often it is picked relative to the others
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedAction {
+    pub enabled: bool,
+    pub weight: u32,
+    pub action: Action,
+}
+
+/// Everything the engine can do on a tick.
+///
+/// Adding an action is self-contained: define its parameter struct with an
+/// `ActionPlanner` impl, add a variant here and in `Action::planner`, and give
+/// it a default entry in `Action::registry`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    MouseMove(MouseMoveAction),
+    Click(ClickAction),
+    Scroll(ScrollAction),
+    KeyPress(KeyPressAction),
+    Text(TextAction),
+}
+
+impl Action {
+    /// All built-in actions with their default parameters and weights.
+    /// Only mouse movement is enabled out of the box.
+    pub fn registry() -> Vec<WeightedAction> {
+        vec![
+            WeightedAction {
+                enabled: true,
+                weight: 3,
+                action: Action::MouseMove(MouseMoveAction {
+                    range: 300,
+                    bounds: MoveBounds::CurrentMonitor,
+                    restore_cursor: false,
+                }),
+            },
+            WeightedAction {
+                enabled: false,
+                weight: 1,
+                action: Action::Click(ClickAction {
+                    button: MouseButton::Left,
+                    count: 1,
+                    target: None,
+                    no_click_zones: Vec::new(),
+                }),
+            },
+            WeightedAction {
+                enabled: false,
+                weight: 1,
+                action: Action::Scroll(ScrollAction {
+                    amount: 3,
+                    axis: ScrollAxis::Vertical,
+                    pattern: ScrollPattern::Random,
+                }),
+            },
+            WeightedAction {
+                enabled: false,
+                weight: 1,
+                action: Action::KeyPress(KeyPressAction { keys: vec!["Shift".to_string()] }),
+            },
+            WeightedAction {
+                enabled: false,
+                weight: 1,
+                action: Action::Text(TextAction { text: None }),
+            },
+        ]
+    }
+
+    pub fn planner(&self) -> &dyn ActionPlanner {
+        match self {
+            Action::MouseMove(action) => action,
+            Action::Click(action) => action,
+            Action::Scroll(action) => action,
+            Action::KeyPress(action) => action,
+            Action::Text(action) => action,
+        }
+    }
+}
+
+/// Engine state an action may read while planning
+pub struct ActionContext<'a> {
+    pub rng: &'a mut dyn RngCore,
+    /// Grapheme offset into the configured text where typing resumes
+    pub text_position: usize,
+}
+
+/// An input command ready to send to the input thread
+pub struct PlannedAction {
+    pub command: InputCommand,
+    /// Set when the command types configured text starting at this offset
+    pub text_start: Option<usize>,
+}
+
+impl From<InputCommand> for PlannedAction {
+    fn from(command: InputCommand) -> Self {
+        Self { command, text_start: None }
+    }
+}
+
+/// Turns an action's parameters into concrete input
+pub trait ActionPlanner {
+    fn plan(&self, ctx: &mut ActionContext<'_>) -> Result<PlannedAction, String>;
+
+    /// Check the parameters before the engine starts. Fields are named
+    /// relative to the action, e.g. `range`.
+    fn validate(&self) -> Vec<FieldError> {
+        Vec::new()
+    }
+}
+
+/// Move the mouse smoothly by a random offset of up to `range` pixels per
+/// axis, staying inside `bounds`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MouseMoveAction {
+    pub range: i32,
+    #[serde(default)]
+    pub bounds: MoveBounds,
+    /// Move the cursor back to where it started after each move
+    #[serde(default)]
+    pub restore_cursor: bool,
+}
+
+impl ActionPlanner for MouseMoveAction {
+    fn plan(&self, ctx: &mut ActionContext<'_>) -> Result<PlannedAction, String> {
+        let dx = ctx.rng.gen_range(-self.range..=self.range);
+        let dy = ctx.rng.gen_range(-self.range..=self.range);
+        let path = MousePath {
+            bounds: Some(self.bounds),
+            restore: self.restore_cursor,
+            ..MousePath::smooth(dx, dy, &mut ctx.rng)
+        };
+        Ok(InputCommand::MovePath(path).into())
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        if !(0..=MAX_MOVE_RANGE).contains(&self.range) {
+            problems.push(FieldError::new(
+                "range",
+                format!("Mouse movement range must be between 0 and {} pixels", MAX_MOVE_RANGE),
+            ));
+        }
+        if let MoveBounds::Region(area) = self.bounds {
+            if area.is_empty() {
+                problems.push(FieldError::new("bounds", "Movement region must have a positive width and height"));
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseButton {
+    #[default]
+    Left,
+    Middle,
+    Right,
+}
+
+/// Where to move before clicking
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClickTarget {
+    Point { x: i32, y: i32 },
+    /// A random point inside the area
+    Region(Rect),
+}
+
+fn default_click_count() -> u32 {
+    1
+}
+
+/// Click a mouse button `count` times, at `target` or wherever the cursor is.
+/// Clicks landing in one of `no_click_zones` are refused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClickAction {
+    #[serde(default)]
+    pub button: MouseButton,
+    #[serde(default = "default_click_count")]
+    pub count: u32,
+    #[serde(default)]
+    pub target: Option<ClickTarget>,
+    #[serde(default)]
+    pub no_click_zones: Vec<Rect>,
+}
+
+impl ActionPlanner for ClickAction {
+    fn plan(&self, ctx: &mut ActionContext<'_>) -> Result<PlannedAction, String> {
+        let target = self.target.map(|target| match target {
+            ClickTarget::Point { x, y } => (x, y),
+            ClickTarget::Region(area) => (
+                ctx.rng.gen_range(area.x..area.x + area.width),
+                ctx.rng.gen_range(area.y..area.y + area.height),
+            ),
+        });
+        let button = match self.button {
+            MouseButton::Left => Button::Left,
+            MouseButton::Middle => Button::Middle,
+            MouseButton::Right => Button::Right,
+        };
+        let click = MouseClick {
+            button,
+            count: self.count,
+            gap_ms: ctx.rng.gen_range(60..120),
+            target,
+            glide_seed: ctx.rng.gen(),
+            no_click_zones: self.no_click_zones.clone(),
+        };
+        Ok(InputCommand::Click(click).into())
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        if !(1..=3).contains(&self.count) {
+            problems.push(FieldError::new("count", "Click count must be between 1 and 3"));
+        }
+        match self.target {
+            Some(ClickTarget::Region(area)) if area.is_empty() => {
+                problems.push(FieldError::new("target", "Click region must have a positive width and height"));
+            }
+            Some(ClickTarget::Point { x, y }) if self.no_click_zones.iter().any(|zone| zone.contains((x, y))) => {
+                problems.push(FieldError::new("target", "Click target lies inside a no-click zone"));
+            }
+            _ => {}
+        }
+        for (i, zone) in self.no_click_zones.iter().enumerate() {
+            if zone.is_empty() {
+                problems.push(FieldError::new(
+                    format!("no_click_zones[{}]", i),
+                    "No-click zone must have a positive width and height",
+                ));
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollAxis {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+/// Which way to scroll. On the horizontal axis down means right.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollPattern {
+    /// Down or up, picked at random each time
+    #[default]
+    Random,
+    Down,
+    Up,
+    /// Down and back up again, leaving the page where it was
+    DownThenUp,
+}
+
+/// Scroll the wheel by `amount` notches along `axis`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrollAction {
+    pub amount: i32,
+    #[serde(default)]
+    pub axis: ScrollAxis,
+    #[serde(default)]
+    pub pattern: ScrollPattern,
+}
+
+impl ActionPlanner for ScrollAction {
+    fn plan(&self, ctx: &mut ActionContext<'_>) -> Result<PlannedAction, String> {
+        let amount = self.amount;
+        let runs = match self.pattern {
+            ScrollPattern::Random if ctx.rng.gen_bool(0.5) => vec![amount],
+            ScrollPattern::Random => vec![-amount],
+            ScrollPattern::Down => vec![amount],
+            ScrollPattern::Up => vec![-amount],
+            ScrollPattern::DownThenUp => vec![amount, -amount],
+        };
+        let axis = match self.axis {
+            ScrollAxis::Vertical => Axis::Vertical,
+            ScrollAxis::Horizontal => Axis::Horizontal,
+        };
+        Ok(InputCommand::scroll(axis, &runs, &mut ctx.rng).into())
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        if !(1..=MAX_SCROLL_AMOUNT).contains(&self.amount) {
+            problems.push(FieldError::new(
+                "amount",
+                format!("Scroll amount must be between 1 and {}", MAX_SCROLL_AMOUNT),
+            ));
+        }
+        problems
+    }
+}
+
+/// Tap one key or chord picked at random from `keys`, e.g. `F15` or
+/// `Ctrl+Alt+Right`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyPressAction {
+    pub keys: Vec<String>,
+}
+
+impl ActionPlanner for KeyPressAction {
+    fn plan(&self, ctx: &mut ActionContext<'_>) -> Result<PlannedAction, String> {
+        if self.keys.is_empty() {
+            return Err("No keys configured".to_string());
+        }
+        let name = &self.keys[ctx.rng.gen_range(0..self.keys.len())];
+        Ok(InputCommand::KeyChord(KeySpec::parse(name)?).into())
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        if self.keys.is_empty() {
+            return vec![FieldError::new("keys", "Key press action needs at least one key")];
+        }
+        self.keys
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| {
+                KeySpec::parse(name)
+                    .err()
+                    .map(|e| FieldError::new(format!("keys[{}]", i), e))
+            })
+            .collect()
+    }
+}
+
+/// Type the configured text a few words at a time, continuing where the
+/// previous tick stopped. Without text a random harmless character is typed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextAction {
+    pub text: Option<String>,
+}
+
+impl TextAction {
+    /// Pick the next slice of `text` to type, as a range of grapheme clusters
+    /// starting at `position`. Chunks end at a sentence boundary if one is in
+    /// reach, else after a space or punctuation, so CJK text breaks at `。`
+    /// and `、` the way English breaks at `.` and `,`.
+    fn next_chunk(text: &str, position: usize, rng: &mut dyn RngCore) -> (usize, usize) {
+        let offsets: Vec<usize> = text.grapheme_indices(true).map(|(offset, _)| offset).collect();
+
+        // Reset position if we've reached the end
+        let start_pos = if position >= offsets.len() {
+            println!("Reached end of text, wrapping around to beginning");
+            0
+        } else {
+            position
+        };
+
+        // Determine how many graphemes to type (5-20 or until end of sentence/word)
+        let base_len = rng.gen_range(5..=20);
+        let max_end = (start_pos + base_len).min(offsets.len());
+        // Type at least the minimum before looking for a place to stop
+        let min_end = (start_pos + 6).min(max_end);
+
+        // Segment ends as grapheme counts; word and sentence boundaries never
+        // fall inside a grapheme cluster
+        let grapheme_end = |offset: usize| offsets.partition_point(|&start| start < offset);
+        let in_reach = |end: &usize| (min_end..=max_end).contains(end);
+        let sentence_end = text
+            .split_sentence_bound_indices()
+            .map(|(offset, sentence)| grapheme_end(offset + sentence.len()))
+            .find(in_reach);
+        let word_end = || {
+            text.split_word_bound_indices()
+                .filter(|(_, segment)| !segment.chars().any(char::is_alphanumeric))
+                .map(|(offset, segment)| grapheme_end(offset + segment.len()))
+                .find(in_reach)
+        };
+
+        (start_pos, sentence_end.or_else(word_end).unwrap_or(max_end))
+    }
+}
+
+impl ActionPlanner for TextAction {
+    fn plan(&self, ctx: &mut ActionContext<'_>) -> Result<PlannedAction, String> {
+        match self.text.as_deref() {
+            // Type text sequentially from the configured text
+            Some(text) if !text.is_empty() => {
+                let (start, end) = Self::next_chunk(text, ctx.text_position, ctx.rng);
+                let chunk: String = text.graphemes(true).skip(start).take(end - start).collect();
+                Ok(PlannedAction {
+                    command: InputCommand::type_text(&chunk, &mut ctx.rng),
+                    text_start: Some(start),
+                })
+            }
+            _ => {
+                // Default behavior: type a random character
+                let chars = [' ', 'a', 'e', 'i', 'o', 'u'];
+                let random_char = chars[ctx.rng.gen_range(0..chars.len())];
+                Ok(InputCommand::Text(random_char.to_string()).into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Plan chunks from the start to the end of `text`, returning each
+    /// chunk's keystrokes
+    fn chunks(text: &str, seed: u64) -> Vec<Vec<String>> {
+        let action = TextAction { text: Some(text.to_string()) };
+        let mut rng = StdRng::seed_from_u64(seed);
+        let total = text.graphemes(true).count();
+        let mut position = 0;
+        let mut chunks = Vec::new();
+        while position < total {
+            let mut ctx = ActionContext { rng: &mut rng, text_position: position };
+            let planned = action.plan(&mut ctx).unwrap();
+            assert_eq!(planned.text_start, Some(position));
+            let InputCommand::TypeText(keystrokes) = planned.command else {
+                panic!("configured text is typed keystroke by keystroke");
+            };
+            assert!(!keystrokes.is_empty());
+            position += keystrokes.len();
+            chunks.push(keystrokes.into_iter().map(|keystroke| keystroke.text).collect());
+        }
+        chunks
+    }
+
+    #[test]
+    fn chunks_never_split_a_grapheme() {
+        let texts = [
+            "안녕하세요. 반갑습니다, 오늘 날씨가 정말 좋네요! 내일 봬요.",
+            // Hangul spelled with conjoining jamo, three code points per syllable
+            "\u{1112}\u{1161}\u{11ab}\u{1100}\u{1173}\u{11af} \u{1112}\u{1161}\u{11ab}\u{1100}\u{1173}\u{11af}.",
+            "今日はいい天気ですね。明日も晴れるでしょう、たぶん。カタカナもひらがなも漢字も。",
+            "👩‍👩‍👧‍👦 family, 👨🏽‍💻 at work. 🏳️‍🌈 and 🧑‍🤝‍🧑 too!",
+            "🇰🇷🇯🇵🇺🇸🇫🇷🇩🇪🇧🇷 flags 🇨🇦🇮🇳. More: 🇦🇺🇳🇿",
+            "Cafe\u{301} line one\r\nline two\r\nline three.",
+        ];
+        for text in texts {
+            let graphemes: Vec<&str> = text.graphemes(true).collect();
+            for seed in 0..20 {
+                let typed: Vec<String> = chunks(text, seed).into_iter().flatten().collect();
+                assert_eq!(typed, graphemes, "seed {} on {:?}", seed, text);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_wrap_around_at_the_end() {
+        let text = "今日は。明日は。";
+        let action = TextAction { text: Some(text.to_string()) };
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut ctx = ActionContext { rng: &mut rng, text_position: 8 };
+        assert_eq!(action.plan(&mut ctx).unwrap().text_start, Some(0));
+    }
+}
+
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use crate::failsafe::ScreenCorner;
+
+/// How many entries the history keeps before dropping the oldest
+const HISTORY_LIMIT: usize = 200;
+
+/// Why the engine let a tick pass without acting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The focused window is not in `active_apps`
+    FocusNotAllowed,
+    /// The focused window could not be determined, so acting was not safe
+    FocusUnknown,
+    /// The user has touched the mouse or keyboard too recently
+    UserActive,
+    /// Idle time could not be read, so the user may be active
+    IdleUnknown,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::FocusNotAllowed => write!(f, "focus not allowed"),
+            SkipReason::FocusUnknown => write!(f, "focus unknown"),
+            SkipReason::UserActive => write!(f, "user active"),
+            SkipReason::IdleUnknown => write!(f, "idle time unknown"),
+        }
+    }
+}
+
+/// Something the engine did or declined to do
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Activity {
+    Performed { action: String },
+    Aborted { action: String, steps: usize },
+    Failed { action: String, error: String },
+    Skipped { reason: SkipReason, app: Option<String> },
+    /// The cursor hit a fail-safe corner and automation was stopped
+    FailSafe { corner: ScreenCorner },
+    /// A timed session ran out and automation was stopped
+    SessionEnded,
+}
+
+impl fmt::Display for Activity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Activity::Performed { action } => write!(f, "Performed {}", action),
+            Activity::Aborted { action, steps } => write!(f, "Aborted {} after {} steps", action, steps),
+            Activity::Failed { action, error } => write!(f, "Failed to perform {}: {}", action, error),
+            Activity::Skipped { reason, app: Some(app) } => write!(f, "Skipped: {} ({})", reason, app),
+            Activity::Skipped { reason, app: None } => write!(f, "Skipped: {}", reason),
+            Activity::FailSafe { corner } => write!(f, "Fail-safe triggered in the {} corner, automation stopped", corner),
+            Activity::SessionEnded => write!(f, "Session time is up, automation stopped"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityRecord {
+    pub at: DateTime<Local>,
+    #[serde(flatten)]
+    pub activity: Activity,
+}
+
+type Listener = Box<dyn Fn(&ActivityRecord) + Send + Sync>;
+
+/// Recent engine activity, kept for the UI and forwarded to a listener as it happens
+#[derive(Default)]
+pub struct ActivityLog {
+    history: Mutex<VecDeque<ActivityRecord>>,
+    listener: Mutex<Option<Listener>>,
+}
+
+impl ActivityLog {
+    /// Forward every new record, e.g. as a frontend event
+    pub fn set_listener(&self, listener: impl Fn(&ActivityRecord) + Send + Sync + 'static) {
+        *self.listener.lock().unwrap() = Some(Box::new(listener));
+    }
+
+    pub fn record(&self, activity: Activity) {
+        println!("{}", activity);
+        let record = ActivityRecord {
+            at: Local::now(),
+            activity,
+        };
+
+        {
+            let mut history = self.history.lock().unwrap();
+            if history.len() == HISTORY_LIMIT {
+                history.pop_front();
+            }
+            history.push_back(record.clone());
+        }
+        if let Some(listener) = self.listener.lock().unwrap().as_ref() {
+            listener(&record);
+        }
+    }
+
+    /// Oldest first
+    pub fn history(&self) -> Vec<ActivityRecord> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+}
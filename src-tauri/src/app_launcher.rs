@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 pub struct AppConfig {
     pub name: String,
     pub path: String,
+    #[serde(default)]
     pub args: Vec<String>,
     pub icon: Option<String>,
 }
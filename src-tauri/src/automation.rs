@@ -1,397 +1,904 @@
-use rand::{thread_rng, Rng};
-use std::sync::Arc;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tokio::time::sleep;
+use tokio::sync::{watch, Mutex, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, sleep_until, Instant};
+use tokio_util::sync::CancellationToken;
 use serde::{Deserialize, Serialize};
-use enigo::{Enigo, Settings, Coordinate, Direction, Button, Keyboard, Mouse};
+use chrono::{DateTime, Local};
+use crate::actions::{Action, ActionContext, WeightedAction, MAX_WEIGHT};
+use crate::activity::{Activity, ActivityLog, SkipReason};
+use crate::error::{FieldError, LupinError};
+use crate::failsafe::{self, Client, ScreenCorner};
+use crate::focus::FocusProbe;
+use crate::idle::{IdleProbe, IdleTracker};
+use crate::input::InputActor;
+use crate::keep_awake::{InhibitGuard, KeepAwakeBackend};
+use crate::scheduler::{self, Zone};
+
+/// What an engine does while it runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutomationMode {
+    /// Inject the configured actions at random intervals
+    #[default]
+    Simulate,
+    /// Only hold an OS lock that keeps the screen on and the machine awake
+    KeepAwake,
+    /// Keep the machine awake and inject actions
+    Both,
+}
+
+impl AutomationMode {
+    pub fn simulates(self) -> bool {
+        matches!(self, AutomationMode::Simulate | AutomationMode::Both)
+    }
+
+    pub fn keeps_awake(self) -> bool {
+        matches!(self, AutomationMode::KeepAwake | AutomationMode::Both)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutomationConfig {
     pub enabled: bool,
+    #[serde(default)]
+    pub mode: AutomationMode,
     pub min_interval_ms: u64,
     pub max_interval_ms: u64,
-    pub mouse_movement_range: i32,
-    pub enable_clicks: bool,
-    pub enable_keyboard: bool,
-    pub keyboard_text: Option<String>,
+    /// Only act after the user has been inactive this long, and abort
+    /// whatever is being injected as soon as they touch the mouse or keyboard
+    #[serde(default)]
+    pub idle_threshold_ms: Option<u64>,
+    pub actions: Vec<WeightedAction>,
     pub active_apps: Vec<String>,
+    /// Moving the cursor into one of these corners stops automation at once.
+    /// Empty turns the fail-safe off.
+    #[serde(default = "failsafe::default_corners")]
+    pub fail_safe_corners: Vec<ScreenCorner>,
 }
 
 impl Default for AutomationConfig {
     fn default() -> Self {
         Self {
             enabled: false,
+            mode: AutomationMode::default(),
             min_interval_ms: 30000,  // 30 seconds
             max_interval_ms: 840000,  // 14 minutes
-            mouse_movement_range: 5,
-            enable_clicks: false,
-            enable_keyboard: false,
-            keyboard_text: None,
+            idle_threshold_ms: None,
+            actions: Action::registry(),
             active_apps: vec![],
+            fail_safe_corners: failsafe::default_corners(),
+        }
+    }
+}
+
+/// Shortest allowed interval between actions
+const MIN_INTERVAL_MS: u64 = 1000;
+
+/// How often user input is checked for while an action is being injected
+const USER_INPUT_POLL: Duration = Duration::from_millis(100);
+
+/// Longest timed session, which also catches a mistyped duration
+const MAX_SESSION_MINUTES: u32 = 24 * 60;
+
+/// How often a timed session checks the clock, so time spent suspended
+/// still counts towards it
+const SESSION_POLL: Duration = Duration::from_secs(15);
+
+/// When a session stops by itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionLimit {
+    /// Run for this many minutes
+    Duration { minutes: u32 },
+    /// Run until this local time as `HH:MM`, tomorrow if it has already passed today
+    Until { time: String },
+}
+
+impl SessionLimit {
+    /// When a session started at `now` ends. Pausing does not extend it.
+    pub fn ends_at(&self, now: DateTime<Local>) -> Result<DateTime<Local>, LupinError> {
+        let invalid = |field: &str, message: String| LupinError::InvalidConfig {
+            problems: vec![FieldError::new(format!("session.{}", field), message)],
+        };
+        match self {
+            SessionLimit::Duration { minutes } => {
+                if !(1..=MAX_SESSION_MINUTES).contains(minutes) {
+                    return Err(invalid(
+                        "minutes",
+                        format!("Session must last between 1 and {} minutes", MAX_SESSION_MINUTES),
+                    ));
+                }
+                Ok(now + chrono::Duration::minutes((*minutes).into()))
+            }
+            SessionLimit::Until { time } => {
+                let time = scheduler::parse_time(time).map_err(|message| invalid("time", message))?;
+                let today = now.date_naive();
+                [today, today + chrono::Duration::days(1)]
+                    .into_iter()
+                    .map(|date| Zone::System.resolve(date.and_time(time)).with_timezone(&Local))
+                    .find(|end| *end > now)
+                    .ok_or_else(|| invalid("time", "Session end could not be worked out".to_string()))
+            }
+        }
+    }
+}
+
+impl AutomationConfig {
+    /// Check every field the engine relies on, reporting all problems at once
+    pub fn validate(&self) -> Result<(), LupinError> {
+        let mut problems = Vec::new();
+        if self.mode.simulates() {
+            self.validate_simulation(&mut problems);
+        }
+
+        for (i, app) in self.active_apps.iter().enumerate() {
+            if app.trim().is_empty() {
+                problems.push(FieldError::new(format!("active_apps[{}]", i), "App name must not be empty"));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(LupinError::InvalidConfig { problems })
+        }
+    }
+
+    /// Intervals and actions only matter when input is injected
+    fn validate_simulation(&self, problems: &mut Vec<FieldError>) {
+        if self.min_interval_ms < MIN_INTERVAL_MS {
+            problems.push(FieldError::new(
+                "min_interval_ms",
+                format!("Minimum interval must be at least {} ms", MIN_INTERVAL_MS),
+            ));
+        }
+        if self.min_interval_ms > self.max_interval_ms {
+            problems.push(FieldError::new(
+                "max_interval_ms",
+                "Maximum interval must not be shorter than the minimum interval",
+            ));
+        }
+
+        if self.idle_threshold_ms.is_some_and(|threshold| threshold < 1000) {
+            problems.push(FieldError::new("idle_threshold_ms", "Idle threshold must be at least 1 second"));
+        }
+
+        if !self.actions.iter().any(|entry| entry.enabled && entry.weight > 0) {
+            problems.push(FieldError::new("actions", "At least one action must be enabled"));
+        }
+        for (i, entry) in self.actions.iter().enumerate().filter(|(_, entry)| entry.enabled) {
+            if entry.weight > MAX_WEIGHT {
+                problems.push(FieldError::new(
+                    format!("actions[{}].weight", i),
+                    format!("Weight must be at most {}", MAX_WEIGHT),
+                ));
+            }
+            for problem in entry.action.planner().validate() {
+                problems.push(FieldError::new(
+                    format!("actions[{}].action.{}", i, problem.field),
+                    problem.message,
+                ));
+            }
+        }
+    }
+}
+
+/// Lifecycle state of an automation engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineState {
+    Idle,
+    Running,
+    Paused,
+    Stopping,
+}
+
+/// Snapshot of the engine state returned to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct AutomationStatus {
+    pub state: EngineState,
+    pub started_at: Option<DateTime<Local>>,
+    pub next_fire_at: Option<DateTime<Local>>,
+    /// When a timed session stops by itself
+    pub ends_at: Option<DateTime<Local>>,
+}
+
+impl Default for AutomationStatus {
+    fn default() -> Self {
+        Self {
+            state: EngineState::Idle,
+            started_at: None,
+            next_fire_at: None,
+            ends_at: None,
         }
     }
 }
 
+/// Platform services engines drive, shared by every engine the app creates
+#[derive(Clone)]
+pub struct EngineServices {
+    pub input: Arc<InputActor>,
+    pub keep_awake: Arc<dyn KeepAwakeBackend>,
+    pub focus: Arc<dyn FocusProbe>,
+    pub idle: Arc<dyn IdleProbe>,
+    pub activity: Arc<ActivityLog>,
+}
+
 pub struct AutomationEngine {
     config: Arc<Mutex<AutomationConfig>>,
     text_position: Arc<Mutex<usize>>,  // Track current position in text
+    services: EngineServices,
+    idle: IdleTracker,
+    rng: StdMutex<StdRng>,
+    state: watch::Sender<EngineState>,
+    status: StdMutex<AutomationStatus>,
+    /// Corner the fail-safe tripped on, reported once the task has wound down
+    tripped: StdMutex<Option<ScreenCorner>>,
+    /// Set when a timed session ran out, reported once the task has wound down
+    expired: AtomicBool,
+    cancel: CancellationToken,
+    /// Wakes the pending sleep so new intervals or a new mode take effect
+    reschedule: Notify,
+    task: StdMutex<Option<JoinHandle<()>>>,
 }
 
 impl AutomationEngine {
-    pub fn new(config: AutomationConfig) -> Self {
+    pub fn new(config: AutomationConfig, services: EngineServices) -> Self {
+        Self::with_rng(config, services, StdRng::from_entropy())
+    }
+
+    /// Create an engine with a specific RNG, e.g. a seeded one for reproducible runs
+    pub fn with_rng(config: AutomationConfig, services: EngineServices, rng: StdRng) -> Self {
+        let (state, _) = watch::channel(EngineState::Idle);
         Self {
             config: Arc::new(Mutex::new(config)),
             text_position: Arc::new(Mutex::new(0)),
+            idle: IdleTracker::new(Arc::clone(&services.idle)),
+            services,
+            rng: StdMutex::new(rng),
+            state,
+            status: StdMutex::new(AutomationStatus::default()),
+            tripped: StdMutex::new(None),
+            expired: AtomicBool::new(false),
+            cancel: CancellationToken::new(),
+            reschedule: Notify::new(),
+            task: StdMutex::new(None),
+        }
+    }
+
+    /// Switch to Running and drive the engine on a background task, stopping
+    /// by itself at `ends_at` if given
+    pub fn start(self: &Arc<Self>, ends_at: Option<DateTime<Local>>) {
+        if !self.transition(EngineState::Idle, EngineState::Running) {
+            return;
+        }
+        {
+            let mut status = self.status.lock().unwrap();
+            status.started_at = Some(Local::now());
+            status.ends_at = ends_at;
+        }
+
+        // Subscribe before spawning so no trip can slip through
+        let mut trips = self.services.input.fail_safe().subscribe();
+        let engine = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::select! {
+                trip = trips.recv() => {
+                    if let Ok(corner) = trip {
+                        engine.fail_safe_tripped(corner);
+                    }
+                }
+                _ = engine.cancel.cancelled() => {}
+            }
+        });
+
+        if let Some(ends_at) = ends_at {
+            let engine = Arc::clone(self);
+            tokio::spawn(async move {
+                // Compare against the wall clock rather than sleeping the whole
+                // session, which would not count time spent suspended
+                loop {
+                    let remaining = (ends_at - Local::now()).to_std().unwrap_or_default();
+                    if remaining.is_zero() {
+                        engine.session_expired();
+                        break;
+                    }
+                    tokio::select! {
+                        _ = sleep(remaining.min(SESSION_POLL)) => {}
+                        _ = engine.cancel.cancelled() => break,
+                    }
+                }
+            });
         }
+
+        let engine = Arc::clone(self);
+        let task = tokio::spawn(async move {
+            engine.run().await;
+        });
+        *self.task.lock().unwrap() = Some(task);
+    }
+
+    /// The input thread has already aborted the current action and released
+    /// held inputs; all that is left is to stop
+    fn fail_safe_tripped(&self, corner: ScreenCorner) {
+        *self.tripped.lock().unwrap() = Some(corner);
+        self.request_stop();
     }
 
-    pub async fn run(&self) {
+    fn session_expired(&self) {
+        self.expired.store(true, Ordering::SeqCst);
+        self.request_stop();
+    }
+
+    async fn run(&self) {
+        let mut state_rx = self.state.subscribe();
+        // Intervals are measured from the last action, so a reschedule keeps
+        // the time already waited
+        let mut cycle_start = Instant::now();
+        let mut keep_awake: Option<InhibitGuard> = None;
+        let fail_safe = self.services.input.fail_safe();
+        fail_safe.arm(Client::Engine, self.config.lock().await.fail_safe_corners.clone());
         loop {
+            // Wait here while paused; any other state change ends the wait
+            while *state_rx.borrow_and_update() == EngineState::Paused {
+                // Pausing lets the machine sleep again
+                keep_awake = None;
+                cycle_start = Instant::now();
+                self.status.lock().unwrap().next_fire_at = None;
+                tokio::select! {
+                    changed = state_rx.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                    _ = self.cancel.cancelled() => break,
+                }
+            }
+            if *state_rx.borrow() != EngineState::Running {
+                break;
+            }
+
             // Generate random values before await
-            let (interval, mouse_range) = {
+            let (mode, interval) = {
                 let config = self.config.lock().await;
                 if !config.enabled {
                     break;
                 }
                 
-                let mut rng = thread_rng();
-                let interval = rng.gen_range(config.min_interval_ms..=config.max_interval_ms);
-                (interval, config.mouse_movement_range)
+                // Configs are validated before the engine starts; clamp anyway so
+                // a bad range can never panic inside this task
+                let max = config.max_interval_ms.max(config.min_interval_ms);
+                let interval = self.rng.lock().unwrap().gen_range(config.min_interval_ms..=max);
+                (config.mode, interval)
             };
             
-            // Sleep with generated interval
-            sleep(Duration::from_millis(interval)).await;
+            if !mode.keeps_awake() {
+                keep_awake = None;
+            } else if keep_awake.is_none() {
+                match self.services.keep_awake.inhibit("Lupin automation is running").await {
+                    Ok(guard) => {
+                        println!("Keeping the system awake");
+                        keep_awake = Some(guard);
+                    }
+                    Err(e) => println!("Failed to keep the system awake: {}", e),
+                }
+            }
+            
+            if !mode.simulates() {
+                // Nothing to schedule; wait for a pause, a stop or a new config
+                self.status.lock().unwrap().next_fire_at = None;
+                tokio::select! {
+                    _ = state_rx.changed() => {}
+                    _ = self.reschedule.notified() => {}
+                    _ = self.cancel.cancelled() => break,
+                }
+                cycle_start = Instant::now();
+                continue;
+            }
             
-            // Re-check if still enabled after sleep
-            let config = self.config.lock().await;
-            if !config.enabled {
-                break;
+            let deadline = cycle_start + Duration::from_millis(interval);
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            self.status.lock().unwrap().next_fire_at =
+                Some(Local::now() + chrono::Duration::milliseconds(remaining.as_millis() as i64));
+            
+            // Sleep with generated interval, waking early on pause, stop or new intervals
+            tokio::select! {
+                _ = sleep_until(deadline) => {}
+                _ = state_rx.changed() => continue,
+                _ = self.reschedule.notified() => {
+                    println!("Config changed, rescheduling next action");
+                    continue;
+                }
+                _ = self.cancel.cancelled() => break,
+            }
+            cycle_start = Instant::now();
+            
+            // Paused or stopped while sleeping: the pending action is dropped
+            if *self.state.borrow() != EngineState::Running {
+                continue;
             }
             
+            // Re-check if still enabled after sleep
+            let config = {
+                let config = self.config.lock().await;
+                if !config.enabled {
+                    break;
+                }
+                config.clone()
+            };
+            
             // Perform automation action
-            self.perform_action(
-                mouse_range,
-                config.enable_clicks,
-                config.enable_keyboard,
-                config.keyboard_text.clone()
-            ).await;
+            self.perform_action(&config).await;
+        }
+
+        drop(keep_awake);
+        fail_safe.disarm(Client::Engine);
+        self.state.send_replace(EngineState::Idle);
+        *self.status.lock().unwrap() = AutomationStatus::default();
+        // After the aborted action, so the history reads in order
+        if let Some(corner) = self.tripped.lock().unwrap().take() {
+            self.services.activity.record(Activity::FailSafe { corner });
+        }
+        if self.expired.swap(false, Ordering::SeqCst) {
+            self.services.activity.record(Activity::SessionEnded);
+        }
+        println!("Automation engine finished");
+    }
+
+    /// Move from `from` to `to`, returning false if the engine was in another state
+    fn transition(&self, from: EngineState, to: EngineState) -> bool {
+        self.state.send_if_modified(|state| {
+            if *state == from {
+                *state = to;
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    pub fn pause(&self) -> bool {
+        let paused = self.transition(EngineState::Running, EngineState::Paused);
+        if paused {
+            println!("Automation engine paused");
+        }
+        paused
+    }
+
+    pub fn resume(&self) -> bool {
+        let resumed = self.transition(EngineState::Paused, EngineState::Running);
+        if resumed {
+            println!("Automation engine resumed");
+        }
+        resumed
+    }
+
+    /// Swap in a new config while the engine keeps running. The caller is
+    /// expected to have validated it. Action changes apply from the next tick;
+    /// interval and mode changes reschedule the pending one right away.
+    pub async fn update_config(&self, mut new_config: AutomationConfig) {
+        let mut config = self.config.lock().await;
+        new_config.enabled = config.enabled;
+        let reschedule = new_config.mode != config.mode
+            || new_config.min_interval_ms != config.min_interval_ms
+            || new_config.max_interval_ms != config.max_interval_ms;
+        if new_config.fail_safe_corners != config.fail_safe_corners {
+            self.services.input.fail_safe().arm(Client::Engine, new_config.fail_safe_corners.clone());
+        }
+        *config = new_config;
+        drop(config);
+
+        if reschedule {
+            self.reschedule.notify_one();
+        }
+        println!("Automation config updated");
+    }
+
+    pub fn status(&self) -> AutomationStatus {
+        let mut status = self.status.lock().unwrap().clone();
+        status.state = *self.state.borrow();
+        status
+    }
+
+    /// Check the foreground window against `active_apps`. An empty list allows
+    /// every window; otherwise a window that cannot be identified is refused.
+    fn focus_skip_reason(&self, active_apps: &[String]) -> Option<(SkipReason, Option<String>)> {
+        if active_apps.is_empty() {
+            return None;
+        }
+        match self.services.focus.focused_window() {
+            Ok(Some(window)) if window.matches(active_apps) => None,
+            Ok(Some(window)) => Some((SkipReason::FocusNotAllowed, Some(window.app))),
+            Ok(None) => Some((SkipReason::FocusNotAllowed, None)),
+            Err(e) => {
+                println!("Failed to query the focused window: {}", e);
+                Some((SkipReason::FocusUnknown, None))
+            }
+        }
+    }
+
+    /// Whether the user has been inactive for at least `threshold`. Fails
+    /// closed: if idle time cannot be read, the user counts as active.
+    async fn idle_skip_reason(&self, threshold: Duration) -> Option<SkipReason> {
+        let input = &self.services.input;
+        match self.idle.user_idle(input.last_event(), input.user_moved_at()).await {
+            Ok(idle) if idle >= threshold => None,
+            Ok(_) => Some(SkipReason::UserActive),
+            Err(e) => {
+                println!("Failed to read idle time: {}", e);
+                Some(SkipReason::IdleUnknown)
+            }
+        }
+    }
+
+    /// Whether real user input arrived after `since`
+    async fn user_input_since(&self, since: Instant) -> bool {
+        let input = &self.services.input;
+        match self.idle.user_idle(input.last_event(), input.user_moved_at()).await {
+            Ok(idle) => idle < since.elapsed(),
+            Err(_) => false,
         }
     }
 
-    async fn perform_action(&self, mouse_range: i32, enable_clicks: bool, enable_keyboard: bool, keyboard_text: Option<String>) {
+    async fn perform_action(&self, config: &AutomationConfig) {
+        if self.cancel.is_cancelled() {
+            return;
+        }
+        
+        if let Some(threshold) = config.idle_threshold_ms {
+            if let Some(reason) = self.idle_skip_reason(Duration::from_millis(threshold)).await {
+                self.services.activity.record(Activity::Skipped { reason, app: None });
+                return;
+            }
+        }
+        if let Some((reason, app)) = self.focus_skip_reason(&config.active_apps) {
+            self.services.activity.record(Activity::Skipped { reason, app });
+            return;
+        }
+        
         // Check if any action is enabled
-        if !enable_clicks && !enable_keyboard {
+        let enabled: Vec<&WeightedAction> = config.actions
+            .iter()
+            .filter(|entry| entry.enabled && entry.weight > 0)
+            .collect();
+        if enabled.is_empty() {
             println!("No actions enabled, skipping");
             return;
         }
         
-        // Determine action before creating non-Send types
-        let action = {
-            let mut rng = thread_rng();
-            // If only keyboard is enabled, always do keyboard action
-            if enable_keyboard && !enable_clicks {
-                2
-            }
-            // If only clicks are enabled, choose between mouse move or click
-            else if enable_clicks && !enable_keyboard {
-                rng.gen_range(0..2)
-            }
-            // If both are enabled, choose from all actions
-            else {
-                rng.gen_range(0..3)
-            }
-        };
+        let mut text_position = self.text_position.lock().await;
         
-        // Handle keyboard text position if needed
-        let mut text_position = if action == 2 && enable_keyboard {
-            Some(self.text_position.lock().await)
-        } else {
-            None
+        // Pick a weighted action and plan it; the input thread does the rest
+        let planned = {
+            let mut rng = self.rng.lock().unwrap();
+            // Summed as u64 so even unvalidated weights cannot overflow the total
+            match WeightedIndex::new(enabled.iter().map(|entry| u64::from(entry.weight))) {
+                Ok(weights) => {
+                    let action = &enabled[weights.sample(&mut *rng)].action;
+                    let mut ctx = ActionContext {
+                        rng: &mut *rng,
+                        text_position: *text_position,
+                    };
+                    action.planner().plan(&mut ctx)
+                }
+                Err(e) => {
+                    drop(rng);
+                    self.services.activity.record(Activity::Failed {
+                        action: "action choice".to_string(),
+                        error: format!("Invalid action weights: {}", e),
+                    });
+                    return;
+                }
+            }
         };
-        
-        // Now create non-Send types after all awaits
-        let mut enigo = match Enigo::new(&Settings::default()) {
-            Ok(e) => e,
+        let (command, text_start) = match planned {
+            Ok(planned) => (planned.command, planned.text_start),
             Err(e) => {
-                println!("Failed to initialize Enigo: {:?}", e);
+                println!("Failed to plan action: {}", e);
                 return;
             }
         };
         
-        let mut rng = thread_rng();
-        
-        match action {
-            0 => {
-                // Move mouse smoothly to a random position
-                let target_dx = rng.gen_range(-mouse_range..=mouse_range) as i32;
-                let target_dy = rng.gen_range(-mouse_range..=mouse_range) as i32;
-                
-                // Smooth movement with multiple small steps
-                Self::smooth_mouse_move(&mut enigo, target_dx, target_dy);
-                
-                println!("Smoothly moved mouse by ({}, {})", target_dx, target_dy);
-            },
-            1 if enable_clicks => {
-                // Perform a click
-                if let Err(e) = enigo.button(Button::Left, Direction::Click) {
-                    println!("Failed to click mouse: {:?}", e);
-                } else {
-                    println!("Clicked mouse");
-                }
-            },
-            2 if enable_keyboard => {
-                // Type text sequentially from the configured text
-                if let Some(ref text) = keyboard_text {
-                    if !text.is_empty() {
-                        // Use the pre-locked position
-                        if let Some(ref mut pos) = text_position {
-                            Self::type_text_sequentially(&mut enigo, text, pos);
-                        }
-                    } else {
-                        println!("Keyboard text is empty, typing default character");
-                        // Default behavior: type a random character
-                        let chars = vec![' ', 'a', 'e', 'i', 'o', 'u'];
-                        let random_char = chars[rng.gen_range(0..chars.len())];
-                        if let Err(e) = enigo.text(&random_char.to_string()) {
-                            println!("Failed to type character: {:?}", e);
-                        } else {
-                            println!("Typed character: {}", random_char);
-                        }
+        let action = command.to_string();
+        let action_cancel = self.cancel.child_token();
+        let started = Instant::now();
+        let execute = async {
+            if config.idle_threshold_ms.is_some() {
+                self.services.input.execute_yielding(command, &action_cancel).await
+            } else {
+                self.services.input.execute(command, &action_cancel).await
+            }
+        };
+        tokio::pin!(execute);
+        let result = loop {
+            tokio::select! {
+                result = &mut execute => break result,
+                // In idle-aware mode the user always wins: stop typing or
+                // moving the moment they take over
+                _ = sleep(USER_INPUT_POLL), if config.idle_threshold_ms.is_some() && !action_cancel.is_cancelled() => {
+                    if self.user_input_since(started).await {
+                        println!("User input detected, aborting {}", action);
+                        action_cancel.cancel();
                     }
+                }
+            }
+        };
+        let activity = match result {
+            Ok(progress) => {
+                if let Some(start) = text_start {
+                    // Resume after the last character that was actually typed
+                    *text_position = start + progress.steps;
+                }
+                if progress.aborted {
+                    Activity::Aborted { action, steps: progress.steps }
                 } else {
-                    println!("No keyboard text configured, typing default character");
-                    // Default behavior: type a random character
-                    let chars = vec![' ', 'a', 'e', 'i', 'o', 'u'];
-                    let random_char = chars[rng.gen_range(0..chars.len())];
-                    if let Err(e) = enigo.text(&random_char.to_string()) {
-                        println!("Failed to type character: {:?}", e);
-                    } else {
-                        println!("Typed character: {}", random_char);
-                    }
+                    Activity::Performed { action }
                 }
-            },
-            _ => {
-                // Default to mouse movement
-                let dx = rng.gen_range(-mouse_range..=mouse_range) as i32;
-                let dy = rng.gen_range(-mouse_range..=mouse_range) as i32;
-                
-                Self::smooth_mouse_move(&mut enigo, dx, dy);
-                
-                println!("Moved mouse by ({}, {})", dx, dy);
             }
-        }
+            Err(error) => Activity::Failed { action, error },
+        };
+        self.services.activity.record(activity);
     }
 
-    pub async fn stop(&self) {
-        let mut config = self.config.lock().await;
-        config.enabled = false;
-        println!("Automation engine stopped");
+    /// Tell the task to wind down without waiting for it
+    fn request_stop(&self) {
+        self.state.send_if_modified(|state| match state {
+            EngineState::Running | EngineState::Paused => {
+                *state = EngineState::Stopping;
+                true
+            }
+            _ => false,
+        });
+        self.cancel.cancel();
     }
 
-    #[allow(dead_code)]
-    pub async fn is_running(&self) -> bool {
-        let config = self.config.lock().await;
-        config.enabled
-    }
-    
-    /// Smoothly move the mouse from current position to target position
-    fn smooth_mouse_move(enigo: &mut Enigo, target_dx: i32, target_dy: i32) {
-        use std::thread;
-        use std::time::Duration;
-        use rand::Rng;
-        
-        let mut rng = thread_rng();
-        
-        // Calculate the number of steps based on distance
-        let distance = ((target_dx.pow(2) + target_dy.pow(2)) as f64).sqrt();
-        let steps = (distance / 1.5).max(15.0).min(80.0) as i32; // More steps for smoother movement
-        
-        // Add slight curve to the path for more natural movement
-        let curve_factor = rng.gen_range(-0.3..0.3); // Random curve
-        
-        let mut current_x = 0.0;
-        let mut current_y = 0.0;
+    /// Cancel the engine and wait until its task has exited, so no further
+    /// input is injected once this returns
+    pub async fn stop(&self) {
+        self.request_stop();
         
-        for i in 0..steps {
-            let progress = i as f64 / steps as f64;
-            
-            // Use easing function for natural acceleration/deceleration
-            // This creates an S-curve motion (slow-fast-slow)
-            let eased_progress = if progress < 0.5 {
-                2.0 * progress * progress
-            } else {
-                1.0 - 2.0 * (1.0 - progress) * (1.0 - progress)
-            };
-            
-            // Calculate target position for this step with slight curve
-            let target_x = target_dx as f64 * eased_progress;
-            let target_y = target_dy as f64 * eased_progress;
-            
-            // Add subtle curve to the path
-            let curve_offset = (progress * std::f64::consts::PI).sin() * curve_factor * distance;
-            let perpendicular_x = -target_dy as f64 / distance * curve_offset;
-            let perpendicular_y = target_dx as f64 / distance * curve_offset;
-            
-            // Calculate movement delta for this step
-            let final_x = target_x + perpendicular_x;
-            let final_y = target_y + perpendicular_y;
-            
-            let delta_x = (final_x - current_x) as i32;
-            let delta_y = (final_y - current_y) as i32;
-            
-            // Move the mouse by the delta
-            if delta_x != 0 || delta_y != 0 {
-                let _ = enigo.move_mouse(delta_x, delta_y, Coordinate::Rel);
-                current_x = final_x;
-                current_y = final_y;
+        let task = self.task.lock().unwrap().take();
+        if let Some(task) = task {
+            if let Err(e) = task.await {
+                println!("Automation task ended abnormally: {:?}", e);
             }
-            
-            // Variable delay based on movement phase
-            let base_delay = rng.gen_range(1..3); // Base delay in milliseconds
-            let random_delay = rng.gen_range(0..2); // Small random variation
-            
-            // Slower at start and end for more natural movement
-            let speed_variation = if i < steps / 4 || i > steps * 3 / 4 {
-                2 // Slower at start and end
-            } else {
-                0 // Faster in the middle
-            };
-            
-            thread::sleep(Duration::from_millis((base_delay + random_delay + speed_variation) as u64));
-        }
-        
-        // Final adjustment to ensure we reach exact target
-        let final_x = target_dx - current_x as i32;
-        let final_y = target_dy - current_y as i32;
-        
-        if final_x != 0 || final_y != 0 {
-            let _ = enigo.move_mouse(final_x, final_y, Coordinate::Rel);
         }
+        println!("Automation engine stopped");
     }
-    
-    /// Type text sequentially from the current position
-    fn type_text_sequentially(enigo: &mut Enigo, text: &str, position: &mut usize) {
-        use std::thread;
-        use std::time::Duration;
-        use rand::Rng;
-        
-        let mut rng = thread_rng();
-        let chars: Vec<char> = text.chars().collect();
-        
-        if chars.is_empty() {
-            return;
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::activity::ActivityRecord;
+    use crate::focus::{FixedFocus, FocusedWindow};
+    use crate::idle::FakeIdle;
+    use crate::input::{InputEvent, RecordingBackend};
+    use crate::keep_awake::RecordingKeepAwake;
+
+    /// Services around `backend` that never touch the desktop
+    pub(crate) fn services(backend: &RecordingBackend) -> EngineServices {
+        let recorder = backend.clone();
+        EngineServices {
+            input: Arc::new(InputActor::spawn_with(move || Ok(Box::new(recorder))).unwrap()),
+            keep_awake: Arc::new(RecordingKeepAwake::default()),
+            focus: Arc::new(FixedFocus::default()),
+            idle: Arc::new(FakeIdle::default()),
+            activity: Arc::new(ActivityLog::default()),
         }
-        
-        // Reset position if we've reached the end
-        if *position >= chars.len() {
-            *position = 0;
-            println!("Reached end of text, wrapping around to beginning");
+    }
+
+    /// A config that acts every millisecond with the given actions
+    pub(crate) fn fast_config(actions: &str) -> AutomationConfig {
+        AutomationConfig {
+            enabled: true,
+            min_interval_ms: 1,
+            max_interval_ms: 1,
+            actions: serde_json::from_str(actions).unwrap(),
+            ..AutomationConfig::default()
         }
-        
-        let start_pos = *position;
-        
-        // Determine how many characters to type (5-20 characters or until end of sentence/word)
-        let base_chars = rng.gen_range(5..=20);
-        let mut end_pos = start_pos;
-        let max_chars = base_chars.min(chars.len() - start_pos);
-        
-        // Type at least the minimum, then try to complete the current word
-        for i in start_pos..chars.len().min(start_pos + max_chars) {
-            end_pos = i + 1;
-            
-            // If we've typed at least 5 chars and hit a word boundary, consider stopping
-            if i >= start_pos + 5 {
-                if chars[i] == ' ' || chars[i] == '.' || chars[i] == ',' || 
-                   chars[i] == '!' || chars[i] == '?' || chars[i] == '\n' {
-                    break;
-                }
+    }
+
+    /// Wait until the log holds `count` records, failing after a few seconds
+    pub(crate) async fn wait_for_activity(activity: &ActivityLog, count: usize) -> Vec<ActivityRecord> {
+        for _ in 0..500 {
+            let history = activity.history();
+            if history.len() >= count {
+                return history;
             }
+            sleep(Duration::from_millis(10)).await;
         }
-        
-        // Type the selected portion of text
-        let mut typed = String::new();
-        for i in start_pos..end_pos {
-            let ch = chars[i];
-            typed.push(ch);
-            
-            // Type the character
-            if let Err(e) = enigo.text(&ch.to_string()) {
-                println!("Failed to type character '{}': {:?}", ch, e);
-            }
-            
-            // Natural delay between characters
-            // Simulate human typing speed (40-120 WPM)
-            let base_delay = rng.gen_range(50..150); // milliseconds
-            
-            // Add variation for more natural feeling
-            let variation = match ch {
-                ' ' => rng.gen_range(0..50),  // Slightly longer after spaces
-                '.' | '!' | '?' => rng.gen_range(100..300), // Longer pause after sentences
-                ',' => rng.gen_range(50..150), // Medium pause after commas
-                '\n' => rng.gen_range(200..400), // Pause after line breaks
-                _ => 0,
-            };
-            
-            thread::sleep(Duration::from_millis((base_delay + variation) as u64));
+        panic!("only {} activity records", activity.history().len());
+    }
+
+    fn action_name(record: &ActivityRecord) -> String {
+        match &record.activity {
+            Activity::Performed { action } => action.clone(),
+            other => panic!("unexpected activity {}", other),
         }
-        
-        // Update position for next time
-        *position = end_pos;
-        
-        println!("Typed text: \"{}\" (position: {} -> {})", typed, start_pos, end_pos);
-    }
-    
-    /// Type text naturally with human-like delays between characters (legacy random version)
-    #[allow(dead_code)]
-    fn type_text_naturally(enigo: &mut Enigo, text: &str) {
-        use std::thread;
-        use std::time::Duration;
-        use rand::Rng;
-        
-        let mut rng = thread_rng();
-        let chars: Vec<char> = text.chars().collect();
-        
-        // Pick a random starting position in the text
-        let start_pos = rng.gen_range(0..chars.len());
-        
-        // Determine how many characters to type (1-5 characters or a word)
-        let mut end_pos = start_pos;
-        let max_chars = rng.gen_range(1..=5).min(chars.len() - start_pos);
-        
-        // Try to complete a word if we're in the middle of one
-        for i in start_pos..chars.len().min(start_pos + max_chars) {
-            end_pos = i + 1;
-            // Stop at word boundaries (space, punctuation)
-            if chars[i] == ' ' || chars[i] == '.' || chars[i] == ',' || chars[i] == '!' || chars[i] == '?' {
+    }
+
+    /// Run an engine seeded with `seed` until it has acted `count` times
+    async fn run_seeded(seed: u64, count: usize) -> (Vec<String>, Vec<InputEvent>) {
+        let backend = RecordingBackend::default();
+        let services = services(&backend);
+        let activity = Arc::clone(&services.activity);
+        let config = fast_config(r#"[
+            {"enabled": false, "weight": 5, "action": {"type": "mouse_move", "range": 100}},
+            {"enabled": true, "weight": 1, "action": {"type": "click", "target": {"type": "point", "x": 300, "y": 300}}},
+            {"enabled": true, "weight": 3, "action": {"type": "key_press", "keys": ["Shift"]}}
+        ]"#);
+        let engine = Arc::new(AutomationEngine::with_rng(config, services, StdRng::seed_from_u64(seed)));
+        engine.start(None);
+        let history = wait_for_activity(&activity, count).await;
+        engine.stop().await;
+        let actions = history.iter().take(count).map(action_name).collect();
+        (actions, backend.events())
+    }
+
+    #[tokio::test]
+    async fn seeded_engine_picks_only_enabled_actions() {
+        let (actions, events) = run_seeded(7, 8).await;
+        assert!(actions.iter().all(|action| action == "left click at (300, 300)" || action == "key press Shift"), "{:?}", actions);
+        assert!(actions.iter().any(|action| action.starts_with("left click")));
+        assert!(actions.iter().any(|action| action.starts_with("key press")));
+        // Mouse moves are disabled, so the only relative moves are click glides
+        let clicks = actions.iter().filter(|action| action.starts_with("left click")).count();
+        let landings = events.iter().filter(|event| **event == InputEvent::MoveTo(300, 300)).count();
+        assert!(landings >= clicks);
+    }
+
+    #[test]
+    fn validate_bounds_weights_ranges_and_scroll_amounts() {
+        let config = fast_config(r#"[
+            {"enabled": true, "weight": 1001, "action": {"type": "mouse_move", "range": 50000}},
+            {"enabled": true, "weight": 1000, "action": {"type": "scroll", "amount": 1000000}},
+            {"enabled": false, "weight": 5000, "action": {"type": "scroll", "amount": 5}}
+        ]"#);
+        let fields = match config.validate() {
+            Err(LupinError::InvalidConfig { problems }) => {
+                // The fast test interval is below the minimum, so only look at the actions
+                problems
+                    .into_iter()
+                    .map(|problem| problem.field)
+                    .filter(|field| field.starts_with("actions"))
+                    .collect::<Vec<_>>()
+            }
+            other => panic!("expected invalid config, got {:?}", other),
+        };
+        assert_eq!(fields, ["actions[0].weight", "actions[0].action.range", "actions[1].action.amount"]);
+    }
+
+    #[tokio::test]
+    async fn unvalidated_huge_weights_still_pick_an_action() {
+        let backend = RecordingBackend::default();
+        let services = services(&backend);
+        let activity = Arc::clone(&services.activity);
+        let config = fast_config(r#"[
+            {"enabled": true, "weight": 4294967295, "action": {"type": "key_press", "keys": ["Shift"]}},
+            {"enabled": true, "weight": 4294967295, "action": {"type": "key_press", "keys": ["Control"]}}
+        ]"#);
+        let engine = Arc::new(AutomationEngine::with_rng(config, services, StdRng::seed_from_u64(3)));
+        engine.start(None);
+        let history = wait_for_activity(&activity, 2).await;
+        engine.stop().await;
+        assert!(history.iter().take(2).map(action_name).all(|action| action.starts_with("key press")));
+    }
+
+    #[tokio::test]
+    async fn acts_only_while_an_allowed_app_has_focus() {
+        let focus = Arc::new(FixedFocus::default());
+        focus.set(Some(FocusedWindow { app: "Browser".to_string(), title: None }));
+        let services = EngineServices {
+            focus: Arc::clone(&focus) as Arc<dyn FocusProbe>,
+            ..services(&RecordingBackend::default())
+        };
+        let activity = Arc::clone(&services.activity);
+        let config = AutomationConfig {
+            active_apps: vec!["Editor".to_string()],
+            ..fast_config(r#"[{"enabled": true, "weight": 1, "action": {"type": "key_press", "keys": ["Shift"]}}]"#)
+        };
+        let engine = Arc::new(AutomationEngine::with_rng(config, services, StdRng::seed_from_u64(5)));
+        engine.start(None);
+        let history = wait_for_activity(&activity, 3).await;
+        assert!(history.iter().all(|record| matches!(
+            &record.activity,
+            Activity::Skipped { reason: SkipReason::FocusNotAllowed, app: Some(app) } if app == "Browser"
+        )));
+
+        // Matching ignores case
+        focus.set(Some(FocusedWindow { app: "editor".to_string(), title: None }));
+        for _ in 0..500 {
+            if activity.history().iter().any(|record| matches!(record.activity, Activity::Performed { .. })) {
                 break;
             }
+            sleep(Duration::from_millis(10)).await;
         }
-        
-        // Type the selected portion of text
-        for i in start_pos..end_pos {
-            let ch = chars[i];
-            
-            // Type the character
-            if let Err(e) = enigo.text(&ch.to_string()) {
-                println!("Failed to type character '{}': {:?}", ch, e);
+        engine.stop().await;
+        assert!(activity.history().iter().any(|record| matches!(record.activity, Activity::Performed { .. })));
+    }
+
+    #[tokio::test]
+    async fn keep_awake_lock_is_held_only_while_running() {
+        async fn wait_for_held(keep_awake: &RecordingKeepAwake, held: usize) {
+            for _ in 0..500 {
+                if keep_awake.held() == held {
+                    return;
+                }
+                sleep(Duration::from_millis(10)).await;
             }
-            
-            // Natural delay between characters
-            // Simulate human typing speed (40-120 WPM)
-            let base_delay = rng.gen_range(50..150); // milliseconds
-            
-            // Add variation for more natural feeling
-            let variation = match ch {
-                ' ' => rng.gen_range(0..50),  // Slightly longer after spaces
-                '.' | '!' | '?' => rng.gen_range(100..300), // Longer pause after sentences
-                ',' => rng.gen_range(50..150), // Medium pause after commas
-                _ => 0,
+            panic!("{} locks held, expected {}", keep_awake.held(), held);
+        }
+
+        // Both mode sleeps a long interval between actions, which pausing must cut short
+        let keep_awake_only = AutomationConfig { mode: AutomationMode::KeepAwake, ..fast_config("[]") };
+        let both = AutomationConfig {
+            mode: AutomationMode::Both,
+            min_interval_ms: 600_000,
+            max_interval_ms: 600_000,
+            ..fast_config(r#"[{"enabled": true, "weight": 1, "action": {"type": "key_press", "keys": ["Shift"]}}]"#)
+        };
+        for config in [keep_awake_only, both] {
+            let keep_awake = RecordingKeepAwake::default();
+            let services = EngineServices {
+                keep_awake: Arc::new(keep_awake.clone()),
+                ..services(&RecordingBackend::default())
             };
-            
-            thread::sleep(Duration::from_millis((base_delay + variation) as u64));
+            let engine = Arc::new(AutomationEngine::with_rng(config, services, StdRng::seed_from_u64(1)));
+
+            engine.start(None);
+            wait_for_held(&keep_awake, 1).await;
+            assert!(engine.pause());
+            wait_for_held(&keep_awake, 0).await;
+            assert!(engine.resume());
+            wait_for_held(&keep_awake, 1).await;
+            engine.stop().await;
+            assert_eq!(keep_awake.held(), 0);
         }
-        
-        println!("Typed text portion: '{}'", &text[start_pos..end_pos]);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn pausing_mid_interval_clears_the_next_fire_time() {
+        let config = AutomationConfig {
+            min_interval_ms: 60_000,
+            max_interval_ms: 60_000,
+            ..fast_config(r#"[{"enabled": true, "weight": 1, "action": {"type": "key_press", "keys": ["Shift"]}}]"#)
+        };
+        let engine = Arc::new(AutomationEngine::with_rng(config, services(&RecordingBackend::default()), StdRng::seed_from_u64(1)));
+
+        async fn wait_for_fire_time(engine: &AutomationEngine, scheduled: bool) {
+            for _ in 0..500 {
+                if engine.status().next_fire_at.is_some() == scheduled {
+                    return;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+            panic!("next fire time still {:?}", engine.status().next_fire_at);
+        }
+
+        engine.start(None);
+        wait_for_fire_time(&engine, true).await;
+        assert!(engine.pause());
+        wait_for_fire_time(&engine, false).await;
+        assert!(engine.resume());
+        wait_for_fire_time(&engine, true).await;
+        engine.stop().await;
+    }
+
+    #[tokio::test]
+    async fn same_seed_replays_the_same_input() {
+        let (first_actions, first_events) = run_seeded(11, 6).await;
+        let (second_actions, second_events) = run_seeded(11, 6).await;
+        assert_eq!(first_actions, second_actions);
+        // Either run may have got a little further before it was stopped
+        let common = first_events.len().min(second_events.len());
+        assert!(common > 0);
+        assert_eq!(first_events[..common], second_events[..common]);
+    }
+}
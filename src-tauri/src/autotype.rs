@@ -0,0 +1,216 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::activity::Activity;
+use crate::automation::EngineServices;
+use crate::error::{FieldError, LupinError};
+use crate::failsafe::{Client, ScreenCorner};
+use crate::input::{InputCommand, InputProgress};
+
+/// Where auto-type gets its text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AutoTypeSource {
+    Clipboard,
+    File { path: String },
+}
+
+fn default_countdown_secs() -> u32 {
+    5
+}
+
+fn default_wpm() -> u32 {
+    60
+}
+
+/// Type the whole of `source` into the focused window, for targets such as
+/// VM consoles and remote desktops that refuse a paste
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoTypeRequest {
+    pub source: AutoTypeSource,
+    /// Time to switch to the target window before typing starts
+    #[serde(default = "default_countdown_secs")]
+    pub countdown_secs: u32,
+    #[serde(default = "default_wpm")]
+    pub wpm: u32,
+}
+
+impl AutoTypeRequest {
+    pub fn validate(&self) -> Result<(), LupinError> {
+        let mut problems = Vec::new();
+        if !(10..=400).contains(&self.wpm) {
+            problems.push(FieldError::new("wpm", "Typing speed must be between 10 and 400 WPM"));
+        }
+        if self.countdown_secs > 60 {
+            problems.push(FieldError::new("countdown_secs", "Countdown must be at most 60 seconds"));
+        }
+        if let AutoTypeSource::File { path } = &self.source {
+            if path.trim().is_empty() {
+                problems.push(FieldError::new("source.path", "Choose a file to type"));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(LupinError::InvalidConfig { problems })
+        }
+    }
+
+    /// Read the text now, so a failure is reported before the countdown
+    pub fn read_text(&self) -> Result<String, LupinError> {
+        let text = match &self.source {
+            AutoTypeSource::Clipboard => read_clipboard().map_err(|message| LupinError::AutoType { message })?,
+            AutoTypeSource::File { path } => fs::read_to_string(path)
+                .map_err(|e| LupinError::AutoType { message: format!("Failed to read {}: {}", path, e) })?,
+        };
+        if text.is_empty() {
+            return Err(LupinError::AutoType { message: "There is nothing to type".to_string() });
+        }
+        Ok(text)
+    }
+}
+
+/// Text currently on the clipboard
+pub fn read_clipboard() -> Result<String, String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| format!("Failed to read the clipboard: {}", e))
+}
+
+/// Runs one auto-type at a time through the engine's input layer
+pub struct AutoTyper {
+    services: EngineServices,
+    current: Mutex<Option<CancellationToken>>,
+}
+
+impl AutoTyper {
+    pub fn new(services: EngineServices) -> Self {
+        Self {
+            services,
+            current: Mutex::new(None),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.current.lock().unwrap().is_some()
+    }
+
+    /// Count down, calling `on_countdown` with the seconds left and finally
+    /// with 0, then type `text` at the requested speed. The text itself never
+    /// reaches the activity log, since it is often a password.
+    pub fn start(
+        self: &Arc<Self>,
+        text: String,
+        request: &AutoTypeRequest,
+        corners: Vec<ScreenCorner>,
+        on_countdown: impl Fn(u32) + Send + 'static,
+    ) -> Result<(), LupinError> {
+        let cancel = self.claim()?;
+        let command = InputCommand::type_paced(&text, request.wpm, &mut StdRng::from_entropy());
+        let action = format!("auto-type of {} characters", text.graphemes(true).count());
+        let countdown = request.countdown_secs;
+        let typer = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut counted_down = true;
+            for remaining in (1..=countdown).rev() {
+                on_countdown(remaining);
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(1)) => {}
+                    _ = cancel.cancelled() => {
+                        counted_down = false;
+                        break;
+                    }
+                }
+            }
+            on_countdown(0);
+
+            let activity = if counted_down {
+                typer.type_guarded(command, action, corners, &cancel).await
+            } else {
+                Activity::Aborted { action, steps: 0 }
+            };
+            *typer.current.lock().unwrap() = None;
+            typer.services.activity.record(activity);
+        });
+        Ok(())
+    }
+
+    /// Wait `delay`, then type the command `prepare` returns. Used for
+    /// snippets, which share the auto-type slot: they are refused while
+    /// another auto-type runs, stopped by `abort` and guarded by `corners`.
+    pub fn start_after(
+        self: &Arc<Self>,
+        action: String,
+        delay: Duration,
+        corners: Vec<ScreenCorner>,
+        prepare: impl FnOnce() -> Result<InputCommand, String> + Send + 'static,
+    ) -> Result<(), LupinError> {
+        let cancel = self.claim()?;
+        let typer = Arc::clone(self);
+        tokio::spawn(async move {
+            let waited = tokio::select! {
+                _ = sleep(delay) => true,
+                _ = cancel.cancelled() => false,
+            };
+            let activity = if !waited {
+                Activity::Aborted { action, steps: 0 }
+            } else {
+                match prepare() {
+                    Ok(command) => typer.type_guarded(command, action, corners, &cancel).await,
+                    Err(error) => Activity::Failed { action, error },
+                }
+            };
+            *typer.current.lock().unwrap() = None;
+            typer.services.activity.record(activity);
+        });
+        Ok(())
+    }
+
+    /// Take the auto-type slot, returning the token that aborts whatever uses it
+    fn claim(&self) -> Result<CancellationToken, LupinError> {
+        let mut current = self.current.lock().unwrap();
+        if current.is_some() {
+            return Err(LupinError::AutoTypeRunning);
+        }
+        let cancel = CancellationToken::new();
+        *current = Some(cancel.clone());
+        Ok(cancel)
+    }
+
+    /// Type `command` with the fail-safe armed on `corners`
+    async fn type_guarded(&self, command: InputCommand, action: String, corners: Vec<ScreenCorner>, cancel: &CancellationToken) -> Activity {
+        let fail_safe = self.services.input.fail_safe();
+        let mut trips = fail_safe.subscribe();
+        fail_safe.arm(Client::AutoType, corners);
+        let result = tokio::select! {
+            result = self.services.input.execute(command, cancel) => result,
+            Ok(_) = trips.recv() => {
+                cancel.cancel();
+                Ok(InputProgress { steps: 0, aborted: true })
+            }
+        };
+        fail_safe.disarm(Client::AutoType);
+        match result {
+            Ok(progress) if progress.aborted => Activity::Aborted { action, steps: progress.steps },
+            Ok(_) => Activity::Performed { action },
+            Err(error) => Activity::Failed { action, error },
+        }
+    }
+
+    /// Abort the countdown or the typing. Returns false if nothing was running.
+    pub fn abort(&self) -> bool {
+        match self.current.lock().unwrap().as_ref() {
+            Some(cancel) => {
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
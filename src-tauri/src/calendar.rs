@@ -0,0 +1,768 @@
+//! Keep-awake windows from iCalendar (`.ics`) files, such as the export of a
+//! calendar or the folder a sync tool keeps up to date. Only `VEVENT`s are
+//! read. Recurrences cover the common `RRULE` parts (`FREQ`, `INTERVAL`,
+//! `COUNT`, `UNTIL`, `BYDAY`, `BYMONTHDAY` and `BYMONTH`) along with
+//! `EXDATE`s and moved or cancelled instances (`RECURRENCE-ID`).
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use crate::error::FieldError;
+use crate::scheduler::Zone;
+
+/// Stop expanding a recurrence after this many periods, so a rule without
+/// an end that started decades ago cannot stall the scheduler
+const MAX_PERIODS: u32 = 100_000;
+
+/// A calendar whose matching events run a profile, usually a keep-awake one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarSource {
+    /// An `.ics` file, or a directory whose `.ics` files are all read
+    pub path: String,
+    pub profile: String,
+    /// Only events in one of these categories, unless empty
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Only events whose title matches this regular expression
+    #[serde(default)]
+    pub title_pattern: Option<String>,
+}
+
+/// When one calendar event takes place
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CalendarWindow {
+    pub title: String,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+}
+
+impl CalendarSource {
+    /// Problems with the source itself; the file is only read when windows are needed
+    pub fn problems(&self, field: impl Fn(&str) -> String) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        if self.path.trim().is_empty() {
+            problems.push(FieldError::new(field("path"), "Choose an .ics file or a folder of them"));
+        }
+        if let Some(Err(e)) = self.title_pattern.as_deref().map(Regex::new) {
+            problems.push(FieldError::new(field("title_pattern"), format!("Invalid pattern: {}", e)));
+        }
+        problems
+    }
+
+    /// Matching events overlapping `from..until`, earliest first
+    pub fn windows(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<CalendarWindow>, String> {
+        let pattern = self
+            .title_pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("Invalid pattern: {}", e))?;
+        let categories: Vec<&str> = self
+            .categories
+            .iter()
+            .map(|category| category.trim())
+            .filter(|category| !category.is_empty())
+            .collect();
+        let matches = |event: &Event| {
+            (categories.is_empty()
+                || event.categories.iter().any(|category| {
+                    categories.iter().any(|wanted| wanted.eq_ignore_ascii_case(category))
+                }))
+                && pattern.as_ref().is_none_or(|pattern| pattern.is_match(&event.summary))
+        };
+
+        let mut windows = Vec::new();
+        for text in read_calendars(Path::new(&self.path))? {
+            let events = parse_events(&text);
+            windows.extend(event_windows(events.iter().filter(|event| matches(event)), &events, from, until));
+        }
+        windows.sort_by_key(|window| window.start);
+        Ok(windows)
+    }
+}
+
+/// Instances of `wanted` overlapping `from..until`, with overrides from
+/// `events` applied. An event whose recurrence cannot be expanded is
+/// skipped and reported rather than hiding the rest of the calendar.
+fn event_windows<'a>(
+    wanted: impl Iterator<Item = &'a Event>,
+    events: &[Event],
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<CalendarWindow> {
+    let mut windows = Vec::new();
+    for event in wanted.filter(|event| event.recurrence_id.is_none() && !event.cancelled) {
+        let occurrences = match event.occurrences(until) {
+            Ok(occurrences) => occurrences,
+            Err(e) => {
+                println!("Skipping event {}: {}", event.summary, e);
+                continue;
+            }
+        };
+        // Instances moved or cancelled by an override of this event
+        let overrides: Vec<&Event> = events
+            .iter()
+            .filter(|other| other.uid == event.uid && other.recurrence_id.is_some())
+            .collect();
+        let replaced: HashSet<DateTime<FixedOffset>> = overrides
+            .iter()
+            .filter_map(|other| other.recurrence_id.map(|id| id.resolve()))
+            .chain(event.exdates.iter().map(EventTime::resolve))
+            .collect();
+        for (start, end) in occurrences {
+            if !replaced.contains(&start) && end > from {
+                windows.push(CalendarWindow { title: event.summary.clone(), start, end });
+            }
+        }
+        for other in overrides.into_iter().filter(|other| !other.cancelled) {
+            let (start, end) = other.span();
+            if end > from && start < until {
+                windows.push(CalendarWindow { title: other.summary.clone(), start, end });
+            }
+        }
+    }
+    windows
+}
+
+/// Contents of `path`, or of every `.ics` file directly inside it. In a
+/// folder, a file that cannot be read (say, one a sync tool is still
+/// writing) is skipped and reported rather than hiding the others.
+fn read_calendars(path: &Path) -> Result<Vec<String>, String> {
+    let read = |path: &Path| fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    if !path.is_dir() {
+        return Ok(vec![read(path)?]);
+    }
+    let entries = fs::read_dir(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut texts = Vec::new();
+    for entry in entries.flatten() {
+        let file = entry.path();
+        if file.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("ics")) {
+            match read(&file) {
+                Ok(text) => texts.push(text),
+                Err(e) => println!("Skipping calendar file: {}", e),
+            }
+        }
+    }
+    Ok(texts)
+}
+
+/// A date or date-time value, in the zone it was written in
+#[derive(Debug, Clone, Copy)]
+struct EventTime {
+    local: NaiveDateTime,
+    zone: Zone,
+    all_day: bool,
+}
+
+impl EventTime {
+    /// Read a `DTSTART`-style value. Floating times, dates and time zones
+    /// that are not IANA names (as some Windows exports use) fall back to
+    /// the system zone.
+    fn parse(value: &str, tzid: Option<&str>) -> Result<Self, String> {
+        let invalid = || format!("Invalid date {}", value);
+        let value = value.trim();
+        if value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| invalid())?;
+            return Ok(Self { local: date.and_time(NaiveTime::MIN), zone: Zone::System, all_day: true });
+        }
+        let (value, zone) = match value.strip_suffix('Z') {
+            Some(utc) => (utc, Zone::Named(Tz::UTC)),
+            None => (value, tzid.and_then(|name| name.parse::<Tz>().ok()).map_or(Zone::System, Zone::Named)),
+        };
+        let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        Ok(Self { local, zone, all_day: false })
+    }
+
+    fn resolve(&self) -> DateTime<FixedOffset> {
+        self.zone.resolve(self.local)
+    }
+
+    /// Another wall-clock time in the same zone, e.g. a later occurrence
+    fn at(&self, local: NaiveDateTime) -> Self {
+        Self { local, ..*self }
+    }
+}
+
+/// Start and end of one instance of an event
+type Span = (DateTime<FixedOffset>, DateTime<FixedOffset>);
+
+#[derive(Debug)]
+struct Event {
+    uid: String,
+    summary: String,
+    categories: Vec<String>,
+    start: EventTime,
+    /// Wall-clock length, so a daily 09:00-10:00 meeting keeps its hours across DST
+    length: Duration,
+    rrule: Option<String>,
+    exdates: Vec<EventTime>,
+    /// Set on an override of one instance of a recurring event
+    recurrence_id: Option<EventTime>,
+    cancelled: bool,
+}
+
+impl Event {
+    fn span(&self) -> Span {
+        (self.start.resolve(), self.start.at(self.start.local + self.length).resolve())
+    }
+
+    /// Start and end of every instance starting before `until`
+    fn occurrences(&self, until: DateTime<Utc>) -> Result<Vec<Span>, String> {
+        let Some(rrule) = &self.rrule else {
+            let (start, end) = self.span();
+            return Ok(if start < until { vec![(start, end)] } else { Vec::new() });
+        };
+        let rule = RRule::parse(rrule)?;
+        let mut occurrences = Vec::new();
+        let mut counted = 0;
+        for period in 0..MAX_PERIODS {
+            // Past the last date chrono can represent, so nothing more can match
+            let Some(mut candidates) = rule.candidates(self.start.local, period) else {
+                break;
+            };
+            candidates.sort();
+            for local in candidates.into_iter().filter(|local| *local >= self.start.local) {
+                let start = self.start.at(local);
+                let instant = start.resolve();
+                let past_until = rule.until.is_some_and(|last| {
+                    if last.all_day { local.date() > last.local.date() } else { instant > last.resolve() }
+                });
+                if past_until || rule.count.is_some_and(|count| counted >= count) || instant >= until {
+                    return Ok(occurrences);
+                }
+                counted += 1;
+                occurrences.push((instant, start.at(local + self.length).resolve()));
+            }
+        }
+        Ok(occurrences)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The supported part of an RFC 5545 recurrence rule
+#[derive(Debug)]
+struct RRule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<EventTime>,
+    /// Weekdays, each optionally numbered within the month, e.g. `-1FR`
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+}
+
+impl RRule {
+    fn parse(rule: &str) -> Result<Self, String> {
+        let invalid = |part: &str| format!("Unsupported RRULE part {} in {}", part, rule);
+        let mut parsed = Self {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+        let mut frequency = None;
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part.split_once('=').ok_or_else(|| invalid(part))?;
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(invalid(part)),
+                    })
+                }
+                "INTERVAL" => parsed.interval = value.parse().ok().filter(|&n| n > 0).ok_or_else(|| invalid(part))?,
+                "COUNT" => parsed.count = Some(value.parse().map_err(|_| invalid(part))?),
+                "UNTIL" => parsed.until = Some(EventTime::parse(value, None)?),
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let split = day.len().checked_sub(2).ok_or_else(|| invalid(part))?;
+                        let (ordinal, name) = day.split_at(split);
+                        let weekday = parse_weekday(name).ok_or_else(|| invalid(part))?;
+                        let ordinal = match ordinal {
+                            "" => None,
+                            ordinal => Some(ordinal.trim_start_matches('+').parse::<i32>().map_err(|_| invalid(part))?),
+                        };
+                        parsed.by_day.push((ordinal, weekday));
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in value.split(',') {
+                        let day = day.parse::<i32>().ok().filter(|day| (1..=31).contains(&day.abs()));
+                        parsed.by_month_day.push(day.ok_or_else(|| invalid(part))?);
+                    }
+                }
+                "BYMONTH" => {
+                    for month in value.split(',') {
+                        let month = month.parse().ok().filter(|month| (1..=12).contains(month));
+                        parsed.by_month.push(month.ok_or_else(|| invalid(part))?);
+                    }
+                }
+                // Weeks always start on Monday here, which only matters for
+                // rare BYDAY rules with an INTERVAL above one
+                "WKST" => {}
+                _ => return Err(invalid(part)),
+            }
+        }
+        parsed.frequency = frequency.ok_or_else(|| format!("RRULE {} has no FREQ", rule))?;
+        Ok(parsed)
+    }
+
+    /// Local start times in the `period`th interval after `start`, unsorted,
+    /// or `None` once that interval lies beyond the dates chrono can hold
+    fn candidates(&self, start: NaiveDateTime, period: u32) -> Option<Vec<NaiveDateTime>> {
+        let steps = period.checked_mul(self.interval)?;
+        let time = start.time();
+        let after = |date: NaiveDate, days: u32| date.checked_add_signed(Duration::try_days(days.into())?);
+        let dates: Vec<NaiveDate> = match self.frequency {
+            Frequency::Daily => vec![after(start.date(), steps)?],
+            Frequency::Weekly => {
+                let week = after(start.date().week(Weekday::Mon).first_day(), steps.checked_mul(7)?)?;
+                if self.by_day.is_empty() {
+                    vec![after(week, start.weekday().num_days_from_monday())?]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|(_, weekday)| after(week, weekday.num_days_from_monday()))
+                        .collect::<Option<_>>()?
+                }
+            }
+            Frequency::Monthly => {
+                let months = (start.year() * 12 + start.month0() as i32).checked_add(i32::try_from(steps).ok()?)?;
+                self.days_in_month(months / 12, months as u32 % 12 + 1, start.day())?
+            }
+            Frequency::Yearly => {
+                let year = start.year().checked_add(i32::try_from(steps).ok()?)?;
+                let months = if self.by_month.is_empty() { vec![start.month()] } else { self.by_month.clone() };
+                let mut dates = Vec::new();
+                for month in months {
+                    dates.extend(self.days_in_month(year, month, start.day())?);
+                }
+                dates
+            }
+        };
+        let candidates = dates
+            .into_iter()
+            .filter(|date| self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            .filter(|date| {
+                // For daily and weekly rules these only narrow the set down
+                matches!(self.frequency, Frequency::Monthly | Frequency::Yearly)
+                    || (self.by_month_day.is_empty() || self.by_month_day.contains(&(date.day() as i32)))
+                        && (self.frequency == Frequency::Weekly
+                            || self.by_day.is_empty()
+                            || self.by_day.iter().any(|(_, weekday)| *weekday == date.weekday()))
+            })
+            .map(|date| date.and_time(time))
+            .collect();
+        Some(candidates)
+    }
+
+    /// Days of one month picked by `BYMONTHDAY` and `BYDAY`, or `day` itself
+    /// when neither is given. Months too short for a day simply skip it.
+    /// `None` if the month is beyond the dates chrono can hold.
+    fn days_in_month(&self, year: i32, month: u32, day: u32) -> Option<Vec<NaiveDate>> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let length = (first.checked_add_months(chrono::Months::new(1))? - first).num_days() as i32;
+        let all: Vec<NaiveDate> = (0..length).map(|offset| first + Duration::days(offset.into())).collect();
+
+        let by_month_day = |date: &NaiveDate| {
+            let day = date.day() as i32;
+            self.by_month_day.iter().any(|&wanted| wanted == day || wanted == day - length - 1)
+        };
+        let by_day = |date: &NaiveDate| {
+            self.by_day.iter().any(|&(ordinal, weekday)| {
+                date.weekday() == weekday
+                    && ordinal.is_none_or(|n| {
+                        let from_start = (date.day() as i32 - 1) / 7 + 1;
+                        let from_end = -((length - date.day() as i32) / 7 + 1);
+                        n == from_start || n == from_end
+                    })
+            })
+        };
+        Some(match (self.by_month_day.is_empty(), self.by_day.is_empty()) {
+            (true, true) => NaiveDate::from_ymd_opt(year, month, day).into_iter().collect(),
+            (false, true) => all.into_iter().filter(by_month_day).collect(),
+            (true, false) => all.into_iter().filter(by_day).collect(),
+            (false, false) => all.into_iter().filter(|date| by_month_day(date) && by_day(date)).collect(),
+        })
+    }
+}
+
+/// Two-letter weekday as used by `BYDAY`
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    match name.to_ascii_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// One content line, e.g. `DTSTART;TZID=Europe/Berlin:20240105T090000`
+struct Property<'a> {
+    name: String,
+    params: Vec<(String, &'a str)>,
+    value: &'a str,
+}
+
+impl<'a> Property<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        // The value starts at the first colon outside a quoted parameter
+        let mut quoted = false;
+        let colon = line.char_indices().find(|&(_, ch)| {
+            if ch == '"' {
+                quoted = !quoted;
+            }
+            ch == ':' && !quoted
+        })?.0;
+        let mut parts = line[..colon].split(';');
+        let name = parts.next()?.to_ascii_uppercase();
+        let params = parts
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key.to_ascii_uppercase(), value.trim_matches('"')))
+            .collect();
+        Some(Self { name, params, value: &line[colon + 1..] })
+    }
+
+    fn param(&self, key: &str) -> Option<&'a str> {
+        self.params.iter().find(|(name, _)| name == key).map(|(_, value)| *value)
+    }
+
+    fn time(&self) -> Result<EventTime, String> {
+        EventTime::parse(self.value, self.param("TZID"))
+    }
+
+    /// Comma-separated values with escapes removed
+    fn list(&self) -> Vec<String> {
+        let mut items = vec![String::new()];
+        let mut chars = self.value.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => match chars.next() {
+                    Some('n' | 'N') => items.last_mut().unwrap().push('\n'),
+                    Some(escaped) => items.last_mut().unwrap().push(escaped),
+                    None => {}
+                },
+                ',' => items.push(String::new()),
+                ch => items.last_mut().unwrap().push(ch),
+            }
+        }
+        items
+    }
+
+    fn text(&self) -> String {
+        self.list().join(",")
+    }
+}
+
+/// Join continuation lines, which start with a space or tab, onto the line before
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(previous)) => previous.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Parse an ISO 8601 duration such as `PT1H30M` or `P1D`
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration {}", value);
+    let body = value.trim().trim_start_matches('+').strip_prefix('P').ok_or_else(invalid)?;
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for ch in body.chars() {
+        match ch {
+            'T' => in_time = true,
+            '0'..='9' => number.push(ch),
+            unit => {
+                let n: i64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                total += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return Err(invalid()),
+                };
+            }
+        }
+    }
+    Ok(total)
+}
+
+/// Every `VEVENT` in `text`. Nested components such as `VALARM` are left
+/// out, and an event that cannot be read is skipped and reported.
+fn parse_events(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Vec<String>> = None;
+    // Components opened inside the current event and not yet closed
+    let mut nested = 0;
+    for line in unfold(text) {
+        let trimmed = line.trim_end();
+        let Some(lines) = current.as_mut() else {
+            if trimmed.eq_ignore_ascii_case("BEGIN:VEVENT") {
+                current = Some(Vec::new());
+                nested = 0;
+            }
+            continue;
+        };
+        let upper = trimmed.to_ascii_uppercase();
+        if upper.starts_with("BEGIN:") {
+            nested += 1;
+        } else if upper.starts_with("END:") && nested > 0 {
+            nested -= 1;
+        } else if upper == "END:VEVENT" {
+            match parse_event(lines) {
+                Ok(event) => events.push(event),
+                Err(e) => println!("Skipping event: {}", e),
+            }
+            current = None;
+        } else if nested == 0 {
+            lines.push(line);
+        }
+    }
+    events
+}
+
+fn parse_event(lines: &[String]) -> Result<Event, String> {
+    let mut uid = String::new();
+    let mut summary = String::new();
+    let mut categories = Vec::new();
+    let mut start = None;
+    let mut end = None;
+    let mut duration = None;
+    let mut rrule = None;
+    let mut exdates = Vec::new();
+    let mut recurrence_id = None;
+    let mut cancelled = false;
+    for property in lines.iter().filter_map(|line| Property::parse(line)) {
+        match property.name.as_str() {
+            "UID" => uid = property.value.to_string(),
+            "SUMMARY" => summary = property.text(),
+            "CATEGORIES" => categories.extend(property.list().into_iter().map(|category| category.trim().to_string())),
+            "DTSTART" => start = Some(property.time()?),
+            "DTEND" => end = Some(property.time()?),
+            "DURATION" => duration = Some(parse_duration(property.value)?),
+            "RRULE" => rrule = Some(property.value.to_string()),
+            "EXDATE" => {
+                for value in property.value.split(',') {
+                    exdates.push(EventTime::parse(value, property.param("TZID"))?);
+                }
+            }
+            "RECURRENCE-ID" => recurrence_id = Some(property.time()?),
+            "STATUS" => cancelled = property.value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+    let start = start.ok_or_else(|| format!("Event {} has no DTSTART", summary))?;
+    let length = match (end, duration) {
+        // Measured in wall-clock time in the start's zone, even if the end
+        // was written in another one
+        (Some(end), _) => start.zone.local(end.resolve().with_timezone(&Utc)) - start.local,
+        (None, Some(duration)) => duration,
+        (None, None) if start.all_day => Duration::days(1),
+        (None, None) => Duration::zero(),
+    };
+    Ok(Event { uid, summary, categories, start, length, rrule, exdates, recurrence_id, cancelled })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").unwrap().and_utc()
+    }
+
+    /// Title and UTC start of every window in `events` during January 2024
+    fn windows(events: &str) -> Vec<(String, DateTime<Utc>)> {
+        let text = format!("BEGIN:VCALENDAR\r\n{}END:VCALENDAR\r\n", events.replace('\n', "\r\n"));
+        let events = parse_events(&text);
+        let mut windows = event_windows(events.iter(), &events, utc("20240101T000000"), utc("20240201T000000"));
+        windows.sort_by_key(|window| window.start);
+        windows.into_iter().map(|window| (window.title, window.start.with_timezone(&Utc))).collect()
+    }
+
+    fn starts(windows: &[(String, DateTime<Utc>)]) -> Vec<DateTime<Utc>> {
+        windows.iter().map(|(_, start)| *start).collect()
+    }
+
+    #[test]
+    fn unreadable_files_in_a_folder_are_skipped() {
+        let folder = std::env::temp_dir().join(format!("lupin-calendar-test-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("work.ics"), "BEGIN:VCALENDAR\nEND:VCALENDAR\n").unwrap();
+        // Not UTF-8, as from a half-written file or an odd export
+        fs::write(folder.join("broken.ics"), [0x42, 0x45, 0xff, 0xfe]).unwrap();
+        fs::write(folder.join("notes.txt"), "not a calendar").unwrap();
+
+        let texts = read_calendars(&folder);
+        fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(texts.unwrap(), ["BEGIN:VCALENDAR\nEND:VCALENDAR\n"]);
+    }
+
+    #[test]
+    fn rrules_stop_at_count_and_until_and_follow_byday() {
+        let weekly = windows(
+            "BEGIN:VEVENT
+UID:weekly
+SUMMARY:Standup
+DTSTART:20240101T090000Z
+DTEND:20240101T091500Z
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=3
+END:VEVENT
+",
+        );
+        assert_eq!(starts(&weekly), [utc("20240101T090000"), utc("20240103T090000"), utc("20240108T090000")]);
+
+        let daily = windows(
+            "BEGIN:VEVENT
+UID:daily
+SUMMARY:Focus
+DTSTART:20240101T130000Z
+DURATION:PT1H
+RRULE:FREQ=DAILY;UNTIL=20240103T130000Z
+END:VEVENT
+",
+        );
+        assert_eq!(starts(&daily), [utc("20240101T130000"), utc("20240102T130000"), utc("20240103T130000")]);
+    }
+
+    #[test]
+    fn rules_that_never_match_stop_at_the_end_of_time() {
+        for rule in ["FREQ=WEEKLY;INTERVAL=200;BYMONTH=2;BYMONTHDAY=30", "FREQ=YEARLY;INTERVAL=5000;BYMONTH=2;BYMONTHDAY=30"] {
+            let found = windows(&format!(
+                "BEGIN:VEVENT
+UID:never
+SUMMARY:Never
+DTSTART:20240101T090000Z
+RRULE:{}
+END:VEVENT
+",
+                rule
+            ));
+            assert!(found.is_empty(), "{}: {:?}", rule, found);
+        }
+    }
+
+    #[test]
+    fn rrule_parse_checks_month_and_day_ranges() {
+        assert!(RRule::parse("FREQ=YEARLY;BYMONTH=12;BYMONTHDAY=-31,31").is_ok());
+        for rule in ["FREQ=YEARLY;BYMONTH=0", "FREQ=YEARLY;BYMONTH=13", "FREQ=MONTHLY;BYMONTHDAY=0", "FREQ=MONTHLY;BYMONTHDAY=-32"] {
+            let error = RRule::parse(rule).unwrap_err();
+            assert!(error.starts_with("Unsupported RRULE part BY"), "{}: {}", rule, error);
+        }
+    }
+
+    #[test]
+    fn exdates_and_overrides_replace_single_instances() {
+        let found = windows(
+            "BEGIN:VEVENT
+UID:standup
+SUMMARY:Standup
+DTSTART:20240101T090000Z
+DTEND:20240101T091500Z
+RRULE:FREQ=DAILY;COUNT=4
+EXDATE:20240102T090000Z
+END:VEVENT
+BEGIN:VEVENT
+UID:standup
+SUMMARY:Late standup
+RECURRENCE-ID:20240103T090000Z
+DTSTART:20240103T140000Z
+DTEND:20240103T141500Z
+END:VEVENT
+BEGIN:VEVENT
+UID:standup
+SUMMARY:Standup
+RECURRENCE-ID:20240104T090000Z
+DTSTART:20240104T090000Z
+DTEND:20240104T091500Z
+STATUS:CANCELLED
+END:VEVENT
+",
+        );
+        assert_eq!(
+            found,
+            [
+                ("Standup".to_string(), utc("20240101T090000")),
+                ("Late standup".to_string(), utc("20240103T140000")),
+            ]
+        );
+    }
+
+    #[test]
+    fn alarms_inside_an_event_are_ignored() {
+        let text = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:review
+SUMMARY:Review
+DTSTART:20240105T100000Z
+DTEND:20240105T110000Z
+BEGIN:VALARM
+ACTION:DISPLAY
+SUMMARY:Reminder
+TRIGGER:-PT15M
+DURATION:PT5M
+END:VALARM
+CATEGORIES:Work
+END:VEVENT
+END:VCALENDAR
+";
+        let events = parse_events(text);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Review");
+        assert_eq!(events[0].categories, ["Work"]);
+        assert_eq!(events[0].length, Duration::hours(1));
+    }
+
+    #[test]
+    fn unreadable_events_are_skipped_without_hiding_the_rest() {
+        let found = windows(
+            "BEGIN:VEVENT
+UID:first-monday
+SUMMARY:Planning
+DTSTART:20240101T090000Z
+RRULE:FREQ=MONTHLY;BYDAY=MO;BYSETPOS=1
+END:VEVENT
+BEGIN:VEVENT
+UID:undated
+SUMMARY:Someday
+END:VEVENT
+BEGIN:VEVENT
+UID:demo
+SUMMARY:Demo
+DTSTART:20240110T150000Z
+DTEND:20240110T160000Z
+END:VEVENT
+",
+        );
+        assert_eq!(found, [("Demo".to_string(), utc("20240110T150000"))]);
+    }
+}
@@ -0,0 +1,483 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::actions::{Action, WeightedAction, MAX_MOVE_RANGE};
+use crate::automation::AutomationConfig;
+use crate::error::{FieldError, LupinError};
+use crate::hotkeys::HotkeyConfig;
+use crate::scheduler::{Schedule, ScheduleRule};
+use crate::snippets::Snippet;
+
+/// Upgrades a config document by one schema version
+type Migration = fn(Value) -> Result<Value, LupinError>;
+
+/// Schema migrations, indexed by the version they upgrade from minus one,
+/// so `MIGRATIONS[0]` turns a v1 document into v2
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2, migrate_v2_to_v3];
+
+/// Version written to new config files
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+/// Name of the profile created on first run and by migrations
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// A named automation setup, e.g. keep-awake only or a data entry macro
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub automation: AutomationConfig,
+}
+
+/// Profile names in menu order, as shown by the UI and the tray
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileList {
+    pub active: String,
+    pub names: Vec<String>,
+}
+
+/// Everything Lupin persists, as stored in `config.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredConfig {
+    pub schema_version: u32,
+    pub active_profile: String,
+    /// Shared by all profiles
+    #[serde(default)]
+    pub hotkeys: HotkeyConfig,
+    /// Shared by all profiles, typed by their own hotkeys
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<Snippet>,
+    /// Starts and stops profiles at set times
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// Never empty; kept in the order the user created them
+    pub profiles: Vec<Profile>,
+}
+
+impl Default for StoredConfig {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            active_profile: DEFAULT_PROFILE.to_string(),
+            hotkeys: HotkeyConfig::default(),
+            snippets: Vec::new(),
+            schedule: Schedule::default(),
+            profiles: vec![Profile {
+                name: DEFAULT_PROFILE.to_string(),
+                automation: AutomationConfig::default(),
+            }],
+        }
+    }
+}
+
+impl StoredConfig {
+    /// Bring a document of any known schema version up to date.
+    /// Documents without a version are treated as v1.
+    pub fn migrate(mut document: Value) -> Result<Self, LupinError> {
+        let version = document
+            .get("schema_version")
+            .and_then(Value::as_u64)
+            .map_or(1, |version| u32::try_from(version).unwrap_or(u32::MAX));
+        if version == 0 || version > SCHEMA_VERSION {
+            return Err(LupinError::UnsupportedSchema {
+                version,
+                supported: SCHEMA_VERSION,
+            });
+        }
+
+        for migration in &MIGRATIONS[version as usize - 1..] {
+            document = migration(document)?;
+        }
+        document["schema_version"] = json!(SCHEMA_VERSION);
+
+        let mut config: Self = serde_json::from_value(document)
+            .map_err(|e| LupinError::config(format!("Invalid config: {}", e)))?;
+        if config.profiles.is_empty() {
+            config.profiles = Self::default().profiles;
+        }
+        if config.profile(&config.active_profile).is_none() {
+            config.active_profile = config.profiles[0].name.clone();
+        }
+        Ok(config)
+    }
+
+    pub fn profile_list(&self) -> ProfileList {
+        ProfileList {
+            active: self.active_profile.clone(),
+            names: self.profiles.iter().map(|profile| profile.name.clone()).collect(),
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn profile_mut(&mut self, name: &str) -> Result<&mut Profile, LupinError> {
+        self.profiles
+            .iter_mut()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| LupinError::ProfileNotFound { name: name.to_string() })
+    }
+
+    pub fn active(&self) -> &Profile {
+        self.profile(&self.active_profile).unwrap_or(&self.profiles[0])
+    }
+
+    pub fn active_mut(&mut self) -> &mut Profile {
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == self.active_profile)
+            .unwrap_or(0);
+        &mut self.profiles[index]
+    }
+
+    /// Check that `name` can be used for a new or renamed profile
+    fn check_new_name(&self, name: &str) -> Result<(), LupinError> {
+        if name.trim().is_empty() {
+            return Err(LupinError::EmptyProfileName);
+        }
+        if self.profile(name).is_some() {
+            return Err(LupinError::ProfileExists { name: name.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Add a profile with default settings
+    pub fn create_profile(&mut self, name: &str) -> Result<(), LupinError> {
+        self.check_new_name(name)?;
+        self.profiles.push(Profile {
+            name: name.to_string(),
+            automation: AutomationConfig::default(),
+        });
+        Ok(())
+    }
+
+    pub fn rename_profile(&mut self, name: &str, new_name: &str) -> Result<(), LupinError> {
+        self.check_new_name(new_name)?;
+        self.profile_mut(name)?.name = new_name.to_string();
+        if self.active_profile == name {
+            self.active_profile = new_name.to_string();
+        }
+        // Keep schedule rules and calendars pointing at the renamed profile
+        let rules = self.schedule.rules.iter_mut().map(|rule| {
+            let (ScheduleRule::Window { profile, .. } | ScheduleRule::Cron { profile, .. }) = rule;
+            profile
+        });
+        let calendars = self.schedule.calendars.iter_mut().map(|calendar| &mut calendar.profile);
+        for profile in rules.chain(calendars).filter(|profile| *profile == name) {
+            *profile = new_name.to_string();
+        }
+        Ok(())
+    }
+
+    /// Add a copy of `source` named `new_name`, right after the original
+    pub fn duplicate_profile(&mut self, source: &str, new_name: &str) -> Result<(), LupinError> {
+        self.check_new_name(new_name)?;
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == source)
+            .ok_or_else(|| LupinError::ProfileNotFound { name: source.to_string() })?;
+        let copy = Profile {
+            name: new_name.to_string(),
+            automation: self.profiles[index].automation.clone(),
+        };
+        self.profiles.insert(index + 1, copy);
+        Ok(())
+    }
+
+    /// Remove a profile. The last remaining profile cannot be deleted, nor can
+    /// one the schedule still runs; deleting the active one makes the first
+    /// remaining profile active.
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), LupinError> {
+        if self.profiles.len() == 1 {
+            return Err(LupinError::LastProfile);
+        }
+        self.profile_mut(name)?;
+
+        let rules = self.schedule.rules.iter().enumerate().filter(|(_, rule)| rule.profile() == name);
+        let calendars = self.schedule.calendars.iter().enumerate().filter(|(_, calendar)| calendar.profile == name);
+        let problems: Vec<FieldError> = rules
+            .map(|(index, _)| {
+                FieldError::new(
+                    format!("schedule.rules[{}].profile", index),
+                    format!("Schedule rule {} runs {}; change or remove it first", index + 1, name),
+                )
+            })
+            .chain(calendars.map(|(index, calendar)| {
+                FieldError::new(
+                    format!("schedule.calendars[{}].profile", index),
+                    format!("Calendar {} runs {}; change or remove it first", calendar.path, name),
+                )
+            }))
+            .collect();
+        if !problems.is_empty() {
+            return Err(LupinError::InvalidConfig { problems });
+        }
+
+        self.profiles.retain(|profile| profile.name != name);
+        if self.active_profile == name {
+            self.active_profile = self.profiles[0].name.clone();
+        }
+        Ok(())
+    }
+
+    pub fn set_active_profile(&mut self, name: &str) -> Result<(), LupinError> {
+        self.profile_mut(name)?;
+        self.active_profile = name.to_string();
+        Ok(())
+    }
+}
+
+/// v1 is the flat config the frontend used to keep in localStorage, with
+/// fixed click/keyboard toggles. v2 nests it under `automation` and replaces
+/// the toggles with the weighted action list.
+fn migrate_v1_to_v2(legacy: Value) -> Result<Value, LupinError> {
+    let defaults = AutomationConfig::default();
+    let flag = |key: &str| legacy.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let range = legacy
+        .get("mouse_movement_range")
+        .and_then(Value::as_i64)
+        .map_or(300, |range| i32::try_from(range.clamp(0, MAX_MOVE_RANGE.into())).unwrap_or(MAX_MOVE_RANGE));
+    let text = legacy
+        .get("keyboard_text")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let actions: Vec<WeightedAction> = Action::registry()
+        .into_iter()
+        .map(|mut entry| {
+            match entry.action {
+                Action::MouseMove(ref mut action) => action.range = range,
+                Action::Click(_) => entry.enabled = flag("enable_clicks"),
+                Action::Text(ref mut action) => {
+                    entry.enabled = flag("enable_keyboard");
+                    action.text = text.clone();
+                }
+                _ => {}
+            }
+            entry
+        })
+        .collect();
+
+    Ok(json!({
+        "schema_version": 2,
+        "automation": {
+            "enabled": flag("enabled"),
+            "min_interval_ms": legacy.get("min_interval_ms").cloned().unwrap_or(json!(defaults.min_interval_ms)),
+            "max_interval_ms": legacy.get("max_interval_ms").cloned().unwrap_or(json!(defaults.max_interval_ms)),
+            "actions": actions,
+            "active_apps": legacy.get("active_apps").cloned().unwrap_or(json!([])),
+        },
+    }))
+}
+
+/// v3 turns the single automation config into the "Default" profile
+fn migrate_v2_to_v3(mut document: Value) -> Result<Value, LupinError> {
+    let automation = document
+        .get_mut("automation")
+        .map(Value::take)
+        .ok_or_else(|| LupinError::config("Config is missing the automation section"))?;
+
+    Ok(json!({
+        "schema_version": 3,
+        "active_profile": DEFAULT_PROFILE,
+        "profiles": [{ "name": DEFAULT_PROFILE, "automation": automation }],
+    }))
+}
+
+/// Owns the config file in the app config directory. The tray, the UI and
+/// any other entry point read and write settings through this store.
+pub struct ConfigStore {
+    path: PathBuf,
+    config: Mutex<StoredConfig>,
+}
+
+impl ConfigStore {
+    /// Load the config at `path`, falling back to defaults when it is missing
+    /// or unreadable. A broken file is set aside rather than overwritten.
+    pub fn open(path: PathBuf) -> Self {
+        let config = match Self::load(&path) {
+            Ok(Some(config)) => config,
+            Ok(None) => StoredConfig::default(),
+            Err(e) => {
+                println!("Failed to load config from {}: {}", path.display(), e);
+                let backup = path.with_extension("toml.broken");
+                if let Err(e) = fs::rename(&path, &backup) {
+                    println!("Failed to move broken config aside: {}", e);
+                }
+                StoredConfig::default()
+            }
+        };
+
+        Self {
+            path,
+            config: Mutex::new(config),
+        }
+    }
+
+    fn load(path: &Path) -> Result<Option<StoredConfig>, LupinError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(LupinError::config(e.to_string())),
+        };
+        let document: Value = toml::from_str(&text).map_err(|e| LupinError::config(e.to_string()))?;
+        StoredConfig::migrate(document).map(Some)
+    }
+
+    pub fn get(&self) -> StoredConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Apply `change` and write the result to disk. Nothing is saved if `change` fails.
+    pub fn update<F>(&self, change: F) -> Result<StoredConfig, LupinError>
+    where
+        F: FnOnce(&mut StoredConfig) -> Result<(), LupinError>,
+    {
+        let mut config = self.config.lock().unwrap();
+        let mut updated = config.clone();
+        change(&mut updated)?;
+        self.write(&updated)?;
+        *config = updated.clone();
+        Ok(updated)
+    }
+
+    fn write(&self, config: &StoredConfig) -> Result<(), LupinError> {
+        let text = toml::to_string_pretty(config)
+            .map_err(|e| LupinError::config(format!("Failed to serialize config: {}", e)))?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| LupinError::config(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+
+        // Write next to the target and rename so a crash never leaves half a file
+        let temp = self.path.with_extension("toml.tmp");
+        fs::write(&temp, text)
+            .map_err(|e| LupinError::config(format!("Failed to write {}: {}", temp.display(), e)))?;
+        fs::rename(&temp, &self.path)
+            .map_err(|e| LupinError::config(format!("Failed to replace {}: {}", self.path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::CalendarSource;
+
+    fn move_range(config: &StoredConfig) -> i32 {
+        config.active().automation.actions.iter().find_map(|entry| match &entry.action {
+            Action::MouseMove(action) => Some(action.range),
+            _ => None,
+        }).unwrap()
+    }
+
+    fn is_enabled(config: &StoredConfig, wanted: fn(&Action) -> bool) -> bool {
+        config.active().automation.actions.iter().any(|entry| entry.enabled && wanted(&entry.action))
+    }
+
+    #[test]
+    fn legacy_local_storage_config_upgrades_to_the_current_schema() {
+        // As the frontend saved it under `lupin-automation-config`, without a version
+        let legacy = json!({
+            "enabled": true,
+            "min_interval_ms": 30000,
+            "max_interval_ms": 90000,
+            "mouse_movement_range": 300,
+            "enable_clicks": true,
+            "enable_keyboard": false,
+            "keyboard_text": "Reviewing the specification",
+            "active_apps": ["Code"],
+        });
+        let config = StoredConfig::migrate(legacy).unwrap();
+        assert_eq!(config.schema_version, SCHEMA_VERSION);
+        assert_eq!(config.active_profile, DEFAULT_PROFILE);
+        assert_eq!(config.profiles.len(), 1);
+
+        let automation = &config.active().automation;
+        assert!(automation.enabled);
+        assert_eq!((automation.min_interval_ms, automation.max_interval_ms), (30000, 90000));
+        assert_eq!(automation.active_apps, ["Code"]);
+        assert_eq!(move_range(&config), 300);
+        assert!(is_enabled(&config, |action| matches!(action, Action::Click(_))));
+        assert!(!is_enabled(&config, |action| matches!(action, Action::Text(_))));
+        let text = automation.actions.iter().find_map(|entry| match &entry.action {
+            Action::Text(action) => action.text.clone(),
+            _ => None,
+        });
+        assert_eq!(text.as_deref(), Some("Reviewing the specification"));
+    }
+
+    #[test]
+    fn legacy_move_ranges_are_kept_within_bounds() {
+        let range = |value: Value| move_range(&StoredConfig::migrate(json!({ "mouse_movement_range": value })).unwrap());
+        assert_eq!(range(json!(120)), 120);
+        assert_eq!(range(json!(null)), 300);
+        assert_eq!(range(json!(-5)), 0);
+        assert_eq!(range(json!(1_i64 << 40)), MAX_MOVE_RANGE);
+    }
+
+    #[test]
+    fn v2_config_becomes_the_default_profile() {
+        let mut automation = serde_json::to_value(AutomationConfig::default()).unwrap();
+        automation["min_interval_ms"] = json!(45000);
+        let config = StoredConfig::migrate(json!({ "schema_version": 2, "automation": automation })).unwrap();
+        assert_eq!(config.schema_version, SCHEMA_VERSION);
+        assert_eq!(config.profile_list().names, [DEFAULT_PROFILE]);
+        assert_eq!(config.active().automation.min_interval_ms, 45000);
+
+        let missing = StoredConfig::migrate(json!({ "schema_version": 2 }));
+        assert!(matches!(missing, Err(LupinError::Config { .. })), "{:?}", missing);
+    }
+
+    #[test]
+    fn unknown_schema_versions_are_refused() {
+        for version in [0, u64::from(SCHEMA_VERSION) + 1, u64::from(u32::MAX) + 2] {
+            let refused = StoredConfig::migrate(json!({ "schema_version": version }));
+            assert!(
+                matches!(refused, Err(LupinError::UnsupportedSchema { supported: SCHEMA_VERSION, .. })),
+                "version {} gave {:?}",
+                version,
+                refused
+            );
+        }
+    }
+
+    #[test]
+    fn profiles_the_schedule_runs_cannot_be_deleted() {
+        let mut stored = StoredConfig::default();
+        stored.create_profile("Meetings").unwrap();
+        stored.schedule.rules.push(ScheduleRule::Cron {
+            profile: DEFAULT_PROFILE.to_string(),
+            expression: "0 9 * * 1-5".to_string(),
+            duration_minutes: 60,
+        });
+        stored.schedule.rules.push(ScheduleRule::Cron {
+            profile: "Meetings".to_string(),
+            expression: "0 14 * * *".to_string(),
+            duration_minutes: 30,
+        });
+        stored.schedule.calendars.push(CalendarSource {
+            path: "work.ics".to_string(),
+            profile: "Meetings".to_string(),
+            categories: Vec::new(),
+            title_pattern: None,
+        });
+
+        let problems = match stored.delete_profile("Meetings") {
+            Err(LupinError::InvalidConfig { problems }) => problems,
+            other => panic!("expected the schedule to block the delete, got {:?}", other),
+        };
+        let fields: Vec<_> = problems.iter().map(|problem| problem.field.as_str()).collect();
+        assert_eq!(fields, ["schedule.rules[1].profile", "schedule.calendars[0].profile"]);
+        assert!(stored.profile_mut("Meetings").is_ok());
+
+        stored.schedule.rules.pop();
+        stored.schedule.calendars.clear();
+        stored.delete_profile("Meetings").unwrap();
+        assert_eq!(stored.profile_list().names, [DEFAULT_PROFILE]);
+    }
+}
+
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a display layout is trusted before it is listed again
+const LAYOUT_REFRESH: Duration = Duration::from_secs(5);
+
+/// A screen area in desktop coordinates, the same space the cursor moves in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    /// Whether the area covers no pixels at all
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Leftmost and rightmost column inside the area
+    pub fn columns(&self) -> (i32, i32) {
+        (self.x, self.x + self.width - 1)
+    }
+
+    /// Top and bottom row inside the area
+    pub fn rows(&self) -> (i32, i32) {
+        (self.y, self.y + self.height - 1)
+    }
+}
+
+/// Where mouse moves may take the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MoveBounds {
+    /// The monitor the cursor is on when the move starts
+    #[default]
+    CurrentMonitor,
+    /// A fixed area, which may span several monitors
+    Region(Rect),
+}
+
+/// The last display layout listed, so the fail-safe can look at it on every
+/// poll without asking the OS each time
+#[derive(Default)]
+pub struct DisplayCache {
+    layout: Mutex<Option<(Instant, Vec<Rect>)>>,
+}
+
+impl DisplayCache {
+    /// The cached layout, or a new one from `list` once it has gone stale.
+    /// Failures are not cached.
+    pub fn get(&self, list: impl FnOnce() -> Result<Vec<Rect>, String>) -> Result<Vec<Rect>, String> {
+        let mut layout = self.layout.lock().unwrap();
+        if let Some((listed_at, displays)) = layout.as_ref() {
+            if listed_at.elapsed() < LAYOUT_REFRESH {
+                return Ok(displays.clone());
+            }
+        }
+        let displays = list()?;
+        *layout = Some((Instant::now(), displays.clone()));
+        Ok(displays)
+    }
+}
+
+// Every monitor of the desktop, primary first where the OS says which it is
+#[cfg(target_os = "linux")]
+pub use linux::displays as platform_displays;
+#[cfg(target_os = "macos")]
+pub use macos::displays as platform_displays;
+#[cfg(target_os = "windows")]
+pub use windows::displays as platform_displays;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub fn platform_displays() -> Result<Vec<Rect>, String> {
+    Err("Listing displays is not supported on this platform".to_string())
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::Rect;
+    use x11rb::connection::Connection as _;
+    use x11rb::protocol::randr::ConnectionExt as _;
+
+    /// Monitors as RandR sees them. Fails without an X server, e.g. on a
+    /// pure Wayland session.
+    pub fn displays() -> Result<Vec<Rect>, String> {
+        let (conn, screen) = x11rb::connect(None).map_err(|e| e.to_string())?;
+        let root = conn.setup().roots[screen].root;
+        let reply = conn
+            .randr_get_monitors(root, true)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        let mut displays: Vec<_> = reply
+            .monitors
+            .iter()
+            .map(|monitor| (monitor.primary, Rect {
+                x: monitor.x.into(),
+                y: monitor.y.into(),
+                width: monitor.width.into(),
+                height: monitor.height.into(),
+            }))
+            .collect();
+        displays.sort_by_key(|(primary, _)| !primary);
+        Ok(displays.into_iter().map(|(_, rect)| rect).collect())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::Rect;
+    use cocoa::appkit::NSScreen;
+    use cocoa::base::nil;
+    use cocoa::foundation::{NSArray, NSAutoreleasePool};
+
+    /// Screen frames from `NSScreen`, in points like the cursor
+    pub fn displays() -> Result<Vec<Rect>, String> {
+        let frames: Vec<_> = unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let screens = NSScreen::screens(nil);
+            let frames = (0..screens.count())
+                .map(|i| NSScreen::frame(screens.objectAtIndex(i)))
+                .collect();
+            pool.drain();
+            frames
+        };
+
+        // Cocoa counts up from the bottom of the primary screen, the cursor
+        // counts down from its top
+        let primary_height = frames.first().ok_or("No screens found")?.size.height;
+        Ok(frames
+            .iter()
+            .map(|frame| Rect {
+                x: frame.origin.x as i32,
+                y: (primary_height - frame.origin.y - frame.size.height) as i32,
+                width: frame.size.width as i32,
+                height: frame.size.height as i32,
+            })
+            .collect())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use super::Rect;
+    use windows_sys::Win32::Foundation::{BOOL, LPARAM, RECT, TRUE};
+    use windows_sys::Win32::Graphics::Gdi::{
+        EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO, MONITORINFOF_PRIMARY,
+    };
+
+    unsafe extern "system" fn collect(monitor: HMONITOR, _dc: HDC, _clip: *mut RECT, data: LPARAM) -> BOOL {
+        let displays = &mut *(data as *mut Vec<(bool, Rect)>);
+        let mut info: MONITORINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+        if GetMonitorInfoW(monitor, &mut info) != 0 {
+            let area = info.rcMonitor;
+            displays.push((info.dwFlags & MONITORINFOF_PRIMARY != 0, Rect {
+                x: area.left,
+                y: area.top,
+                width: area.right - area.left,
+                height: area.bottom - area.top,
+            }));
+        }
+        TRUE
+    }
+
+    pub fn displays() -> Result<Vec<Rect>, String> {
+        let mut displays: Vec<(bool, Rect)> = Vec::new();
+        let listed = unsafe {
+            EnumDisplayMonitors(
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                Some(collect),
+                &mut displays as *mut _ as LPARAM,
+            )
+        };
+        if listed == 0 {
+            return Err("EnumDisplayMonitors failed".to_string());
+        }
+        displays.sort_by_key(|(primary, _)| !primary);
+        Ok(displays.into_iter().map(|(_, rect)| rect).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn layouts_are_listed_once_until_they_go_stale() {
+        let cache = DisplayCache::default();
+        let listed = Cell::new(0);
+        let list = || {
+            listed.set(listed.get() + 1);
+            Ok(vec![Rect { x: 0, y: 0, width: 1920, height: 1080 }])
+        };
+        for _ in 0..20 {
+            assert_eq!(cache.get(list).unwrap().len(), 1);
+        }
+        assert_eq!(listed.get(), 1);
+
+        cache.layout.lock().unwrap().as_mut().unwrap().0 -= LAYOUT_REFRESH;
+        cache.get(list).unwrap();
+        assert_eq!(listed.get(), 2);
+    }
+
+    #[test]
+    fn failed_listings_are_retried() {
+        let cache = DisplayCache::default();
+        assert!(cache.get(|| Err("no X server".to_string())).is_err());
+        assert_eq!(cache.get(|| Ok(Vec::new())), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,96 @@
+use serde::Serialize;
+use std::fmt;
+use crate::macros::MacroProblem;
+
+/// One problem with a config value, e.g. `actions[2].range`
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Error returned by every Tauri command.
+///
+/// Serialized with a `kind` tag so the frontend can match on it, e.g.
+/// `{ "kind": "invalid_config", "problems": [...] }`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LupinError {
+    /// The config failed validation; lists every problem found
+    InvalidConfig { problems: Vec<FieldError> },
+    NotRunning,
+    NotPaused,
+    ProfileNotFound { name: String },
+    ProfileExists { name: String },
+    EmptyProfileName,
+    LastProfile,
+    UnsupportedSchema { version: u32, supported: u32 },
+    /// Reading, parsing or writing the config file failed
+    Config { message: String },
+    Launch { message: String },
+    MacroNotFound { name: String },
+    /// The macro file failed to parse or check; lists every problem found
+    InvalidMacro { name: String, problems: Vec<MacroProblem> },
+    MacroRunning,
+    /// Macros, auto-type and the engine would fight over the input, so only
+    /// one runs at a time
+    AutomationRunning,
+    AutoType { message: String },
+    AutoTypeRunning,
+}
+
+impl LupinError {
+    pub fn config(message: impl Into<String>) -> Self {
+        LupinError::Config { message: message.into() }
+    }
+}
+
+impl fmt::Display for LupinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LupinError::InvalidConfig { problems } => {
+                write!(f, "Invalid config:")?;
+                for problem in problems {
+                    write!(f, " {}: {};", problem.field, problem.message)?;
+                }
+                Ok(())
+            }
+            LupinError::NotRunning => write!(f, "Automation is not running"),
+            LupinError::NotPaused => write!(f, "Automation is not paused"),
+            LupinError::ProfileNotFound { name } => write!(f, "Profile not found: {}", name),
+            LupinError::ProfileExists { name } => write!(f, "A profile named {} already exists", name),
+            LupinError::EmptyProfileName => write!(f, "Profile name must not be empty"),
+            LupinError::LastProfile => write!(f, "Cannot delete the only profile"),
+            LupinError::UnsupportedSchema { version, supported } => write!(
+                f,
+                "Unsupported config schema version {} (this build supports up to {})",
+                version, supported
+            ),
+            LupinError::Config { message } => write!(f, "{}", message),
+            LupinError::Launch { message } => write!(f, "Failed to launch app: {}", message),
+            LupinError::MacroNotFound { name } => write!(f, "Macro not found: {}", name),
+            LupinError::InvalidMacro { name, problems } => {
+                write!(f, "Invalid macro {}:", name)?;
+                for problem in problems {
+                    write!(f, " line {}: {};", problem.line, problem.message)?;
+                }
+                Ok(())
+            }
+            LupinError::MacroRunning => write!(f, "A macro is already running"),
+            LupinError::AutomationRunning => write!(f, "Stop automation before running a macro or auto-type"),
+            LupinError::AutoType { message } => write!(f, "{}", message),
+            LupinError::AutoTypeRunning => write!(f, "Auto-type is already running"),
+        }
+    }
+}
+
+impl std::error::Error for LupinError {}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use crate::displays::Rect;
+use crate::input::InputBackend;
+
+/// How close to the corner, in pixels, the cursor has to be
+const CORNER_MARGIN: i32 = 2;
+
+/// How often the cursor is checked
+pub const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ScreenCorner {
+    /// Whether `cursor` is in this corner of the display it is on, with no
+    /// other display past either edge. Corners where monitors meet are
+    /// left out, since the cursor only passes through them.
+    pub fn contains(&self, (x, y): (i32, i32), displays: &[Rect]) -> bool {
+        let Some(display) = displays.iter().find(|display| display.contains((x, y))) else {
+            return false;
+        };
+        let (left, right) = (display.x, display.x + display.width - 1);
+        let (top, bottom) = (display.y, display.y + display.height - 1);
+        let (in_column, beyond_x) = match self {
+            ScreenCorner::TopLeft | ScreenCorner::BottomLeft => (x < left + CORNER_MARGIN, left - 1),
+            ScreenCorner::TopRight | ScreenCorner::BottomRight => (x > right - CORNER_MARGIN, right + 1),
+        };
+        let (in_row, beyond_y) = match self {
+            ScreenCorner::TopLeft | ScreenCorner::TopRight => (y < top + CORNER_MARGIN, top - 1),
+            ScreenCorner::BottomLeft | ScreenCorner::BottomRight => (y > bottom - CORNER_MARGIN, bottom + 1),
+        };
+        let open = |point| !displays.iter().any(|display| display.contains(point));
+        in_column && in_row && open((beyond_x, y)) && open((x, beyond_y))
+    }
+}
+
+impl fmt::Display for ScreenCorner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenCorner::TopLeft => write!(f, "top-left"),
+            ScreenCorner::TopRight => write!(f, "top-right"),
+            ScreenCorner::BottomLeft => write!(f, "bottom-left"),
+            ScreenCorner::BottomRight => write!(f, "bottom-right"),
+        }
+    }
+}
+
+/// Corners the engine gets by default, as in pyautogui
+pub fn default_corners() -> Vec<ScreenCorner> {
+    vec![ScreenCorner::TopLeft]
+}
+
+/// What armed the fail-safe. Each keeps its own corners, so one finishing
+/// never disarms another that is still running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Client {
+    Engine,
+    Macro,
+    AutoType,
+}
+
+/// Stops automation when the user slams the cursor into a screen corner.
+/// The input thread polls the cursor through its backend, both between the
+/// steps of a command and while it waits for the next one, and aborts the
+/// command it is running when the fail-safe trips.
+///
+/// Our own mouse moves can trip it too, which errs on the safe side.
+pub struct FailSafe {
+    armed: Mutex<Vec<(Client, Vec<ScreenCorner>)>>,
+    last_check: Mutex<Option<Instant>>,
+    trips: broadcast::Sender<ScreenCorner>,
+}
+
+impl Default for FailSafe {
+    fn default() -> Self {
+        Self {
+            armed: Mutex::new(Vec::new()),
+            last_check: Mutex::new(None),
+            trips: broadcast::channel(4).0,
+        }
+    }
+}
+
+impl FailSafe {
+    /// Watch `corners` for `client`, replacing what it armed before; an
+    /// empty list disarms it
+    pub fn arm(&self, client: Client, corners: Vec<ScreenCorner>) {
+        let mut armed = self.armed.lock().unwrap();
+        armed.retain(|(armed_by, _)| *armed_by != client);
+        if !corners.is_empty() {
+            armed.push((client, corners));
+        }
+    }
+
+    pub fn disarm(&self, client: Client) {
+        self.armed.lock().unwrap().retain(|(armed_by, _)| *armed_by != client);
+    }
+
+    /// Receive every trip from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ScreenCorner> {
+        self.trips.subscribe()
+    }
+
+    /// Check the cursor if it has not been checked recently. Trips at most
+    /// once per arming and returns the corner it tripped on. Every client
+    /// stops on a trip, so it disarms them all.
+    pub fn check(&self, backend: &dyn InputBackend) -> Option<ScreenCorner> {
+        {
+            let mut last_check = self.last_check.lock().unwrap();
+            if last_check.is_some_and(|at| at.elapsed() < POLL_INTERVAL) {
+                return None;
+            }
+            *last_check = Some(Instant::now());
+        }
+        let mut armed = self.armed.lock().unwrap();
+        if armed.is_empty() {
+            return None;
+        }
+
+        let cursor = backend.location().ok()?;
+        let displays = backend.displays().ok()?;
+        let corner = armed
+            .iter()
+            .flat_map(|(_, corners)| corners.iter().copied())
+            .find(|corner| corner.contains(cursor, &displays))?;
+        armed.clear();
+        drop(armed);
+
+        println!("Fail-safe triggered: cursor in the {} corner", corner);
+        let _ = self.trips.send(corner);
+        Some(corner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::RecordingBackend;
+
+    /// A 1920x1080 monitor with a taller 1080x1920 one to its right, top-aligned
+    const SIDE_BY_SIDE: [Rect; 2] = [
+        Rect { x: 0, y: 0, width: 1920, height: 1080 },
+        Rect { x: 1920, y: 0, width: 1080, height: 1920 },
+    ];
+
+    #[test]
+    fn outer_corners_of_every_display_count() {
+        assert!(ScreenCorner::TopLeft.contains((0, 1), &SIDE_BY_SIDE));
+        assert!(ScreenCorner::BottomLeft.contains((1, 1079), &SIDE_BY_SIDE));
+        assert!(ScreenCorner::TopRight.contains((2999, 0), &SIDE_BY_SIDE));
+        assert!(ScreenCorner::BottomRight.contains((2999, 1919), &SIDE_BY_SIDE));
+        // Below the shorter monitor the taller one's left edge is open
+        assert!(ScreenCorner::BottomLeft.contains((1920, 1919), &SIDE_BY_SIDE));
+    }
+
+    #[test]
+    fn corners_where_displays_meet_do_not_count() {
+        assert!(!ScreenCorner::TopRight.contains((1919, 0), &SIDE_BY_SIDE));
+        assert!(!ScreenCorner::TopLeft.contains((1920, 0), &SIDE_BY_SIDE));
+        assert!(!ScreenCorner::TopLeft.contains((10, 10), &SIDE_BY_SIDE));
+        // Off every display
+        assert!(!ScreenCorner::BottomRight.contains((1919, 1919), &SIDE_BY_SIDE));
+    }
+
+    #[test]
+    fn check_trips_once_per_arming() {
+        let backend = RecordingBackend::default();
+        let fail_safe = FailSafe::default();
+        let mut trips = fail_safe.subscribe();
+        fail_safe.arm(Client::Engine, vec![ScreenCorner::TopLeft, ScreenCorner::BottomRight]);
+        assert_eq!(fail_safe.check(&backend), None);
+
+        backend.set_cursor(1919, 1079);
+        std::thread::sleep(POLL_INTERVAL);
+        assert_eq!(fail_safe.check(&backend), Some(ScreenCorner::BottomRight));
+        assert_eq!(trips.try_recv().unwrap(), ScreenCorner::BottomRight);
+
+        std::thread::sleep(POLL_INTERVAL);
+        assert_eq!(fail_safe.check(&backend), None);
+    }
+}
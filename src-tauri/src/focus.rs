@@ -0,0 +1,238 @@
+use serde::Serialize;
+use std::sync::Arc;
+
+/// The window that currently has keyboard focus
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusedWindow {
+    /// The WM_CLASS class on X11, the executable name on Windows and the
+    /// application name on macOS
+    pub app: String,
+    pub title: Option<String>,
+}
+
+impl FocusedWindow {
+    /// Whether this window belongs to one of `apps`, ignoring case
+    pub fn matches(&self, apps: &[String]) -> bool {
+        apps.iter().any(|app| app.trim().eq_ignore_ascii_case(&self.app))
+    }
+}
+
+/// Looks up the foreground window so the engine only acts where it is allowed to
+pub trait FocusProbe: Send + Sync {
+    /// `Ok(None)` when no window has focus
+    fn focused_window(&self) -> Result<Option<FocusedWindow>, String>;
+}
+
+/// Probe for the current OS
+pub fn platform_probe() -> Arc<dyn FocusProbe> {
+    #[cfg(target_os = "linux")]
+    let probe = linux::X11FocusProbe::default();
+    #[cfg(target_os = "macos")]
+    let probe = macos::WorkspaceFocusProbe;
+    #[cfg(target_os = "windows")]
+    let probe = windows::ForegroundFocusProbe;
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let probe = FixedFocus::default();
+    Arc::new(probe)
+}
+
+/// Always reports the window it was last given, for tests and platforms
+/// without a probe
+#[cfg(any(test, not(any(target_os = "linux", target_os = "macos", target_os = "windows"))))]
+#[derive(Default)]
+pub struct FixedFocus {
+    window: std::sync::Mutex<Option<FocusedWindow>>,
+}
+
+#[cfg(test)]
+impl FixedFocus {
+    pub fn set(&self, window: Option<FocusedWindow>) {
+        *self.window.lock().unwrap() = window;
+    }
+}
+
+#[cfg(any(test, not(any(target_os = "linux", target_os = "macos", target_os = "windows"))))]
+impl FocusProbe for FixedFocus {
+    fn focused_window(&self) -> Result<Option<FocusedWindow>, String> {
+        Ok(self.window.lock().unwrap().clone())
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod linux {
+    use super::{FocusProbe, FocusedWindow};
+    use std::sync::Mutex;
+    use x11rb::atom_manager;
+    use x11rb::connection::Connection;
+    use x11rb::properties::WmClass;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    atom_manager! {
+        Atoms: AtomsCookie {
+            _NET_ACTIVE_WINDOW,
+            _NET_WM_NAME,
+            UTF8_STRING,
+        }
+    }
+
+    struct Session {
+        connection: RustConnection,
+        root: Window,
+        atoms: Atoms,
+    }
+
+    impl Session {
+        fn connect() -> Result<Self, String> {
+            let (connection, screen) = x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+            let root = connection.setup().roots[screen].root;
+            let atoms = Atoms::new(&connection)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| e.to_string())?;
+            Ok(Self { connection, root, atoms })
+        }
+
+        fn focused_window(&self) -> Result<Option<FocusedWindow>, String> {
+            let active = self
+                .connection
+                .get_property(false, self.root, self.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW, 0, 1)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| e.to_string())?;
+            let window = match active.value32().and_then(|mut values| values.next()) {
+                Some(window) if window != 0 => window,
+                _ => return Ok(None),
+            };
+
+            let class = WmClass::get(&self.connection, window)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| e.to_string())?;
+            let app = class
+                .map(|class| String::from_utf8_lossy(class.class()).into_owned())
+                .unwrap_or_default();
+
+            let title = self
+                .connection
+                .get_property(false, window, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING, 0, 1024)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .ok()
+                .filter(|reply| !reply.value.is_empty())
+                .map(|reply| String::from_utf8_lossy(&reply.value).into_owned());
+
+            Ok(Some(FocusedWindow { app, title }))
+        }
+    }
+
+    /// Reads `_NET_ACTIVE_WINDOW` from the root window and the `WM_CLASS` of
+    /// that window. Under Wayland this only sees XWayland clients, so native
+    /// Wayland windows report no focus.
+    #[derive(Default)]
+    pub struct X11FocusProbe {
+        session: Mutex<Option<Session>>,
+    }
+
+    impl FocusProbe for X11FocusProbe {
+        fn focused_window(&self) -> Result<Option<FocusedWindow>, String> {
+            let mut session = self.session.lock().unwrap();
+            if session.is_none() {
+                *session = Some(Session::connect()?);
+            }
+            let result = session.as_ref().unwrap().focused_window();
+            if result.is_err() {
+                // Reconnect next time, e.g. after the X server restarted
+                *session = None;
+            }
+            result
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub mod macos {
+    use super::{FocusProbe, FocusedWindow};
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::NSAutoreleasePool;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    /// Asks `NSWorkspace` for the frontmost application
+    pub struct WorkspaceFocusProbe;
+
+    impl FocusProbe for WorkspaceFocusProbe {
+        fn focused_window(&self) -> Result<Option<FocusedWindow>, String> {
+            unsafe {
+                let pool = NSAutoreleasePool::new(nil);
+                let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+                let app: id = msg_send![workspace, frontmostApplication];
+                let window = if app == nil {
+                    None
+                } else {
+                    let name: id = msg_send![app, localizedName];
+                    let utf8: *const c_char = msg_send![name, UTF8String];
+                    (!utf8.is_null()).then(|| FocusedWindow {
+                        app: CStr::from_ptr(utf8).to_string_lossy().into_owned(),
+                        title: None,
+                    })
+                };
+                let _: () = msg_send![pool, drain];
+                Ok(window)
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub mod windows {
+    use super::{FocusProbe, FocusedWindow};
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId,
+    };
+
+    /// Names the foreground window after its executable, e.g. `chrome`
+    pub struct ForegroundFocusProbe;
+
+    impl FocusProbe for ForegroundFocusProbe {
+        fn focused_window(&self) -> Result<Option<FocusedWindow>, String> {
+            unsafe {
+                let hwnd = GetForegroundWindow();
+                if hwnd.is_null() {
+                    return Ok(None);
+                }
+
+                let mut pid = 0u32;
+                GetWindowThreadProcessId(hwnd, &mut pid);
+                let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+                if process.is_null() {
+                    return Err(format!("Failed to open process {}", pid));
+                }
+                let mut path = [0u16; 1024];
+                let mut len = path.len() as u32;
+                let ok = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, path.as_mut_ptr(), &mut len);
+                CloseHandle(process);
+                if ok == 0 {
+                    return Err(format!("Failed to query the executable of process {}", pid));
+                }
+                let path = String::from_utf16_lossy(&path[..len as usize]);
+                let app = Path::new(&path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or(path);
+
+                let mut text = [0u16; 512];
+                let text_len = GetWindowTextW(hwnd, text.as_mut_ptr(), text.len() as i32);
+                let title = (text_len > 0).then(|| String::from_utf16_lossy(&text[..text_len as usize]));
+
+                Ok(Some(FocusedWindow { app, title }))
+            }
+        }
+    }
+}
@@ -0,0 +1,766 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use crate::error::{FieldError, LupinError};
+use crate::keyspec::{Chord, KeyName};
+use crate::snippets::Snippet;
+
+/// What a global hotkey does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Start automation with the active profile, or stop it
+    Toggle,
+    /// Pause or resume the running engine
+    Pause,
+    /// Stop at once, aborting whatever is being typed
+    PanicStop,
+    /// Abort a running auto-type
+    AbortType,
+    /// Type the snippet at this index of the snippet list
+    Snippet(usize),
+}
+
+impl HotkeyAction {
+    /// Config field the action is bound by
+    pub fn field(&self) -> String {
+        match self {
+            HotkeyAction::Toggle => "toggle".to_string(),
+            HotkeyAction::Pause => "pause".to_string(),
+            HotkeyAction::PanicStop => "panic_stop".to_string(),
+            HotkeyAction::AbortType => "abort_type".to_string(),
+            HotkeyAction::Snippet(index) => format!("snippets[{}].hotkey", index),
+        }
+    }
+}
+
+/// Accelerators such as `Ctrl+Alt+L`, persisted with the rest of the config.
+/// `None` leaves an action unbound. Unbound actions are left out of the file,
+/// so only a missing `[hotkeys]` table falls back to the defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeyConfig {
+    #[serde(default)]
+    pub toggle: Option<String>,
+    #[serde(default)]
+    pub pause: Option<String>,
+    #[serde(default)]
+    pub panic_stop: Option<String>,
+    #[serde(default)]
+    pub abort_type: Option<String>,
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            toggle: Some("Ctrl+Alt+L".to_string()),
+            pause: Some("Ctrl+Alt+P".to_string()),
+            panic_stop: Some("Ctrl+Alt+End".to_string()),
+            abort_type: Some("Ctrl+Alt+Q".to_string()),
+        }
+    }
+}
+
+impl HotkeyConfig {
+    fn accelerators(&self) -> [(HotkeyAction, Option<&str>); 4] {
+        [
+            (HotkeyAction::Toggle, self.toggle.as_deref()),
+            (HotkeyAction::Pause, self.pause.as_deref()),
+            (HotkeyAction::PanicStop, self.panic_stop.as_deref()),
+            (HotkeyAction::AbortType, self.abort_type.as_deref()),
+        ]
+    }
+
+    /// Parse every bound accelerator, along with the snippets' own, reporting
+    /// all problems at once
+    pub fn bindings(&self, snippets: &[Snippet]) -> Result<Vec<(HotkeyAction, Hotkey)>, LupinError> {
+        let snippet_accelerators = snippets
+            .iter()
+            .enumerate()
+            .map(|(index, snippet)| (HotkeyAction::Snippet(index), snippet.hotkey.as_deref()));
+        let mut bindings: Vec<(HotkeyAction, Hotkey)> = Vec::new();
+        let mut problems = Vec::new();
+        for (action, accelerator) in self.accelerators().into_iter().chain(snippet_accelerators) {
+            let Some(accelerator) = accelerator else { continue };
+            match Hotkey::parse(accelerator) {
+                Ok(hotkey) => {
+                    if let Some((other, _)) = bindings.iter().find(|(_, bound)| *bound == hotkey) {
+                        problems.push(FieldError::new(
+                            action.field(),
+                            format!("{} is already bound to {}", hotkey, other.field()),
+                        ));
+                    } else {
+                        bindings.push((action, hotkey));
+                    }
+                }
+                Err(message) => problems.push(FieldError::new(action.field(), message)),
+            }
+        }
+        if problems.is_empty() {
+            Ok(bindings)
+        } else {
+            Err(LupinError::InvalidConfig { problems })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// The Windows key, or Command on macOS
+    pub super_key: bool,
+}
+
+/// Keys a hotkey can end in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// An ASCII letter (stored lowercase) or digit
+    Char(char),
+    /// F1 to F24
+    F(u8),
+    Escape,
+    Space,
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+}
+
+impl Key {
+    const NAMED: &'static [(&'static str, Key)] = &[
+        ("Escape", Key::Escape),
+        ("Space", Key::Space),
+        ("Enter", Key::Enter),
+        ("Tab", Key::Tab),
+        ("Backspace", Key::Backspace),
+        ("Delete", Key::Delete),
+        ("Insert", Key::Insert),
+        ("Home", Key::Home),
+        ("End", Key::End),
+        ("PageUp", Key::PageUp),
+        ("PageDown", Key::PageDown),
+        ("Up", Key::Up),
+        ("Down", Key::Down),
+        ("Left", Key::Left),
+        ("Right", Key::Right),
+        ("Pause", Key::Pause),
+    ];
+
+    fn from_name(name: KeyName) -> Option<Self> {
+        match name {
+            KeyName::Char(c) => c.is_ascii_alphanumeric().then(|| Key::Char(c.to_ascii_lowercase())),
+            KeyName::Function(n) => Some(Key::F(n)),
+            KeyName::Named(name) => Self::NAMED.iter().find(|(known, _)| *known == name).map(|(_, key)| *key),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            Key::F(n) => write!(f, "F{}", n),
+            key => {
+                let (name, _) = Self::NAMED.iter().find(|(_, named)| named == key).unwrap();
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+/// A parsed accelerator such as `Ctrl+Alt+L`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Hotkey {
+    /// Names are parsed like key actions, see `Chord`. At least one modifier
+    /// is required so a hotkey never swallows a key the user types normally.
+    pub fn parse(accelerator: &str) -> Result<Self, String> {
+        let chord = Chord::parse(accelerator)?;
+
+        let mut modifiers = Modifiers::default();
+        for name in &chord.held {
+            let flag = match name {
+                KeyName::Named("Ctrl") => &mut modifiers.ctrl,
+                KeyName::Named("Alt") => &mut modifiers.alt,
+                KeyName::Named("Shift") => &mut modifiers.shift,
+                KeyName::Named("Super") => &mut modifiers.super_key,
+                _ => return Err(format!("Unknown modifier {} in {}", name, accelerator)),
+            };
+            *flag = true;
+        }
+        if modifiers == Modifiers::default() {
+            return Err(format!("Hotkey {} needs at least one modifier, e.g. Ctrl+Alt+{}", accelerator, chord.key));
+        }
+
+        let key = Key::from_name(chord.key)
+            .ok_or_else(|| format!("{} cannot be used in a hotkey, in {}", chord.key, accelerator))?;
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Modifiers { ctrl, alt, shift, super_key } = self.modifiers;
+        for (held, name) in [(ctrl, "Ctrl"), (alt, "Alt"), (shift, "Shift"), (super_key, "Super")] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// A hotkey that could not be registered, e.g. because another application
+/// already owns it
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyConflict {
+    pub action: HotkeyAction,
+    pub hotkey: String,
+    pub message: String,
+}
+
+impl HotkeyConflict {
+    fn new(action: HotkeyAction, hotkey: &Hotkey, message: impl Into<String>) -> Self {
+        Self {
+            action,
+            hotkey: hotkey.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Hotkeys as configured, along with any that could not be registered
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyStatus {
+    pub config: HotkeyConfig,
+    pub conflicts: Vec<HotkeyConflict>,
+}
+
+/// Called from the backend's own thread whenever a hotkey is pressed
+pub type HotkeyHandler = Arc<dyn Fn(HotkeyAction) + Send + Sync>;
+
+/// Grabs system-wide hotkeys
+pub trait HotkeyBackend: Send + Sync {
+    /// Replace every registered hotkey with `bindings`, returning the ones
+    /// that could not be registered
+    fn register(&self, bindings: &[(HotkeyAction, Hotkey)]) -> Vec<HotkeyConflict>;
+}
+
+/// Backend for the current OS
+pub fn platform_backend(handler: HotkeyHandler) -> Arc<dyn HotkeyBackend> {
+    #[cfg(target_os = "linux")]
+    let backend = linux::X11Hotkeys::new(handler);
+    #[cfg(target_os = "windows")]
+    let backend = windows::RegisterHotKeys::new(handler);
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    let backend = {
+        drop(handler);
+        UnsupportedHotkeys
+    };
+    Arc::new(backend)
+}
+
+/// Reports every binding as a conflict, where no hotkey API is wired up yet
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub struct UnsupportedHotkeys;
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+impl HotkeyBackend for UnsupportedHotkeys {
+    fn register(&self, bindings: &[(HotkeyAction, Hotkey)]) -> Vec<HotkeyConflict> {
+        bindings
+            .iter()
+            .map(|(action, hotkey)| HotkeyConflict::new(*action, hotkey, "Global hotkeys are not supported on this platform"))
+            .collect()
+    }
+}
+
+/// Keeps bindings in memory and fires them on demand, for tests. Hotkeys
+/// passed to `take` behave as if another application owned them.
+#[cfg(test)]
+pub struct RecordingHotkeys {
+    handler: HotkeyHandler,
+    registered: std::sync::Mutex<Vec<(HotkeyAction, Hotkey)>>,
+    taken: std::sync::Mutex<Vec<Hotkey>>,
+}
+
+#[cfg(test)]
+impl RecordingHotkeys {
+    pub fn new(handler: HotkeyHandler) -> Self {
+        Self {
+            handler,
+            registered: std::sync::Mutex::new(Vec::new()),
+            taken: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn take(&self, hotkey: Hotkey) {
+        self.taken.lock().unwrap().push(hotkey);
+    }
+
+    /// Simulate a key press; returns whether a binding matched
+    pub fn press(&self, hotkey: Hotkey) -> bool {
+        let action = self
+            .registered
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, bound)| *bound == hotkey)
+            .map(|(action, _)| *action);
+        if let Some(action) = action {
+            (self.handler)(action);
+        }
+        action.is_some()
+    }
+}
+
+#[cfg(test)]
+impl HotkeyBackend for RecordingHotkeys {
+    fn register(&self, bindings: &[(HotkeyAction, Hotkey)]) -> Vec<HotkeyConflict> {
+        let taken = self.taken.lock().unwrap();
+        let (conflicts, registered): (Vec<_>, Vec<_>) = bindings.iter().partition(|(_, hotkey)| taken.contains(hotkey));
+        *self.registered.lock().unwrap() = registered;
+        conflicts
+            .into_iter()
+            .map(|(action, hotkey)| HotkeyConflict::new(action, &hotkey, "Hotkey is already in use"))
+            .collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod linux {
+    use super::{Hotkey, HotkeyAction, HotkeyBackend, HotkeyConflict, HotkeyHandler, Key};
+    use std::sync::{Arc, Mutex};
+    use std::thread::JoinHandle;
+    use x11rb::connection::Connection;
+    use x11rb::errors::ReplyError;
+    use x11rb::protocol::xproto::{ConnectionExt, GrabMode, Keycode, Keysym, ModMask, Window};
+    use x11rb::protocol::{ErrorKind, Event};
+    use x11rb::rust_connection::RustConnection;
+
+    /// Caps Lock (`Lock`) and Num Lock (usually `Mod2`) in every combination.
+    /// Each grab is repeated with all of them so hotkeys work regardless.
+    const IGNORED_MODIFIERS: [u16; 4] = [0, 0x02, 0x10, 0x12];
+
+    #[derive(Clone, Copy)]
+    struct Grab {
+        keycode: Keycode,
+        modifiers: u16,
+        action: HotkeyAction,
+    }
+
+    struct Session {
+        connection: Arc<RustConnection>,
+        root: Window,
+        grabs: Arc<Mutex<Vec<Grab>>>,
+        events: JoinHandle<()>,
+    }
+
+    impl Session {
+        fn connect(handler: HotkeyHandler) -> Result<Self, String> {
+            let (connection, screen) = x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+            let root = connection.setup().roots[screen].root;
+            let connection = Arc::new(connection);
+            let grabs = Arc::new(Mutex::new(Vec::<Grab>::new()));
+
+            let events = {
+                let connection = Arc::clone(&connection);
+                let grabs = Arc::clone(&grabs);
+                std::thread::Builder::new()
+                    .name("lupin-hotkeys".to_string())
+                    .spawn(move || loop {
+                        match connection.wait_for_event() {
+                            Ok(Event::KeyPress(event)) => {
+                                let state = u16::from(event.state);
+                                let action = grabs.lock().unwrap().iter().find(|grab| {
+                                    grab.keycode == event.detail
+                                        && IGNORED_MODIFIERS.iter().any(|lock| state == grab.modifiers | lock)
+                                }).map(|grab| grab.action);
+                                if let Some(action) = action {
+                                    handler(action);
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                println!("Hotkey listener stopped: {}", e);
+                                break;
+                            }
+                        }
+                    })
+                    .map_err(|e| format!("Failed to spawn hotkey thread: {}", e))?
+            };
+            Ok(Self { connection, root, grabs, events })
+        }
+
+        fn keycode(&self, keysym: Keysym) -> Result<Option<Keycode>, String> {
+            let setup = self.connection.setup();
+            let (min, max) = (setup.min_keycode, setup.max_keycode);
+            let mapping = self
+                .connection
+                .get_keyboard_mapping(min, max - min + 1)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| e.to_string())?;
+            let per_keycode = mapping.keysyms_per_keycode.max(1) as usize;
+            Ok(mapping
+                .keysyms
+                .chunks(per_keycode)
+                .position(|keysyms| keysyms.contains(&keysym))
+                .map(|index| min + index as u8))
+        }
+
+        fn ungrab_all(&self) {
+            for grab in self.grabs.lock().unwrap().drain(..) {
+                for lock in IGNORED_MODIFIERS {
+                    let _ = self.connection.ungrab_key(grab.keycode, self.root, ModMask::from(grab.modifiers | lock));
+                }
+            }
+            let _ = self.connection.flush();
+        }
+
+        /// Grab `hotkey` with every lock-key combination. A `BadAccess` error
+        /// means another client already grabbed it.
+        fn grab(&self, action: HotkeyAction, hotkey: &Hotkey) -> Result<(), String> {
+            let keycode = self
+                .keycode(keysym(hotkey.key))?
+                .ok_or_else(|| format!("{} is not on the current keyboard layout", hotkey.key))?;
+            let grab = Grab {
+                keycode,
+                modifiers: modifier_mask(hotkey),
+                action,
+            };
+            for (index, lock) in IGNORED_MODIFIERS.iter().enumerate() {
+                let result = self
+                    .connection
+                    .grab_key(false, self.root, ModMask::from(grab.modifiers | lock), keycode, GrabMode::ASYNC, GrabMode::ASYNC)
+                    .map_err(ReplyError::from)
+                    .and_then(|cookie| cookie.check());
+                if let Err(e) = result {
+                    for grabbed in &IGNORED_MODIFIERS[..index] {
+                        let _ = self.connection.ungrab_key(keycode, self.root, ModMask::from(grab.modifiers | grabbed));
+                    }
+                    return Err(match e {
+                        ReplyError::X11Error(error) if error.error_kind == ErrorKind::Access => {
+                            "Already grabbed by another application".to_string()
+                        }
+                        e => e.to_string(),
+                    });
+                }
+            }
+            self.grabs.lock().unwrap().push(grab);
+            Ok(())
+        }
+    }
+
+    fn modifier_mask(hotkey: &Hotkey) -> u16 {
+        let mut mask = 0;
+        for (held, bit) in [
+            (hotkey.modifiers.shift, ModMask::SHIFT),
+            (hotkey.modifiers.ctrl, ModMask::CONTROL),
+            (hotkey.modifiers.alt, ModMask::M1),
+            (hotkey.modifiers.super_key, ModMask::M4),
+        ] {
+            if held {
+                mask |= u16::from(bit);
+            }
+        }
+        mask
+    }
+
+    /// X keysym of `key`, from `X11/keysymdef.h`
+    fn keysym(key: Key) -> Keysym {
+        match key {
+            Key::Char(c) => c as Keysym,
+            Key::F(n) => 0xffbe + (n as Keysym - 1),
+            Key::Escape => 0xff1b,
+            Key::Space => 0x0020,
+            Key::Enter => 0xff0d,
+            Key::Tab => 0xff09,
+            Key::Backspace => 0xff08,
+            Key::Delete => 0xffff,
+            Key::Insert => 0xff63,
+            Key::Home => 0xff50,
+            Key::End => 0xff57,
+            Key::PageUp => 0xff55,
+            Key::PageDown => 0xff56,
+            Key::Up => 0xff52,
+            Key::Down => 0xff54,
+            Key::Left => 0xff51,
+            Key::Right => 0xff53,
+            Key::Pause => 0xff13,
+        }
+    }
+
+    /// Grabs keys on the root window with `XGrabKey`. Under Wayland this only
+    /// works while an XWayland window has focus.
+    pub struct X11Hotkeys {
+        handler: HotkeyHandler,
+        session: Mutex<Option<Session>>,
+    }
+
+    impl X11Hotkeys {
+        pub fn new(handler: HotkeyHandler) -> Self {
+            Self {
+                handler,
+                session: Mutex::new(None),
+            }
+        }
+    }
+
+    impl HotkeyBackend for X11Hotkeys {
+        fn register(&self, bindings: &[(HotkeyAction, Hotkey)]) -> Vec<HotkeyConflict> {
+            let mut session = self.session.lock().unwrap();
+            // Reconnect if the listener died, e.g. after the X server restarted
+            if session.as_ref().is_some_and(|session| session.events.is_finished()) {
+                *session = None;
+            }
+            if session.is_none() {
+                match Session::connect(Arc::clone(&self.handler)) {
+                    Ok(connected) => *session = Some(connected),
+                    Err(e) => {
+                        return bindings
+                            .iter()
+                            .map(|(action, hotkey)| HotkeyConflict::new(*action, hotkey, e.clone()))
+                            .collect();
+                    }
+                }
+            }
+            let session = session.as_ref().unwrap();
+
+            session.ungrab_all();
+            bindings
+                .iter()
+                .filter_map(|(action, hotkey)| {
+                    session
+                        .grab(*action, hotkey)
+                        .err()
+                        .map(|e| HotkeyConflict::new(*action, hotkey, e))
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub mod windows {
+    use super::{Hotkey, HotkeyAction, HotkeyBackend, HotkeyConflict, HotkeyHandler, Key};
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_HOTKEY_ALREADY_REGISTERED};
+    use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::*;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetMessageW, PeekMessageW, PostThreadMessageW, MSG, PM_NOREMOVE, WM_APP, WM_HOTKEY,
+    };
+
+    type Request = (Vec<(HotkeyAction, Hotkey)>, mpsc::Sender<Vec<HotkeyConflict>>);
+
+    /// Hotkeys belong to the thread that registered them and arrive in its
+    /// message queue, so one thread registers them all and runs the loop
+    pub struct RegisterHotKeys {
+        thread_id: Option<u32>,
+        requests: Mutex<mpsc::Sender<Request>>,
+    }
+
+    impl RegisterHotKeys {
+        pub fn new(handler: HotkeyHandler) -> Self {
+            let (requests, pending) = mpsc::channel::<Request>();
+            let (started, thread_id) = mpsc::channel();
+            let spawned = std::thread::Builder::new()
+                .name("lupin-hotkeys".to_string())
+                .spawn(move || unsafe {
+                    let mut msg: MSG = std::mem::zeroed();
+                    // Creates the message queue before anyone posts to it
+                    PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_NOREMOVE);
+                    let _ = started.send(GetCurrentThreadId());
+
+                    let mut registered: Vec<HotkeyAction> = Vec::new();
+                    while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+                        match msg.message {
+                            WM_HOTKEY => {
+                                if let Some(action) = registered.get(msg.wParam) {
+                                    handler(*action);
+                                }
+                            }
+                            WM_APP => {
+                                while let Ok((bindings, reply)) = pending.try_recv() {
+                                    for id in 0..registered.len() {
+                                        UnregisterHotKey(std::ptr::null_mut(), id as i32);
+                                    }
+                                    registered.clear();
+                                    let _ = reply.send(register_all(&bindings, &mut registered));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                });
+            let thread_id = match spawned {
+                Ok(_) => thread_id.recv().ok(),
+                Err(e) => {
+                    println!("Failed to spawn hotkey thread: {}", e);
+                    None
+                }
+            };
+            Self {
+                thread_id,
+                requests: Mutex::new(requests),
+            }
+        }
+    }
+
+    /// Register `bindings` in order; ids are indexes into `registered`
+    unsafe fn register_all(bindings: &[(HotkeyAction, Hotkey)], registered: &mut Vec<HotkeyAction>) -> Vec<HotkeyConflict> {
+        let mut conflicts = Vec::new();
+        for (action, hotkey) in bindings {
+            let mut modifiers = MOD_NOREPEAT;
+            for (held, flag) in [
+                (hotkey.modifiers.ctrl, MOD_CONTROL),
+                (hotkey.modifiers.alt, MOD_ALT),
+                (hotkey.modifiers.shift, MOD_SHIFT),
+                (hotkey.modifiers.super_key, MOD_WIN),
+            ] {
+                if held {
+                    modifiers |= flag;
+                }
+            }
+            if RegisterHotKey(std::ptr::null_mut(), registered.len() as i32, modifiers, virtual_key(hotkey.key) as u32) != 0 {
+                registered.push(*action);
+            } else {
+                let message = match GetLastError() {
+                    ERROR_HOTKEY_ALREADY_REGISTERED => "Already registered by another application".to_string(),
+                    code => format!("RegisterHotKey failed with error {}", code),
+                };
+                conflicts.push(HotkeyConflict::new(*action, hotkey, message));
+            }
+        }
+        conflicts
+    }
+
+    fn virtual_key(key: Key) -> VIRTUAL_KEY {
+        match key {
+            Key::Char(c) => c.to_ascii_uppercase() as VIRTUAL_KEY,
+            Key::F(n) => VK_F1 + (n as VIRTUAL_KEY - 1),
+            Key::Escape => VK_ESCAPE,
+            Key::Space => VK_SPACE,
+            Key::Enter => VK_RETURN,
+            Key::Tab => VK_TAB,
+            Key::Backspace => VK_BACK,
+            Key::Delete => VK_DELETE,
+            Key::Insert => VK_INSERT,
+            Key::Home => VK_HOME,
+            Key::End => VK_END,
+            Key::PageUp => VK_PRIOR,
+            Key::PageDown => VK_NEXT,
+            Key::Up => VK_UP,
+            Key::Down => VK_DOWN,
+            Key::Left => VK_LEFT,
+            Key::Right => VK_RIGHT,
+            Key::Pause => VK_PAUSE,
+        }
+    }
+
+    impl HotkeyBackend for RegisterHotKeys {
+        fn register(&self, bindings: &[(HotkeyAction, Hotkey)]) -> Vec<HotkeyConflict> {
+            let unavailable = |message: &str| {
+                bindings
+                    .iter()
+                    .map(|(action, hotkey)| HotkeyConflict::new(*action, hotkey, message))
+                    .collect()
+            };
+            let Some(thread_id) = self.thread_id else {
+                return unavailable("Hotkey thread is not running");
+            };
+            let (reply, conflicts) = mpsc::channel();
+            if self.requests.lock().unwrap().send((bindings.to_vec(), reply)).is_err()
+                || unsafe { PostThreadMessageW(thread_id, WM_APP, 0, 0) } == 0
+            {
+                return unavailable("Hotkey thread is not running");
+            }
+            conflicts.recv().unwrap_or_else(|_| unavailable("Hotkey thread is not running"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn hotkey(accelerator: &str) -> Hotkey {
+        Hotkey::parse(accelerator).unwrap()
+    }
+
+    #[test]
+    fn parse_normalizes_names_and_aliases() {
+        let parsed = hotkey(" control + SHIFT + pgdn ");
+        assert_eq!(parsed.modifiers, Modifiers { ctrl: true, shift: true, ..Modifiers::default() });
+        assert_eq!(parsed.key, Key::PageDown);
+        assert_eq!(parsed.to_string(), "Ctrl+Shift+PageDown");
+        assert_eq!(hotkey("cmd+alt+q").to_string(), "Alt+Super+Q");
+        assert_eq!(hotkey("Ctrl+F12").key, Key::F(12));
+    }
+
+    #[test]
+    fn parse_rejects_bad_accelerators() {
+        for accelerator in ["", "L", "Ctrl+", "Hyper+L", "LCtrl+L", "Ctrl+F25", "Ctrl+Nope", "Ctrl++", "Ctrl+VolumeUp"] {
+            assert!(Hotkey::parse(accelerator).is_err(), "{:?} parsed", accelerator);
+        }
+    }
+
+    #[test]
+    fn bindings_report_duplicates_and_bad_snippet_hotkeys() {
+        let config = HotkeyConfig {
+            pause: Some("ctrl+alt+l".to_string()),
+            ..HotkeyConfig::default()
+        };
+        let snippets = [Snippet {
+            name: "greeting".to_string(),
+            body: "Hi".to_string(),
+            hotkey: Some("Alt".to_string()),
+        }];
+        let problems = match config.bindings(&snippets) {
+            Err(LupinError::InvalidConfig { problems }) => problems,
+            other => panic!("expected invalid config, got {:?}", other),
+        };
+        let fields: Vec<_> = problems.iter().map(|problem| problem.field.as_str()).collect();
+        assert_eq!(fields, ["pause", "snippets[0].hotkey"]);
+        assert_eq!(problems[0].message, "Ctrl+Alt+L is already bound to toggle");
+    }
+
+    #[test]
+    fn taken_hotkeys_conflict_and_the_rest_fire() {
+        let pressed = Arc::new(Mutex::new(Vec::new()));
+        let handler: HotkeyHandler = {
+            let pressed = Arc::clone(&pressed);
+            Arc::new(move |action| pressed.lock().unwrap().push(action))
+        };
+        let backend = RecordingHotkeys::new(handler);
+        backend.take(hotkey("Ctrl+Alt+P"));
+
+        let bindings = HotkeyConfig::default().bindings(&[]).unwrap();
+        let conflicts = backend.register(&bindings);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].action, HotkeyAction::Pause);
+        assert_eq!(conflicts[0].hotkey, "Ctrl+Alt+P");
+
+        assert!(backend.press(hotkey("Ctrl+Alt+L")));
+        assert!(!backend.press(hotkey("Ctrl+Alt+P")));
+        assert!(!backend.press(hotkey("Ctrl+L")));
+        assert_eq!(*pressed.lock().unwrap(), [HotkeyAction::Toggle]);
+    }
+}
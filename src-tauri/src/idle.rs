@@ -0,0 +1,350 @@
+#[cfg(any(target_os = "linux", test))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::keep_awake::BoxFuture;
+
+/// How much later than our own event the OS may register it. Input seen
+/// within this window after an injected event is assumed to be ours.
+const INJECTION_SLACK: Duration = Duration::from_millis(50);
+
+/// Reports how long the machine has gone without keyboard or mouse input
+pub trait IdleProbe: Send + Sync {
+    /// Time since the last input from any source, including our own
+    fn idle_time(&self) -> BoxFuture<'_, Result<Duration, String>>;
+}
+
+/// Probe for the current OS
+pub fn platform_probe() -> Arc<dyn IdleProbe> {
+    #[cfg(target_os = "linux")]
+    let probe = FirstAvailable::new(vec![
+        Arc::new(linux::XScreenSaverIdle::default()),
+        Arc::new(linux::MutterIdle::default()),
+    ]);
+    #[cfg(target_os = "macos")]
+    let probe = macos::EventSourceIdle;
+    #[cfg(target_os = "windows")]
+    let probe = windows::LastInputIdle;
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let probe = FakeIdle::default();
+    Arc::new(probe)
+}
+
+/// Uses the first probe that works, remembering it for later calls. Handy
+/// where the right API depends on the session, e.g. X11 versus Wayland.
+#[cfg(any(target_os = "linux", test))]
+pub struct FirstAvailable {
+    probes: Vec<Arc<dyn IdleProbe>>,
+    preferred: AtomicUsize,
+}
+
+#[cfg(any(target_os = "linux", test))]
+impl FirstAvailable {
+    pub fn new(probes: Vec<Arc<dyn IdleProbe>>) -> Self {
+        Self {
+            probes,
+            preferred: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", test))]
+impl IdleProbe for FirstAvailable {
+    fn idle_time(&self) -> BoxFuture<'_, Result<Duration, String>> {
+        Box::pin(async move {
+            let start = self.preferred.load(Ordering::Relaxed);
+            let mut errors = Vec::new();
+            for offset in 0..self.probes.len() {
+                let index = (start + offset) % self.probes.len();
+                match self.probes[index].idle_time().await {
+                    Ok(idle) => {
+                        self.preferred.store(index, Ordering::Relaxed);
+                        return Ok(idle);
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+            Err(format!("No idle detector available ({})", errors.join("; ")))
+        })
+    }
+}
+
+/// Idle time driven by hand, for tests and platforms without a detector
+#[cfg(any(test, not(any(target_os = "linux", target_os = "macos", target_os = "windows"))))]
+pub struct FakeIdle {
+    last_input: Mutex<Instant>,
+}
+
+#[cfg(any(test, not(any(target_os = "linux", target_os = "macos", target_os = "windows"))))]
+impl Default for FakeIdle {
+    fn default() -> Self {
+        Self {
+            last_input: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl FakeIdle {
+    /// Pretend the user just pressed a key or moved the mouse
+    pub fn touch(&self) {
+        *self.last_input.lock().unwrap() = Instant::now();
+    }
+}
+
+#[cfg(any(test, not(any(target_os = "linux", target_os = "macos", target_os = "windows"))))]
+impl IdleProbe for FakeIdle {
+    fn idle_time(&self) -> BoxFuture<'_, Result<Duration, String>> {
+        Box::pin(async move { Ok(self.last_input.lock().unwrap().elapsed()) })
+    }
+}
+
+/// Tells real user input apart from the events we inject. OS idle timers
+/// are reset by our own input too, so input only counts as the user's when
+/// it is newer than the last event we sent.
+pub struct IdleTracker {
+    probe: Arc<dyn IdleProbe>,
+    user_active_at: Mutex<Option<Instant>>,
+}
+
+impl IdleTracker {
+    pub fn new(probe: Arc<dyn IdleProbe>) -> Self {
+        Self {
+            probe,
+            user_active_at: Mutex::new(None),
+        }
+    }
+
+    /// How long the user has been inactive. `last_injection` is when we last
+    /// sent an event, if ever, and `user_seen` when the user was last caught
+    /// interrupting our input, which the OS timer may no longer show.
+    pub async fn user_idle(&self, last_injection: Option<Instant>, user_seen: Option<Instant>) -> Result<Duration, String> {
+        let idle = self.probe.idle_time().await?;
+        let now = Instant::now();
+        let last_input = now.checked_sub(idle).unwrap_or(now);
+
+        let mut user_active_at = self.user_active_at.lock().unwrap();
+        let by_user = match last_injection {
+            Some(injected) => last_input > injected + INJECTION_SLACK,
+            None => true,
+        };
+        if by_user && user_active_at.is_none_or(|at| last_input > at) {
+            *user_active_at = Some(last_input);
+        }
+        if let Some(seen) = user_seen.filter(|seen| user_active_at.is_none_or(|at| *seen > at)) {
+            *user_active_at = Some(seen);
+        }
+        Ok(user_active_at.map_or(idle, |at| now.duration_since(at)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod linux {
+    use super::IdleProbe;
+    use crate::keep_awake::BoxFuture;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use x11rb::connection::Connection as _;
+    use x11rb::protocol::screensaver::ConnectionExt as _;
+    use x11rb::protocol::xproto::Window;
+    use x11rb::rust_connection::RustConnection;
+    use zbus::Connection;
+
+    /// Asks the X server through the MIT-SCREEN-SAVER extension
+    #[derive(Default)]
+    pub struct XScreenSaverIdle {
+        session: Mutex<Option<(RustConnection, Window)>>,
+    }
+
+    impl XScreenSaverIdle {
+        fn query(&self) -> Result<Duration, String> {
+            let mut session = self.session.lock().unwrap();
+            if session.is_none() {
+                let (connection, screen) = x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+                let root = connection.setup().roots[screen].root;
+                *session = Some((connection, root));
+            }
+            let (connection, root) = session.as_ref().unwrap();
+            let reply = connection
+                .screensaver_query_info(*root)
+                .map_err(|e| e.to_string())
+                .and_then(|cookie| cookie.reply().map_err(|e| e.to_string()));
+            match reply {
+                Ok(info) => Ok(Duration::from_millis(info.ms_since_user_input as u64)),
+                Err(e) => {
+                    // Reconnect next time, e.g. after the X server restarted
+                    *session = None;
+                    Err(format!("XScreenSaver query failed: {}", e))
+                }
+            }
+        }
+    }
+
+    impl IdleProbe for XScreenSaverIdle {
+        fn idle_time(&self) -> BoxFuture<'_, Result<Duration, String>> {
+            Box::pin(async move { self.query() })
+        }
+    }
+
+    /// Asks GNOME Shell through `org.gnome.Mutter.IdleMonitor`, which also
+    /// works under Wayland
+    #[derive(Default)]
+    pub struct MutterIdle {
+        connection: tokio::sync::Mutex<Option<Connection>>,
+    }
+
+    impl IdleProbe for MutterIdle {
+        fn idle_time(&self) -> BoxFuture<'_, Result<Duration, String>> {
+            Box::pin(async move {
+                let mut connection = self.connection.lock().await;
+                if connection.is_none() {
+                    *connection = Some(Connection::session().await.map_err(|e| e.to_string())?);
+                }
+                let reply = connection
+                    .as_ref()
+                    .unwrap()
+                    .call_method(
+                        Some("org.gnome.Mutter.IdleMonitor"),
+                        "/org/gnome/Mutter/IdleMonitor/Core",
+                        Some("org.gnome.Mutter.IdleMonitor"),
+                        "GetIdletime",
+                        &(),
+                    )
+                    .await
+                    .map_err(|e| format!("Mutter IdleMonitor unavailable: {}", e))?;
+                let idle_ms: u64 = reply.body().deserialize().map_err(|e| e.to_string())?;
+                Ok(Duration::from_millis(idle_ms))
+            })
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub mod macos {
+    use super::IdleProbe;
+    use crate::keep_awake::BoxFuture;
+    use std::time::Duration;
+
+    const COMBINED_SESSION_STATE: i32 = 0;
+    const ANY_INPUT_EVENT_TYPE: u32 = !0;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+    }
+
+    /// Reads the time since the last input event from Core Graphics
+    pub struct EventSourceIdle;
+
+    impl IdleProbe for EventSourceIdle {
+        fn idle_time(&self) -> BoxFuture<'_, Result<Duration, String>> {
+            Box::pin(async move {
+                let seconds = unsafe { CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT_TYPE) };
+                Ok(Duration::from_secs_f64(seconds.max(0.0)))
+            })
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub mod windows {
+    use super::IdleProbe;
+    use crate::keep_awake::BoxFuture;
+    use std::time::Duration;
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    /// Compares `GetLastInputInfo` with the current tick count
+    pub struct LastInputIdle;
+
+    impl IdleProbe for LastInputIdle {
+        fn idle_time(&self) -> BoxFuture<'_, Result<Duration, String>> {
+            Box::pin(async move {
+                let mut info = LASTINPUTINFO {
+                    cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+                    dwTime: 0,
+                };
+                if unsafe { GetLastInputInfo(&mut info) } == 0 {
+                    return Err("GetLastInputInfo failed".to_string());
+                }
+                // Both are 32-bit tick counts, so wrapping subtraction survives rollover
+                let idle_ms = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+                Ok(Duration::from_millis(idle_ms as u64))
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{InputActor, InputCommand, MousePath, PathStep, RecordingBackend};
+    use tokio::time::sleep;
+    use tokio_util::sync::CancellationToken;
+
+    #[tokio::test]
+    async fn own_injection_does_not_count_as_user_input() {
+        let probe = Arc::new(FakeIdle::default());
+        let tracker = IdleTracker::new(Arc::clone(&probe) as Arc<dyn IdleProbe>);
+        tracker.user_idle(None, None).await.unwrap();
+        sleep(Duration::from_millis(200)).await;
+
+        // The OS registers our event a moment after we sent it
+        let injected = Instant::now();
+        probe.touch();
+        assert!(tracker.user_idle(Some(injected), None).await.unwrap() >= Duration::from_millis(200));
+
+        // Input well after our last event is the user's
+        sleep(INJECTION_SLACK * 2).await;
+        probe.touch();
+        assert!(tracker.user_idle(Some(injected), None).await.unwrap() < INJECTION_SLACK);
+    }
+
+    #[tokio::test]
+    async fn taking_the_mouse_mid_path_aborts_it_and_counts_as_user_input() {
+        let backend = RecordingBackend::default();
+        let user = backend.clone();
+        let input = InputActor::spawn_with(move || Ok(Box::new(backend))).unwrap();
+        let tracker = IdleTracker::new(Arc::new(FakeIdle::default()));
+        tracker.user_idle(None, None).await.unwrap();
+
+        let path = MousePath {
+            steps: vec![PathStep { dx: 1, dy: 0, delay_ms: 20 }; 50],
+            bounds: None,
+            restore: false,
+        };
+        let takeover = async {
+            sleep(Duration::from_millis(200)).await;
+            user.set_cursor(10, 10);
+        };
+        let cancel = CancellationToken::new();
+        let (progress, _) = tokio::join!(input.execute_yielding(InputCommand::MovePath(path), &cancel), takeover);
+        let progress = progress.unwrap();
+        assert!(progress.aborted);
+        assert!(progress.steps < 50, "{} steps", progress.steps);
+
+        // Our later steps hide the user's input from the OS timer, but not from the tracker
+        let idle = tracker.user_idle(input.last_event(), input.user_moved_at()).await.unwrap();
+        assert!(idle < Duration::from_millis(100), "{:?}", idle);
+    }
+
+    #[tokio::test]
+    async fn plain_execute_keeps_moving_when_the_user_does() {
+        let backend = RecordingBackend::default();
+        let user = backend.clone();
+        let input = InputActor::spawn_with(move || Ok(Box::new(backend))).unwrap();
+        let path = MousePath {
+            steps: vec![PathStep { dx: 1, dy: 0, delay_ms: 10 }; 10],
+            bounds: None,
+            restore: false,
+        };
+        let takeover = async {
+            sleep(Duration::from_millis(30)).await;
+            user.set_cursor(10, 10);
+        };
+        let cancel = CancellationToken::new();
+        let (progress, _) = tokio::join!(input.execute(InputCommand::MovePath(path), &cancel), takeover);
+        assert!(!progress.unwrap().aborted);
+        assert_eq!(input.user_moved_at(), None);
+    }
+}
@@ -0,0 +1,1107 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+use unicode_segmentation::UnicodeSegmentation;
+use enigo::{Enigo, Settings, Axis, Coordinate, Direction, Button, Key, Keyboard, Mouse};
+use crate::displays::{self, DisplayCache, MoveBounds, Rect};
+use crate::failsafe::{self, FailSafe};
+use crate::keyspec::KeySpec;
+
+/// Low-level input primitives. Everything the engine injects goes through one
+/// of these, so swapping the backend swaps the whole input layer.
+pub trait InputBackend {
+    fn move_by(&mut self, dx: i32, dy: i32) -> Result<(), String>;
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), String>;
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String>;
+    fn scroll(&mut self, length: i32, axis: Axis) -> Result<(), String>;
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String>;
+    fn text(&mut self, text: &str) -> Result<(), String>;
+    /// Cursor position in pixels
+    fn location(&self) -> Result<(i32, i32), String>;
+    /// Size of the main display in pixels
+    fn main_display(&self) -> Result<(i32, i32), String>;
+    /// Every monitor, primary first
+    fn displays(&self) -> Result<Vec<Rect>, String>;
+}
+
+/// The real backend, injecting input into the desktop session
+pub struct EnigoBackend {
+    enigo: Enigo,
+    displays: DisplayCache,
+}
+
+impl EnigoBackend {
+    pub fn new() -> Result<Self, String> {
+        let enigo = Enigo::new(&Settings::default())
+            .map_err(|e| format!("Failed to initialize Enigo: {:?}", e))?;
+        Ok(Self { enigo, displays: DisplayCache::default() })
+    }
+}
+
+impl InputBackend for EnigoBackend {
+    fn move_by(&mut self, dx: i32, dy: i32) -> Result<(), String> {
+        self.enigo.move_mouse(dx, dy, Coordinate::Rel).map_err(|e| format!("{:?}", e))
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), String> {
+        self.enigo.move_mouse(x, y, Coordinate::Abs).map_err(|e| format!("{:?}", e))
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        self.enigo.button(button, direction).map_err(|e| format!("{:?}", e))
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> Result<(), String> {
+        self.enigo.scroll(length, axis).map_err(|e| format!("{:?}", e))
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        self.enigo.key(key, direction).map_err(|e| format!("{:?}", e))
+    }
+
+    fn text(&mut self, text: &str) -> Result<(), String> {
+        self.enigo.text(text).map_err(|e| format!("{:?}", e))
+    }
+
+    fn location(&self) -> Result<(i32, i32), String> {
+        self.enigo.location().map_err(|e| format!("{:?}", e))
+    }
+
+    fn main_display(&self) -> Result<(i32, i32), String> {
+        self.enigo.main_display().map_err(|e| format!("{:?}", e))
+    }
+
+    fn displays(&self) -> Result<Vec<Rect>, String> {
+        // The fail-safe asks on every poll, too often to list monitors each time
+        self.displays.get(|| match displays::platform_displays() {
+            Ok(displays) if !displays.is_empty() => Ok(displays),
+            // Enigo only knows the main display, which is still better than nothing
+            _ => {
+                let (width, height) = self.main_display()?;
+                Ok(vec![Rect { x: 0, y: 0, width, height }])
+            }
+        })
+    }
+}
+
+/// Input event captured by `RecordingBackend`
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    MoveBy(i32, i32),
+    MoveTo(i32, i32),
+    Button(Button, Direction),
+    Scroll(i32, Axis),
+    Key(Key, Direction),
+    Text(String),
+}
+
+/// Display size reported by `RecordingBackend`
+const RECORDING_DISPLAY: (i32, i32) = (1920, 1080);
+
+/// In-memory backend that records events instead of injecting them.
+/// Used for dry runs and for asserting engine behavior without a display.
+#[derive(Clone)]
+pub struct RecordingBackend {
+    events: Arc<Mutex<Vec<InputEvent>>>,
+    cursor: Arc<Mutex<(i32, i32)>>,
+}
+
+impl Default for RecordingBackend {
+    fn default() -> Self {
+        Self {
+            events: Arc::default(),
+            // Start in the middle so the fail-safe does not trip right away
+            cursor: Arc::new(Mutex::new((RECORDING_DISPLAY.0 / 2, RECORDING_DISPLAY.1 / 2))),
+        }
+    }
+}
+
+impl RecordingBackend {
+    /// Events recorded so far, shared with every clone of this backend
+    #[cfg(test)]
+    pub fn events(&self) -> Vec<InputEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Pretend the user moved the cursor to `(x, y)`
+    pub fn set_cursor(&self, x: i32, y: i32) {
+        *self.cursor.lock().unwrap() = (x, y);
+    }
+
+    fn record(&mut self, event: InputEvent) -> Result<(), String> {
+        println!("[dry run] {:?}", event);
+        self.events.lock().unwrap().push(event);
+        Ok(())
+    }
+}
+
+impl InputBackend for RecordingBackend {
+    fn move_by(&mut self, dx: i32, dy: i32) -> Result<(), String> {
+        {
+            let mut cursor = self.cursor.lock().unwrap();
+            cursor.0 = (cursor.0 + dx).clamp(0, RECORDING_DISPLAY.0 - 1);
+            cursor.1 = (cursor.1 + dy).clamp(0, RECORDING_DISPLAY.1 - 1);
+        }
+        self.record(InputEvent::MoveBy(dx, dy))
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), String> {
+        self.set_cursor(x, y);
+        self.record(InputEvent::MoveTo(x, y))
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        self.record(InputEvent::Button(button, direction))
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> Result<(), String> {
+        self.record(InputEvent::Scroll(length, axis))
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        self.record(InputEvent::Key(key, direction))
+    }
+
+    fn text(&mut self, text: &str) -> Result<(), String> {
+        self.record(InputEvent::Text(text.to_string()))
+    }
+
+    fn location(&self) -> Result<(i32, i32), String> {
+        Ok(*self.cursor.lock().unwrap())
+    }
+
+    fn main_display(&self) -> Result<(i32, i32), String> {
+        Ok(RECORDING_DISPLAY)
+    }
+
+    fn displays(&self) -> Result<Vec<Rect>, String> {
+        let (width, height) = RECORDING_DISPLAY;
+        Ok(vec![Rect { x: 0, y: 0, width, height }])
+    }
+}
+
+/// One segment of a planned mouse path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathStep {
+    pub dx: i32,
+    pub dy: i32,
+    pub delay_ms: u64,
+}
+
+/// A planned mouse move and how it treats the edges of the screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct MousePath {
+    pub steps: Vec<PathStep>,
+    /// Where the cursor may go. The path is mirrored or clipped to fit when
+    /// it is executed, since only then is the cursor position known.
+    pub bounds: Option<MoveBounds>,
+    /// Walk the path back afterwards so the cursor ends where it started
+    pub restore: bool,
+}
+
+/// A planned click, see `InputCommand::Click`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MouseClick {
+    pub button: Button,
+    /// 2 for a double click, 3 for a triple click
+    pub count: u32,
+    /// Pause between the clicks of a multi-click
+    pub gap_ms: u64,
+    /// Glide here before clicking instead of clicking where the cursor is
+    pub target: Option<(i32, i32)>,
+    /// Seed for the glide's curve and pacing, drawn from the planner's RNG
+    /// so a seeded engine glides the same way every run
+    pub glide_seed: u64,
+    /// Areas the click is refused in, wherever the cursor ended up
+    pub no_click_zones: Vec<Rect>,
+}
+
+/// One wheel notch of a planned scroll, with the pause that follows it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollStep {
+    pub amount: i32,
+    pub delay_ms: u64,
+}
+
+/// One grapheme cluster of a planned typing sequence, with the pause that
+/// follows it. A cluster such as an emoji ZWJ sequence or a letter with
+/// combining marks is sent in one go so it never arrives half-built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keystroke {
+    pub text: String,
+    pub delay_ms: u64,
+}
+
+impl Keystroke {
+    /// Key event for a control character. Consoles and remote desktops
+    /// ignore these when they arrive as plain text.
+    fn control_key(&self) -> Option<Key> {
+        match self.text.as_str() {
+            "\n" | "\r\n" | "\r" => Some(Key::Return),
+            "\t" => Some(Key::Tab),
+            "\u{8}" => Some(Key::Backspace),
+            "\u{1b}" => Some(Key::Escape),
+            "\u{7f}" => Some(Key::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// How much a typist hesitates after a grapheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pause {
+    None,
+    Word,
+    Clause,
+    Sentence,
+    Line,
+}
+
+impl Pause {
+    /// Covers CJK and fullwidth punctuation as well as ASCII
+    fn after(grapheme: &str) -> Self {
+        match grapheme.chars().next() {
+            Some('\n' | '\r') => Pause::Line,
+            Some('.' | '!' | '?' | '\u{2026}' | '\u{3002}' | '\u{ff01}' | '\u{ff0e}' | '\u{ff1f}' | '\u{ff61}') => Pause::Sentence,
+            Some(',' | ';' | ':' | '\u{3001}' | '\u{ff0c}' | '\u{ff1a}' | '\u{ff1b}' | '\u{ff64}') => Pause::Clause,
+            Some(ch) if ch.is_whitespace() => Pause::Word,
+            _ => Pause::None,
+        }
+    }
+}
+
+/// Commands understood by the input thread
+#[derive(Debug, Clone)]
+pub enum InputCommand {
+    /// Follow a planned mouse path, see `MousePath::smooth`
+    MovePath(MousePath),
+    /// Glide to a point on the desktop. The glide is planned on the input
+    /// thread once the cursor position is known, from `seed`.
+    MoveTo { x: i32, y: i32, seed: u64 },
+    /// Click a mouse button, optionally after moving to a target
+    Click(MouseClick),
+    /// Scroll the wheel one step at a time, see `InputCommand::scroll`
+    Scroll(Axis, Vec<ScrollStep>),
+    /// Tap a key, holding the chord's other keys around it
+    KeyChord(KeySpec),
+    /// Enter text in one go
+    Text(String),
+    /// Type planned keystrokes one at a time, see `InputCommand::type_text`
+    TypeText(Vec<Keystroke>),
+}
+
+impl MousePath {
+    /// Plan a smooth, curved move by a relative offset, with no bounds.
+    ///
+    /// The step deltas always add up to exactly `(target_dx, target_dy)`.
+    pub fn smooth(target_dx: i32, target_dy: i32, rng: &mut (impl Rng + ?Sized)) -> Self {
+        // Calculate the number of steps based on distance
+        let distance = f64::from(target_dx).hypot(f64::from(target_dy));
+        let steps = (distance / 1.5).clamp(15.0, 80.0) as i32; // More steps for smoother movement
+
+        // Add slight curve to the path for more natural movement
+        let curve_factor = rng.gen_range(-0.3..0.3); // Random curve
+
+        let mut path = Vec::with_capacity(steps as usize + 1);
+        let mut current_x = 0;
+        let mut current_y = 0;
+
+        for i in 0..steps {
+            let progress = i as f64 / steps as f64;
+
+            // Use easing function for natural acceleration/deceleration
+            // This creates an S-curve motion (slow-fast-slow)
+            let eased_progress = if progress < 0.5 {
+                2.0 * progress * progress
+            } else {
+                1.0 - 2.0 * (1.0 - progress) * (1.0 - progress)
+            };
+
+            // Calculate target position for this step with slight curve
+            let target_x = target_dx as f64 * eased_progress;
+            let target_y = target_dy as f64 * eased_progress;
+
+            // Add subtle curve to the path
+            let (perpendicular_x, perpendicular_y) = if distance > 0.0 {
+                let curve_offset = (progress * std::f64::consts::PI).sin() * curve_factor * distance;
+                (-target_dy as f64 / distance * curve_offset, target_dx as f64 / distance * curve_offset)
+            } else {
+                (0.0, 0.0)
+            };
+
+            // Track the rounded position so truncation never accumulates
+            let next_x = (target_x + perpendicular_x).round() as i32;
+            let next_y = (target_y + perpendicular_y).round() as i32;
+
+            // Variable delay based on movement phase
+            let base_delay = rng.gen_range(1..3); // Base delay in milliseconds
+            let random_delay = rng.gen_range(0..2); // Small random variation
+
+            // Slower at start and end for more natural movement
+            let speed_variation = if i < steps / 4 || i > steps * 3 / 4 {
+                2 // Slower at start and end
+            } else {
+                0 // Faster in the middle
+            };
+
+            path.push(PathStep {
+                dx: next_x - current_x,
+                dy: next_y - current_y,
+                delay_ms: base_delay + random_delay + speed_variation,
+            });
+            current_x = next_x;
+            current_y = next_y;
+        }
+
+        // Final adjustment to ensure we reach exact target
+        path.push(PathStep {
+            dx: target_dx - current_x,
+            dy: target_dy - current_y,
+            delay_ms: 0,
+        });
+
+        Self { steps: path, bounds: None, restore: false }
+    }
+
+    /// The steps adjusted to stay inside `area` when starting from `start`.
+    /// An axis whose end point would leave the area is mirrored if that
+    /// brings it closer, then each step is clipped so the cursor never
+    /// moves further outside than it already is. Without clipping the OS
+    /// pins the cursor at the screen edge and the rest of the path drifts.
+    fn fit(&self, start: (i32, i32), area: Rect) -> Vec<PathStep> {
+        let (total_dx, total_dy) = self.total();
+        let flip_x = mirror_helps(start.0, total_dx, area.columns());
+        let flip_y = mirror_helps(start.1, total_dy, area.rows());
+
+        let mut position = start;
+        self.steps
+            .iter()
+            .map(|step| {
+                let dx = if flip_x { -step.dx } else { step.dx };
+                let dy = if flip_y { -step.dy } else { step.dy };
+                let x = clip(position.0, dx, area.columns());
+                let y = clip(position.1, dy, area.rows());
+                let fitted = PathStep { dx: x - position.0, dy: y - position.1, delay_ms: step.delay_ms };
+                position = (x, y);
+                fitted
+            })
+            .collect()
+    }
+
+    fn total(&self) -> (i32, i32) {
+        self.steps.iter().fold((0, 0), |(x, y), step| (x + step.dx, y + step.dy))
+    }
+}
+
+/// How far `position` lies outside `range`
+fn overshoot(position: i32, (low, high): (i32, i32)) -> i32 {
+    (low - position).max(position - high).max(0)
+}
+
+/// Whether moving by `-delta` ends closer to `range` than moving by `delta`
+fn mirror_helps(start: i32, delta: i32, range: (i32, i32)) -> bool {
+    overshoot(start - delta, range) < overshoot(start + delta, range)
+}
+
+/// Where moving from `position` by `delta` ends without going further outside `range`
+fn clip(position: i32, delta: i32, (low, high): (i32, i32)) -> i32 {
+    (position + delta).clamp(low.min(position), high.max(position))
+}
+
+impl InputCommand {
+    /// Plan scrolling by each of `runs` in turn, one notch at a time.
+    /// Positive values scroll down or right, and there is a longer pause
+    /// between runs, like someone glancing at the page.
+    pub fn scroll(axis: Axis, runs: &[i32], rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut steps: Vec<ScrollStep> = Vec::new();
+        for &run in runs {
+            if let Some(last) = steps.last_mut() {
+                last.delay_ms += rng.gen_range(300..900);
+            }
+            for _ in 0..run.unsigned_abs() {
+                steps.push(ScrollStep { amount: run.signum(), delay_ms: rng.gen_range(40..120) });
+            }
+        }
+        if let Some(last) = steps.last_mut() {
+            last.delay_ms = 0;
+        }
+        InputCommand::Scroll(axis, steps)
+    }
+
+    /// Plan typing `text` one grapheme cluster at a time with natural delays
+    pub fn type_text(text: &str, rng: &mut (impl Rng + ?Sized)) -> Self {
+        let keystrokes = text
+            .graphemes(true)
+            .map(|grapheme| {
+                // Simulate human typing speed (40-120 WPM)
+                let base_delay = rng.gen_range(50..150); // milliseconds
+
+                // Add variation for more natural feeling
+                let variation = match Pause::after(grapheme) {
+                    Pause::Word => rng.gen_range(0..50),  // Slightly longer after spaces
+                    Pause::Sentence => rng.gen_range(100..300), // Longer pause after sentences
+                    Pause::Clause => rng.gen_range(50..150), // Medium pause after commas
+                    Pause::Line => rng.gen_range(200..400), // Pause after line breaks
+                    Pause::None => 0,
+                };
+
+                Keystroke { text: grapheme.to_string(), delay_ms: base_delay + variation }
+            })
+            .collect();
+
+        InputCommand::TypeText(keystrokes)
+    }
+
+    /// Plan typing `text` at a steady `wpm` words per minute, counting five
+    /// characters to a word. Line endings are normalized so each line break
+    /// becomes a single Enter.
+    pub fn type_paced(text: &str, wpm: u32, rng: &mut (impl Rng + ?Sized)) -> Self {
+        let base_delay = 12_000 / u64::from(wpm.max(1));
+        let keystrokes = text
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .graphemes(true)
+            .map(|grapheme| {
+                // Jitter by up to a third either way, and linger on line breaks
+                let jitter = rng.gen_range(0..=base_delay * 2 / 3);
+                let pause = if grapheme == "\n" { base_delay } else { 0 };
+                Keystroke { text: grapheme.to_string(), delay_ms: base_delay - base_delay / 3 + jitter + pause }
+            })
+            .collect();
+
+        InputCommand::TypeText(keystrokes)
+    }
+}
+
+impl std::fmt::Display for InputCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputCommand::MovePath(path) => {
+                let (dx, dy) = path.total();
+                write!(f, "mouse move by ({}, {})", dx, dy)
+            }
+            InputCommand::MoveTo { x, y, .. } => write!(f, "mouse move to ({}, {})", x, y),
+            InputCommand::Click(click) => {
+                write!(f, "{} click", format!("{:?}", click.button).to_lowercase())?;
+                if click.count > 1 {
+                    write!(f, " x{}", click.count)?;
+                }
+                if let Some((x, y)) = click.target {
+                    write!(f, " at ({}, {})", x, y)?;
+                }
+                Ok(())
+            }
+            InputCommand::Scroll(axis, steps) => {
+                // Collapse the notches back into runs, e.g. "3 then -3"
+                let mut runs: Vec<i32> = Vec::new();
+                for step in steps {
+                    match runs.last_mut() {
+                        Some(run) if run.signum() == step.amount.signum() => *run += step.amount,
+                        _ => runs.push(step.amount),
+                    }
+                }
+                let runs: Vec<String> = runs.iter().map(i32::to_string).collect();
+                let axis = match axis {
+                    Axis::Horizontal => "horizontal",
+                    Axis::Vertical => "vertical",
+                };
+                write!(f, "{} scroll by {}", axis, runs.join(" then "))
+            }
+            InputCommand::KeyChord(chord) => write!(f, "key press {}", chord),
+            InputCommand::Text(text) => write!(f, "text {:?}", text),
+            InputCommand::TypeText(keystrokes) => {
+                let text: String = keystrokes.iter().map(|keystroke| keystroke.text.as_str()).collect();
+                write!(f, "typing {:?}", text)
+            }
+        }
+    }
+}
+
+/// How far a command got before it finished or was aborted
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputProgress {
+    /// Movement steps taken, clicks made, notches scrolled or characters typed
+    pub steps: usize,
+    pub aborted: bool,
+}
+
+type BackendFactory = Box<dyn FnOnce() -> Result<TrackedBackend, String> + Send>;
+
+/// Notes when each event was sent, so idle detection can tell our input
+/// apart from the user's, and which keys and buttons are still held down
+struct TrackedBackend {
+    inner: Box<dyn InputBackend>,
+    last_event: Arc<Mutex<Option<Instant>>>,
+    user_moved_at: Arc<Mutex<Option<Instant>>>,
+    held_keys: Vec<Key>,
+    held_buttons: Vec<Button>,
+    /// Where our last move left the cursor, while the current command yields to the user
+    cursor: Option<(i32, i32)>,
+    yield_to_user: bool,
+}
+
+impl TrackedBackend {
+    fn new(inner: Box<dyn InputBackend>, last_event: Arc<Mutex<Option<Instant>>>, user_moved_at: Arc<Mutex<Option<Instant>>>) -> Self {
+        Self {
+            inner,
+            last_event,
+            user_moved_at,
+            held_keys: Vec::new(),
+            held_buttons: Vec::new(),
+            cursor: None,
+            yield_to_user: false,
+        }
+    }
+
+    fn stamp(&self, result: Result<(), String>) -> Result<(), String> {
+        *self.last_event.lock().unwrap() = Some(Instant::now());
+        result
+    }
+
+    /// Remember where a move left the cursor, so a later move by someone else stands out
+    fn note_cursor(&mut self, result: Result<(), String>) -> Result<(), String> {
+        if self.yield_to_user && result.is_ok() {
+            self.cursor = self.inner.location().ok();
+        }
+        self.stamp(result)
+    }
+
+    /// Whether the cursor has left the spot our last move put it in. OS idle
+    /// timers cannot show this while a path is running, since each of our
+    /// steps hides any earlier input.
+    fn user_moved_cursor(&self) -> bool {
+        let moved = match (self.cursor, self.inner.location()) {
+            (Some(expected), Ok(actual)) => self.yield_to_user && expected != actual,
+            _ => false,
+        };
+        if moved {
+            *self.user_moved_at.lock().unwrap() = Some(Instant::now());
+        }
+        moved
+    }
+
+    /// Release everything a command left pressed, e.g. after it was aborted
+    /// halfway through a chord
+    fn release_held(&mut self) {
+        for key in std::mem::take(&mut self.held_keys).into_iter().rev() {
+            if let Err(e) = self.key(key, Direction::Release) {
+                println!("Failed to release {:?}: {}", key, e);
+            }
+        }
+        for button in std::mem::take(&mut self.held_buttons).into_iter().rev() {
+            if let Err(e) = self.button(button, Direction::Release) {
+                println!("Failed to release {:?}: {}", button, e);
+            }
+        }
+    }
+}
+
+/// Keep `held` in sync with a press or release of `input`
+fn track<T: PartialEq>(held: &mut Vec<T>, input: T, direction: Direction) {
+    match direction {
+        Direction::Press if !held.contains(&input) => held.push(input),
+        Direction::Release => held.retain(|pressed| *pressed != input),
+        _ => {}
+    }
+}
+
+impl InputBackend for TrackedBackend {
+    fn move_by(&mut self, dx: i32, dy: i32) -> Result<(), String> {
+        let result = self.inner.move_by(dx, dy);
+        self.note_cursor(result)
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), String> {
+        let result = self.inner.move_to(x, y);
+        self.note_cursor(result)
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        let result = self.inner.button(button, direction);
+        if result.is_ok() {
+            track(&mut self.held_buttons, button, direction);
+        }
+        self.stamp(result)
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> Result<(), String> {
+        let result = self.inner.scroll(length, axis);
+        self.stamp(result)
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        let result = self.inner.key(key, direction);
+        if result.is_ok() {
+            track(&mut self.held_keys, key, direction);
+        }
+        self.stamp(result)
+    }
+
+    fn text(&mut self, text: &str) -> Result<(), String> {
+        let result = self.inner.text(text);
+        self.stamp(result)
+    }
+
+    fn location(&self) -> Result<(i32, i32), String> {
+        self.inner.location()
+    }
+
+    fn main_display(&self) -> Result<(i32, i32), String> {
+        self.inner.main_display()
+    }
+
+    fn displays(&self) -> Result<Vec<Rect>, String> {
+        self.inner.displays()
+    }
+}
+
+struct Job {
+    command: InputCommand,
+    cancel: CancellationToken,
+    yield_to_user: bool,
+    reply: oneshot::Sender<Result<InputProgress, String>>,
+}
+
+/// Handle to the thread that owns the input backend and injects all input.
+///
+/// Commands run one at a time in the order they were sent. Dropping the
+/// handle shuts the thread down once the current command has finished.
+pub struct InputActor {
+    jobs: mpsc::Sender<Job>,
+    current: Arc<Mutex<Option<CancellationToken>>>,
+    last_event: Arc<Mutex<Option<Instant>>>,
+    user_moved_at: Arc<Mutex<Option<Instant>>>,
+    fail_safe: Arc<FailSafe>,
+}
+
+impl InputActor {
+    /// Spawn the input thread with the default enigo backend
+    pub fn spawn() -> std::io::Result<Self> {
+        Self::spawn_with(|| Ok(Box::new(EnigoBackend::new()?)))
+    }
+
+    /// Spawn the input thread with a custom backend. The backend is built
+    /// on the input thread, so it does not need to be `Send`.
+    pub fn spawn_with<F>(factory: F) -> std::io::Result<Self>
+    where
+        F: FnOnce() -> Result<Box<dyn InputBackend>, String> + Send + 'static,
+    {
+        let last_event = Arc::new(Mutex::new(None));
+        let user_moved_at = Arc::new(Mutex::new(None));
+        let stamps = Arc::clone(&last_event);
+        let user_stamps = Arc::clone(&user_moved_at);
+        let factory: BackendFactory = Box::new(move || Ok(TrackedBackend::new(factory()?, stamps, user_stamps)));
+        let fail_safe = Arc::new(FailSafe::default());
+        let watcher = Arc::clone(&fail_safe);
+        let (jobs, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("lupin-input".to_string())
+            .spawn(move || Self::run(factory, receiver, &watcher))?;
+
+        Ok(Self {
+            jobs,
+            current: Arc::new(Mutex::new(None)),
+            last_event,
+            user_moved_at,
+            fail_safe,
+        })
+    }
+
+    /// Run `command` on the input thread and wait for it to finish.
+    /// Cancelling `cancel` interrupts the command between steps.
+    pub async fn execute(&self, command: InputCommand, cancel: &CancellationToken) -> Result<InputProgress, String> {
+        self.send(command, cancel, false).await
+    }
+
+    /// Like `execute`, but a mouse move is abandoned between steps as soon
+    /// as something else moves the cursor, see `user_moved_at`
+    pub async fn execute_yielding(&self, command: InputCommand, cancel: &CancellationToken) -> Result<InputProgress, String> {
+        self.send(command, cancel, true).await
+    }
+
+    async fn send(&self, command: InputCommand, cancel: &CancellationToken, yield_to_user: bool) -> Result<InputProgress, String> {
+        let cancel = cancel.child_token();
+        *self.current.lock().unwrap() = Some(cancel.clone());
+
+        let (reply, result) = oneshot::channel();
+        self.jobs
+            .send(Job { command, cancel, yield_to_user, reply })
+            .map_err(|_| "Input thread is not running".to_string())?;
+
+        result.await.map_err(|_| "Input thread exited before replying".to_string())?
+    }
+
+    /// When the input thread last sent an event
+    pub fn last_event(&self) -> Option<Instant> {
+        *self.last_event.lock().unwrap()
+    }
+
+    /// When the user last took the cursor away from a yielding move
+    pub fn user_moved_at(&self) -> Option<Instant> {
+        *self.user_moved_at.lock().unwrap()
+    }
+
+    /// The screen-corner fail-safe watched by the input thread
+    pub fn fail_safe(&self) -> &FailSafe {
+        &self.fail_safe
+    }
+
+    /// Abort the command currently being executed, if any
+    pub fn abort(&self) {
+        if let Some(cancel) = self.current.lock().unwrap().take() {
+            cancel.cancel();
+        }
+    }
+
+    fn run(factory: BackendFactory, jobs: mpsc::Receiver<Job>, fail_safe: &FailSafe) {
+        // The backend is created once and never leaves this thread
+        let mut backend = factory();
+        if let Err(ref e) = backend {
+            println!("{}", e);
+        }
+
+        loop {
+            // Wake up regularly so the fail-safe is watched between commands too
+            let job = match jobs.recv_timeout(failsafe::POLL_INTERVAL) {
+                Ok(job) => job,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Ok(ref backend) = backend {
+                        fail_safe.check(backend);
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            let result = match backend {
+                // Aborted while still queued
+                _ if job.cancel.is_cancelled() => Ok(InputProgress { steps: 0, aborted: true }),
+                Ok(ref mut backend) => {
+                    backend.yield_to_user = job.yield_to_user;
+                    backend.cursor = None;
+                    let result = Self::perform(backend, job.command, &job.cancel, fail_safe);
+                    backend.release_held();
+                    result
+                }
+                Err(ref e) => Err(e.clone()),
+            };
+            let _ = job.reply.send(result);
+        }
+
+        println!("Input thread stopped");
+    }
+
+    /// Wait out a step's delay. Returns false if the command should stop,
+    /// either because it was cancelled or because the fail-safe tripped.
+    fn pause_between_steps(backend: &TrackedBackend, cancel: &CancellationToken, fail_safe: &FailSafe, delay: Duration) -> bool {
+        if fail_safe.check(backend).is_some() {
+            cancel.cancel();
+        }
+        Self::sleep_unless_cancelled(cancel, delay)
+    }
+
+    /// Move along `steps`, stopping early when cancelled
+    fn follow(backend: &mut TrackedBackend, steps: &[PathStep], cancel: &CancellationToken, fail_safe: &FailSafe) -> Result<InputProgress, String> {
+        for (i, step) in steps.iter().enumerate() {
+            if backend.user_moved_cursor() {
+                println!("User moved the mouse, abandoning the move");
+                // Still stop everything if they went for a fail-safe corner
+                fail_safe.check(backend);
+                return Ok(InputProgress { steps: i, aborted: true });
+            }
+            if step.dx != 0 || step.dy != 0 {
+                backend.move_by(step.dx, step.dy)?;
+            }
+            if !Self::pause_between_steps(backend, cancel, fail_safe, Duration::from_millis(step.delay_ms)) {
+                return Ok(InputProgress { steps: i + 1, aborted: true });
+            }
+        }
+        Ok(InputProgress { steps: steps.len(), aborted: false })
+    }
+
+    /// Move smoothly from wherever the cursor is to `(x, y)`, along a path planned from `seed`
+    fn glide_to(backend: &mut TrackedBackend, (x, y): (i32, i32), seed: u64, cancel: &CancellationToken, fail_safe: &FailSafe) -> Result<InputProgress, String> {
+        let (cursor_x, cursor_y) = backend.location()?;
+        let glide = MousePath::smooth(x - cursor_x, y - cursor_y, &mut StdRng::seed_from_u64(seed));
+        let progress = Self::follow(backend, &glide.steps, cancel, fail_safe)?;
+        if !progress.aborted {
+            // Land exactly on the target even if pointer acceleration bent the path
+            backend.move_to(x, y)?;
+        }
+        Ok(progress)
+    }
+
+    /// The path's steps, fitted to its bounds when the cursor position and
+    /// the monitor layout can be read
+    fn fit_path(backend: &TrackedBackend, path: &MousePath) -> Vec<PathStep> {
+        let Some(bounds) = path.bounds else {
+            return path.steps.clone();
+        };
+        let area = backend.location().and_then(|cursor| {
+            let area = match bounds {
+                MoveBounds::Region(area) => area,
+                MoveBounds::CurrentMonitor => {
+                    let displays = backend.displays()?;
+                    displays
+                        .iter()
+                        .find(|display| display.contains(cursor))
+                        .or(displays.first())
+                        .copied()
+                        .ok_or("No displays found")?
+                }
+            };
+            Ok((cursor, area))
+        });
+        match area {
+            Ok((cursor, area)) => path.fit(cursor, area),
+            Err(e) => {
+                println!("Moving without bounds: {}", e);
+                path.steps.clone()
+            }
+        }
+    }
+
+    fn perform(backend: &mut TrackedBackend, command: InputCommand, cancel: &CancellationToken, fail_safe: &FailSafe) -> Result<InputProgress, String> {
+        match command {
+            InputCommand::MovePath(path) => {
+                let mut steps = Self::fit_path(backend, &path);
+                if path.restore {
+                    // Retrace the path backwards, never jumping straight back
+                    let back: Vec<_> = steps
+                        .iter()
+                        .rev()
+                        .map(|step| PathStep { dx: -step.dx, dy: -step.dy, delay_ms: step.delay_ms })
+                        .collect();
+                    steps.extend(back);
+                }
+                Self::follow(backend, &steps, cancel, fail_safe)
+            }
+            InputCommand::MoveTo { x, y, seed } => Self::glide_to(backend, (x, y), seed, cancel, fail_safe),
+            InputCommand::Click(click) => {
+                // Check where the click would land before moving anywhere
+                if !click.no_click_zones.is_empty() {
+                    let spot = match click.target {
+                        Some(target) => target,
+                        None => backend.location()?,
+                    };
+                    if let Some(zone) = click.no_click_zones.iter().find(|zone| zone.contains(spot)) {
+                        return Err(format!(
+                            "Refused to click at ({}, {}) inside the no-click zone at ({}, {})",
+                            spot.0, spot.1, zone.x, zone.y,
+                        ));
+                    }
+                }
+                if let Some(target) = click.target {
+                    let progress = Self::glide_to(backend, target, click.glide_seed, cancel, fail_safe)?;
+                    if progress.aborted {
+                        return Ok(progress);
+                    }
+                }
+                for i in 0..click.count {
+                    if i > 0 && !Self::sleep_unless_cancelled(cancel, Duration::from_millis(click.gap_ms)) {
+                        return Ok(InputProgress { steps: i as usize, aborted: true });
+                    }
+                    backend.button(click.button, Direction::Click)?;
+                }
+                Ok(InputProgress { steps: click.count as usize, aborted: false })
+            }
+            InputCommand::Scroll(axis, steps) => {
+                for (i, step) in steps.iter().enumerate() {
+                    backend.scroll(step.amount, axis)?;
+                    if !Self::pause_between_steps(backend, cancel, fail_safe, Duration::from_millis(step.delay_ms)) {
+                        return Ok(InputProgress { steps: i + 1, aborted: true });
+                    }
+                }
+                Ok(InputProgress { steps: steps.len(), aborted: false })
+            }
+            InputCommand::KeyChord(chord) => {
+                // Anything left down on an error is let go by release_held
+                for key in &chord.held {
+                    backend.key(*key, Direction::Press)?;
+                }
+                backend.key(chord.key, Direction::Click)?;
+                for key in chord.held.iter().rev() {
+                    backend.key(*key, Direction::Release)?;
+                }
+                Ok(InputProgress { steps: 1, aborted: false })
+            }
+            InputCommand::Text(text) => {
+                backend.text(&text)?;
+                Ok(InputProgress { steps: text.graphemes(true).count(), aborted: false })
+            }
+            InputCommand::TypeText(keystrokes) => {
+                for (i, keystroke) in keystrokes.iter().enumerate() {
+                    if cancel.is_cancelled() {
+                        return Ok(InputProgress { steps: i, aborted: true });
+                    }
+                    // Text goes in as Unicode rather than key codes, so an
+                    // active IME has nothing to recompose
+                    let typed = match keystroke.control_key() {
+                        Some(key) => backend.key(key, Direction::Click),
+                        // Other control characters have no key and would only confuse the target
+                        None if keystroke.text.chars().all(char::is_control) => Ok(()),
+                        None => backend.text(&keystroke.text),
+                    };
+                    if let Err(e) = typed {
+                        println!("Failed to type {:?}: {}", keystroke.text, e);
+                    }
+                    if !Self::pause_between_steps(backend, cancel, fail_safe, Duration::from_millis(keystroke.delay_ms)) {
+                        return Ok(InputProgress { steps: i + 1, aborted: true });
+                    }
+                }
+                Ok(InputProgress { steps: keystrokes.len(), aborted: false })
+            }
+        }
+    }
+
+    /// Sleep for `duration` in short slices, returning false as soon as `cancel` fires
+    fn sleep_unless_cancelled(cancel: &CancellationToken, duration: Duration) -> bool {
+        const SLICE: Duration = Duration::from_millis(10);
+
+        let deadline = Instant::now() + duration;
+        while !cancel.is_cancelled() {
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            thread::sleep(SLICE.min(deadline - now));
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Rect = Rect { x: 0, y: 0, width: 1920, height: 1080 };
+
+    #[test]
+    fn smooth_path_adds_up_to_the_target() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for (dx, dy) in [(0, 0), (1, -1), (300, -299), (-7, 0), (0, 1500), (-1919, 1079)] {
+            let path = MousePath::smooth(dx, dy, &mut rng);
+            assert_eq!(path.total(), (dx, dy), "path to ({}, {})", dx, dy);
+            assert!(path.steps.len() >= 16);
+        }
+    }
+
+    #[test]
+    fn smooth_path_is_reproducible_with_a_seed() {
+        let first = MousePath::smooth(250, 40, &mut StdRng::seed_from_u64(9));
+        let second = MousePath::smooth(250, 40, &mut StdRng::seed_from_u64(9));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn clip_never_moves_further_outside() {
+        let range = (0, 99);
+        assert_eq!(clip(50, 30, range), 80);
+        assert_eq!(clip(90, 30, range), 99);
+        assert_eq!(clip(10, -30, range), 0);
+        // Already outside: may move back in but not further out
+        assert_eq!(clip(120, 10, range), 120);
+        assert_eq!(clip(120, -30, range), 90);
+    }
+
+    #[test]
+    fn fit_mirrors_a_move_that_would_leave_the_screen() {
+        let path = MousePath::smooth(300, 0, &mut StdRng::seed_from_u64(2));
+        let start = (1800, 500);
+        let steps = path.fit(start, SCREEN);
+        let end = steps.iter().fold(start, |(x, y), step| (x + step.dx, y + step.dy));
+        assert_eq!(end.0, 1500);
+    }
+
+    #[test]
+    fn fit_keeps_every_step_inside_the_area() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let area = Rect { x: 100, y: 100, width: 200, height: 150 };
+        for _ in 0..50 {
+            let path = MousePath::smooth(rng.gen_range(-600..=600), rng.gen_range(-600..=600), &mut rng);
+            let mut position = (rng.gen_range(100..300), rng.gen_range(100..250));
+            for step in path.fit(position, area) {
+                position = (position.0 + step.dx, position.1 + step.dy);
+                assert!(area.contains(position), "{:?} left {:?}", position, area);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn click_glides_the_same_way_for_the_same_seed() {
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let backend = RecordingBackend::default();
+            let recorder = backend.clone();
+            let input = InputActor::spawn_with(move || Ok(Box::new(recorder))).unwrap();
+            let click = MouseClick {
+                button: Button::Left,
+                count: 1,
+                gap_ms: 0,
+                target: Some((200, 300)),
+                glide_seed: 42,
+                no_click_zones: Vec::new(),
+            };
+            let progress = input.execute(InputCommand::Click(click), &CancellationToken::new()).await.unwrap();
+            assert!(!progress.aborted);
+            runs.push(backend.events());
+        }
+        assert_eq!(runs[0], runs[1]);
+        assert_eq!(runs[0].last(), Some(&InputEvent::Button(Button::Left, Direction::Click)));
+        assert!(runs[0].contains(&InputEvent::MoveTo(200, 300)));
+    }
+
+    fn texts(command: &InputCommand) -> Vec<&str> {
+        match command {
+            InputCommand::TypeText(keystrokes) => keystrokes.iter().map(|keystroke| keystroke.text.as_str()).collect(),
+            other => panic!("expected typed text, got {}", other),
+        }
+    }
+
+    #[test]
+    fn type_text_sends_whole_graphemes() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let command = InputCommand::type_text("한글 かな漢字 👩‍👩‍👧‍👦🇰🇷e\u{301}\r\n", &mut rng);
+        assert_eq!(
+            texts(&command),
+            ["한", "글", " ", "か", "な", "漢", "字", " ", "👩‍👩‍👧‍👦", "🇰🇷", "e\u{301}", "\r\n"]
+        );
+    }
+
+    #[test]
+    fn line_endings_become_one_return() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let paced = InputCommand::type_paced("a\r\nb\rc\n", 400, &mut rng);
+        assert_eq!(texts(&paced), ["a", "\n", "b", "\n", "c", "\n"]);
+
+        let keystroke = |text: &str| Keystroke { text: text.to_string(), delay_ms: 0 };
+        for ending in ["\n", "\r", "\r\n"] {
+            assert_eq!(keystroke(ending).control_key(), Some(Key::Return), "{:?}", ending);
+        }
+        assert_eq!(keystroke("\t").control_key(), Some(Key::Tab));
+        assert_eq!(keystroke("🇰🇷").control_key(), None);
+    }
+
+    #[tokio::test]
+    async fn typing_injects_clusters_and_return_keys() {
+        let backend = RecordingBackend::default();
+        let recorder = backend.clone();
+        let input = InputActor::spawn_with(move || Ok(Box::new(recorder))).unwrap();
+        let command = InputCommand::type_text("👨🏽‍💻\r\n🇯🇵", &mut StdRng::seed_from_u64(5));
+        let progress = input.execute(command, &CancellationToken::new()).await.unwrap();
+        assert_eq!(progress.steps, 3);
+        assert_eq!(
+            backend.events(),
+            [
+                InputEvent::Text("👨🏽‍💻".to_string()),
+                InputEvent::Key(Key::Return, Direction::Click),
+                InputEvent::Text("🇯🇵".to_string()),
+            ]
+        );
+    }
+}
+
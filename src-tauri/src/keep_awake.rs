@@ -0,0 +1,318 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A held power-management lock. Dropping it lets the system sleep again.
+pub struct InhibitGuard {
+    release: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl InhibitGuard {
+    pub fn new(release: impl FnOnce() + Send + 'static) -> Self {
+        Self {
+            release: Some(Box::new(release)),
+        }
+    }
+}
+
+impl Drop for InhibitGuard {
+    fn drop(&mut self) {
+        if let Some(release) = self.release.take() {
+            release();
+        }
+        println!("Keep-awake lock released");
+    }
+}
+
+/// Keeps the screen on and the machine awake without injecting any input
+pub trait KeepAwakeBackend: Send + Sync {
+    fn inhibit<'a>(&'a self, reason: &'a str) -> BoxFuture<'a, Result<InhibitGuard, String>>;
+}
+
+/// Backend for the current OS
+pub fn platform_backend() -> Arc<dyn KeepAwakeBackend> {
+    #[cfg(target_os = "linux")]
+    let backend = linux::DbusKeepAwake::default();
+    #[cfg(target_os = "macos")]
+    let backend = macos::CaffeinateKeepAwake;
+    #[cfg(target_os = "windows")]
+    let backend = windows::ExecutionStateKeepAwake;
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let backend = RecordingKeepAwake::default();
+    Arc::new(backend)
+}
+
+/// Counts held locks instead of talking to the OS, for dry runs and tests
+#[derive(Clone, Default)]
+pub struct RecordingKeepAwake {
+    held: Arc<AtomicUsize>,
+}
+
+impl RecordingKeepAwake {
+    /// Number of locks currently held
+    #[cfg(test)]
+    pub fn held(&self) -> usize {
+        self.held.load(Ordering::SeqCst)
+    }
+}
+
+impl KeepAwakeBackend for RecordingKeepAwake {
+    fn inhibit<'a>(&'a self, reason: &'a str) -> BoxFuture<'a, Result<InhibitGuard, String>> {
+        Box::pin(async move {
+            println!("[dry run] keep awake: {}", reason);
+            self.held.fetch_add(1, Ordering::SeqCst);
+            let held = Arc::clone(&self.held);
+            Ok(InhibitGuard::new(move || {
+                held.fetch_sub(1, Ordering::SeqCst);
+            }))
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod linux {
+    use super::{BoxFuture, InhibitGuard, KeepAwakeBackend};
+    use zbus::zvariant::OwnedFd;
+    use zbus::Connection;
+
+    /// Takes an `org.freedesktop.ScreenSaver` inhibit on the session bus to
+    /// keep the screen on, and a logind `Inhibit` lock on the system bus to
+    /// block sleep. Either one is enough to succeed, since desktops differ in
+    /// which they offer.
+    #[derive(Default)]
+    pub struct DbusKeepAwake {
+        session: Option<Connection>,
+        system: Option<Connection>,
+    }
+
+    impl DbusKeepAwake {
+        /// Use existing connections instead of the default buses, e.g. a
+        /// private bus standing in for the session bus in tests
+        #[cfg(test)]
+        pub fn with_connections(session: Option<Connection>, system: Option<Connection>) -> Self {
+            Self { session, system }
+        }
+
+        async fn session(&self) -> zbus::Result<Connection> {
+            match &self.session {
+                Some(connection) => Ok(connection.clone()),
+                None => Connection::session().await,
+            }
+        }
+
+        async fn system(&self) -> zbus::Result<Connection> {
+            match &self.system {
+                Some(connection) => Ok(connection.clone()),
+                None => Connection::system().await,
+            }
+        }
+
+        /// Returns the connection and cookie needed to release the inhibit
+        async fn inhibit_screensaver(&self, reason: &str) -> zbus::Result<(Connection, u32)> {
+            let connection = self.session().await?;
+            let reply = connection
+                .call_method(
+                    Some("org.freedesktop.ScreenSaver"),
+                    "/org/freedesktop/ScreenSaver",
+                    Some("org.freedesktop.ScreenSaver"),
+                    "Inhibit",
+                    &("Lupin", reason),
+                )
+                .await?;
+            let cookie: u32 = reply.body().deserialize()?;
+            Ok((connection, cookie))
+        }
+
+        /// The lock is held for as long as the returned descriptor stays open
+        async fn inhibit_logind(&self, reason: &str) -> zbus::Result<OwnedFd> {
+            let connection = self.system().await?;
+            let reply = connection
+                .call_method(
+                    Some("org.freedesktop.login1"),
+                    "/org/freedesktop/login1",
+                    Some("org.freedesktop.login1.Manager"),
+                    "Inhibit",
+                    &("sleep:idle", "Lupin", reason, "block"),
+                )
+                .await?;
+            reply.body().deserialize()
+        }
+    }
+
+    impl KeepAwakeBackend for DbusKeepAwake {
+        fn inhibit<'a>(&'a self, reason: &'a str) -> BoxFuture<'a, Result<InhibitGuard, String>> {
+            Box::pin(async move {
+                let screensaver = self.inhibit_screensaver(reason).await;
+                if let Err(e) = &screensaver {
+                    println!("ScreenSaver inhibit unavailable: {}", e);
+                }
+                let logind = self.inhibit_logind(reason).await;
+                if let Err(e) = &logind {
+                    println!("logind inhibit unavailable: {}", e);
+                }
+                if let (Err(screensaver), Err(logind)) = (&screensaver, &logind) {
+                    return Err(format!(
+                        "No inhibitor available (ScreenSaver: {}; logind: {})",
+                        screensaver, logind
+                    ));
+                }
+
+                let screensaver = screensaver.ok();
+                let logind = logind.ok();
+                Ok(InhibitGuard::new(move || {
+                    // Closing the descriptor releases the logind lock
+                    drop(logind);
+                    if let Some((connection, cookie)) = screensaver {
+                        // The bus also drops the inhibit when our connection
+                        // closes, so a failed or skipped call is harmless
+                        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                            runtime.spawn(async move {
+                                let _ = connection
+                                    .call_method(
+                                        Some("org.freedesktop.ScreenSaver"),
+                                        "/org/freedesktop/ScreenSaver",
+                                        Some("org.freedesktop.ScreenSaver"),
+                                        "UnInhibit",
+                                        &(cookie,),
+                                    )
+                                    .await;
+                            });
+                        }
+                    }
+                }))
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+        use tokio::net::UnixStream;
+        use zbus::connection::Builder;
+        use zbus::Guid;
+
+        const COOKIE: u32 = 42;
+
+        /// Records the calls a desktop's screensaver service would receive
+        #[derive(Clone, Default)]
+        struct FakeScreenSaver {
+            calls: Arc<Mutex<Vec<String>>>,
+        }
+
+        #[zbus::interface(name = "org.freedesktop.ScreenSaver")]
+        impl FakeScreenSaver {
+            fn inhibit(&self, application: &str, reason: &str) -> u32 {
+                self.calls.lock().unwrap().push(format!("Inhibit {} {}", application, reason));
+                COOKIE
+            }
+
+            #[zbus(name = "UnInhibit")]
+            fn un_inhibit(&self, cookie: u32) {
+                self.calls.lock().unwrap().push(format!("UnInhibit {}", cookie));
+            }
+        }
+
+        /// A private peer-to-peer bus serving `screensaver`, returning the
+        /// server end (which must stay alive) and the client end
+        async fn private_bus(screensaver: FakeScreenSaver) -> (Connection, Connection) {
+            let (server, client) = UnixStream::pair().unwrap();
+            let server = Builder::unix_stream(server)
+                .server(Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at("/org/freedesktop/ScreenSaver", screensaver)
+                .unwrap()
+                .build();
+            let client = Builder::unix_stream(client).p2p().build();
+            tokio::try_join!(server, client).unwrap()
+        }
+
+        #[tokio::test]
+        async fn screensaver_inhibit_is_released_with_the_same_cookie() {
+            let screensaver = FakeScreenSaver::default();
+            let (_server, client) = private_bus(screensaver.clone()).await;
+            // The private bus has no logind, so only the ScreenSaver inhibit succeeds
+            let backend = DbusKeepAwake::with_connections(Some(client.clone()), Some(client));
+
+            let guard = backend.inhibit("testing").await.unwrap();
+            assert_eq!(*screensaver.calls.lock().unwrap(), ["Inhibit Lupin testing"]);
+
+            drop(guard);
+            for _ in 0..200 {
+                if screensaver.calls.lock().unwrap().len() > 1 {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert_eq!(
+                *screensaver.calls.lock().unwrap(),
+                ["Inhibit Lupin testing".to_string(), format!("UnInhibit {}", COOKIE)]
+            );
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub mod macos {
+    use super::{BoxFuture, InhibitGuard, KeepAwakeBackend};
+    use std::process::Command;
+
+    /// Runs `caffeinate` for as long as the lock is held. `-w` ties it to our
+    /// process so a crash never leaves the machine awake forever.
+    pub struct CaffeinateKeepAwake;
+
+    impl KeepAwakeBackend for CaffeinateKeepAwake {
+        fn inhibit<'a>(&'a self, _reason: &'a str) -> BoxFuture<'a, Result<InhibitGuard, String>> {
+            Box::pin(async move {
+                let mut child = Command::new("caffeinate")
+                    .args(["-d", "-i", "-w", &std::process::id().to_string()])
+                    .spawn()
+                    .map_err(|e| format!("Failed to run caffeinate: {}", e))?;
+                Ok(InhibitGuard::new(move || {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }))
+            })
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub mod windows {
+    use super::{BoxFuture, InhibitGuard, KeepAwakeBackend};
+    use std::sync::mpsc;
+    use windows_sys::Win32::System::Power::{
+        SetThreadExecutionState, ES_CONTINUOUS, ES_DISPLAY_REQUIRED, ES_SYSTEM_REQUIRED,
+    };
+
+    /// The execution state belongs to the calling thread, so each lock gets a
+    /// small thread that sets it and clears it again on release
+    pub struct ExecutionStateKeepAwake;
+
+    impl KeepAwakeBackend for ExecutionStateKeepAwake {
+        fn inhibit<'a>(&'a self, _reason: &'a str) -> BoxFuture<'a, Result<InhibitGuard, String>> {
+            Box::pin(async move {
+                let (release, released) = mpsc::channel::<()>();
+                std::thread::Builder::new()
+                    .name("lupin-keep-awake".to_string())
+                    .spawn(move || {
+                        unsafe {
+                            SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED | ES_DISPLAY_REQUIRED);
+                        }
+                        // Returns once the guard drops the sender
+                        let _ = released.recv();
+                        unsafe {
+                            SetThreadExecutionState(ES_CONTINUOUS);
+                        }
+                    })
+                    .map_err(|e| format!("Failed to spawn keep-awake thread: {}", e))?;
+                Ok(InhibitGuard::new(move || drop(release)))
+            })
+        }
+    }
+}
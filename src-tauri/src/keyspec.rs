@@ -0,0 +1,304 @@
+use enigo::Key;
+use std::fmt;
+
+/// Canonical spelling of every named key, followed by the other names it
+/// goes by. Names are matched ignoring case.
+const KEY_NAMES: &[(&str, &[&str])] = &[
+    ("Shift", &[]),
+    ("LShift", &[]),
+    ("RShift", &[]),
+    ("Ctrl", &["Control"]),
+    ("LCtrl", &["LControl"]),
+    ("RCtrl", &["RControl"]),
+    ("Alt", &["Option"]),
+    ("Super", &["Meta", "Win", "Cmd", "Command"]),
+    ("Escape", &["Esc"]),
+    ("Space", &[]),
+    ("Tab", &[]),
+    ("Enter", &["Return"]),
+    ("Backspace", &[]),
+    ("Delete", &["Del"]),
+    ("Insert", &["Ins"]),
+    ("Home", &[]),
+    ("End", &[]),
+    ("PageUp", &["PgUp"]),
+    ("PageDown", &["PgDn"]),
+    ("Up", &[]),
+    ("Down", &[]),
+    ("Left", &[]),
+    ("Right", &[]),
+    ("CapsLock", &[]),
+    ("NumLock", &[]),
+    ("Pause", &[]),
+    ("PrintScreen", &["Print"]),
+    ("Plus", &[]),
+    ("VolumeUp", &[]),
+    ("VolumeDown", &[]),
+    ("Mute", &["VolumeMute"]),
+    ("PlayPause", &[]),
+    ("NextTrack", &[]),
+    ("PrevTrack", &[]),
+];
+
+/// One key of a chord, independent of how it is injected or grabbed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyName {
+    /// A single character, standing for itself
+    Char(char),
+    /// F1 to F24
+    Function(u8),
+    /// A canonical name from `KEY_NAMES`
+    Named(&'static str),
+}
+
+impl KeyName {
+    fn parse(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        if let (Some(ch), None) = (chars.next(), chars.next()) {
+            return Some(KeyName::Char(ch));
+        }
+        if let Some(n) = name.strip_prefix(['F', 'f']).and_then(|n| n.parse::<u8>().ok()) {
+            return (1..=24).contains(&n).then_some(KeyName::Function(n));
+        }
+        KEY_NAMES
+            .iter()
+            .find(|(canonical, aliases)| {
+                canonical.eq_ignore_ascii_case(name) || aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))
+            })
+            .map(|(canonical, _)| KeyName::Named(canonical))
+    }
+}
+
+impl fmt::Display for KeyName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyName::Char(ch) => write!(f, "{}", ch),
+            KeyName::Function(n) => write!(f, "F{}", n),
+            KeyName::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// A key or chord written like `F15`, `Escape` or `Ctrl+Alt+Right`, split
+/// into canonical names. Shared by key actions and global hotkeys, which
+/// each decide which of the names they support.
+///
+/// `Ctrl++` or `Plus` is the plus key. Letters after held keys are stored
+/// lowercase, since they name the key rather than the character.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chord {
+    pub held: Vec<KeyName>,
+    pub key: KeyName,
+}
+
+impl Chord {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Err("Key is empty".to_string());
+        }
+        let (held, key) = if spec == "+" {
+            ("", "+")
+        } else if let Some(held) = spec.strip_suffix("++") {
+            (held, "+")
+        } else {
+            spec.rsplit_once('+').unwrap_or(("", spec))
+        };
+
+        let parse = |name: &str| {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(format!("Missing key in {}", spec));
+            }
+            KeyName::parse(name).ok_or_else(|| format!("Unknown key {} in {}", name, spec))
+        };
+        let held = if held.is_empty() {
+            Vec::new()
+        } else {
+            held.split('+').map(parse).collect::<Result<Vec<_>, _>>()?
+        };
+        let key = match parse(key)? {
+            KeyName::Char(ch) if !held.is_empty() => KeyName::Char(ch.to_ascii_lowercase()),
+            key => key,
+        };
+        Ok(Self { held, key })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.held {
+            write!(f, "{}+", name)?;
+        }
+        match self.key {
+            KeyName::Char(ch) if !self.held.is_empty() => write!(f, "{}", ch.to_ascii_uppercase()),
+            key => write!(f, "{}", key),
+        }
+    }
+}
+
+/// A chord ready to be typed. Every key but the last is held down while the
+/// last one is tapped, then released in reverse order.
+///
+/// A single character stands for itself, and letters in a chord name the
+/// key rather than the character, so `Ctrl+C` copies instead of sending
+/// Ctrl+Shift+C.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySpec {
+    pub held: Vec<Key>,
+    pub key: Key,
+    /// The names as parsed, for display
+    pub chord: Chord,
+}
+
+impl KeySpec {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let chord = Chord::parse(spec)?;
+        let held = chord.held.iter().map(|name| enigo_key(*name)).collect::<Result<Vec<_>, _>>()?;
+        let key = enigo_key(chord.key)?;
+        Ok(Self { held, key, chord })
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.chord)
+    }
+}
+
+fn enigo_key(name: KeyName) -> Result<Key, String> {
+    let key = match name {
+        KeyName::Char(ch) => Some(Key::Unicode(ch)),
+        KeyName::Function(n) => function_key(n),
+        KeyName::Named(name) => named_key(name),
+    };
+    key.ok_or_else(|| format!("{} is not available on this platform", name))
+}
+
+/// Takes a canonical name from `KEY_NAMES`
+fn named_key(name: &str) -> Option<Key> {
+    let key = match name {
+        "Shift" => Key::Shift,
+        "LShift" => Key::LShift,
+        "RShift" => Key::RShift,
+        "Ctrl" => Key::Control,
+        "LCtrl" => Key::LControl,
+        "RCtrl" => Key::RControl,
+        "Alt" => Key::Alt,
+        "Super" => Key::Meta,
+        "Escape" => Key::Escape,
+        "Space" => Key::Space,
+        "Tab" => Key::Tab,
+        "Enter" => Key::Return,
+        "Backspace" => Key::Backspace,
+        "Delete" => Key::Delete,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "PageUp" => Key::PageUp,
+        "PageDown" => Key::PageDown,
+        "Up" => Key::UpArrow,
+        "Down" => Key::DownArrow,
+        "Left" => Key::LeftArrow,
+        "Right" => Key::RightArrow,
+        "CapsLock" => Key::CapsLock,
+        "Plus" => Key::Unicode('+'),
+        "VolumeUp" => Key::VolumeUp,
+        "VolumeDown" => Key::VolumeDown,
+        "Mute" => Key::VolumeMute,
+        "PlayPause" => Key::MediaPlayPause,
+        "NextTrack" => Key::MediaNextTrack,
+        "PrevTrack" => Key::MediaPrevTrack,
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        "Insert" => Key::Insert,
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        "Pause" => Key::Pause,
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        "PrintScreen" => Key::Print,
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        "NumLock" => Key::Numlock,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// F1 to F20 everywhere, up to F24 where the OS has them. Keys past F12
+/// make good keep-alive input since hardly any application reacts to them.
+fn function_key(number: u8) -> Option<Key> {
+    let key = match number {
+        1 => Key::F1,
+        2 => Key::F2,
+        3 => Key::F3,
+        4 => Key::F4,
+        5 => Key::F5,
+        6 => Key::F6,
+        7 => Key::F7,
+        8 => Key::F8,
+        9 => Key::F9,
+        10 => Key::F10,
+        11 => Key::F11,
+        12 => Key::F12,
+        13 => Key::F13,
+        14 => Key::F14,
+        15 => Key::F15,
+        16 => Key::F16,
+        17 => Key::F17,
+        18 => Key::F18,
+        19 => Key::F19,
+        20 => Key::F20,
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        21 => Key::F21,
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        22 => Key::F22,
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        23 => Key::F23,
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        24 => Key::F24,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_uses_canonical_names() {
+        for (spec, shown) in [
+            ("control+option+pgdn", "Ctrl+Alt+PageDown"),
+            (" cmd + c ", "Super+C"),
+            ("esc", "Escape"),
+            ("f15", "F15"),
+            ("return", "Enter"),
+            ("a", "a"),
+        ] {
+            assert_eq!(KeySpec::parse(spec).unwrap().to_string(), shown, "{}", spec);
+        }
+    }
+
+    #[test]
+    fn letters_in_a_chord_name_the_key() {
+        let spec = KeySpec::parse("Ctrl+C").unwrap();
+        assert_eq!(spec.held, [Key::Control]);
+        assert_eq!(spec.key, Key::Unicode('c'));
+        // On their own they are the character
+        assert_eq!(KeySpec::parse("C").unwrap().key, Key::Unicode('C'));
+    }
+
+    #[test]
+    fn plus_can_be_written_three_ways() {
+        for spec in ["+", "Plus", "Ctrl++", "Ctrl+Plus"] {
+            assert_eq!(KeySpec::parse(spec).unwrap().key, Key::Unicode('+'), "{}", spec);
+        }
+        assert_eq!(Chord::parse("Ctrl++").unwrap().to_string(), "Ctrl++");
+    }
+
+    #[test]
+    fn parse_reports_the_bad_part() {
+        assert_eq!(KeySpec::parse("  ").unwrap_err(), "Key is empty");
+        assert_eq!(KeySpec::parse("Ctrl+").unwrap_err(), "Missing key in Ctrl+");
+        assert_eq!(KeySpec::parse("Ctrl+Nope").unwrap_err(), "Unknown key Nope in Ctrl+Nope");
+        assert_eq!(KeySpec::parse("F25").unwrap_err(), "Unknown key F25 in F25");
+    }
+}
+
@@ -1,57 +1,572 @@
+mod actions;
+mod activity;
 mod automation;
+mod config;
+mod displays;
+mod error;
+mod failsafe;
+mod focus;
+mod hotkeys;
+mod idle;
+mod input;
+mod keep_awake;
+mod keyspec;
+mod macros;
 mod process_disguise;
+mod scheduler;
+mod snippets;
 mod app_launcher;
+mod autotype;
+mod calendar;
 mod tray;
 
-use automation::{AutomationConfig, AutomationEngine};
+use activity::{Activity, ActivityLog, ActivityRecord};
+use automation::{AutomationConfig, AutomationEngine, AutomationStatus, EngineServices, EngineState, SessionLimit};
+use config::{ConfigStore, ProfileList, StoredConfig};
+use error::LupinError;
+use hotkeys::{HotkeyAction, HotkeyBackend, HotkeyConfig, HotkeyConflict, HotkeyStatus};
+use input::{InputActor, RecordingBackend};
+use keep_awake::RecordingKeepAwake;
+use macros::{MacroLibrary, MacroList, MacroProblem, MacroRunner};
 use process_disguise::ProcessDisguise;
+use scheduler::{Activation, Schedule, Trigger};
+use calendar::{CalendarSource, CalendarWindow};
+use snippets::Snippet;
 use app_launcher::AppConfig;
+use chrono::{DateTime, FixedOffset, Local, Timelike, Utc};
+use autotype::{AutoTypeRequest, AutoTyper};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tauri::Manager;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
 
 struct AppState {
     automation_engine: Arc<Mutex<Option<Arc<AutomationEngine>>>>,
+    services: EngineServices,
+    macros: Arc<MacroRunner>,
+    auto_typer: Arc<AutoTyper>,
+}
+
+impl AppState {
+    /// Start a new engine, replacing any engine that is already running. A
+    /// `session` makes the engine stop by itself.
+    async fn start_engine(&self, config: AutomationConfig, session: Option<SessionLimit>) -> Result<(), LupinError> {
+        config.validate()?;
+        let ends_at = session.map(|session| session.ends_at(Local::now())).transpose()?;
+        if self.macros.is_running() {
+            return Err(LupinError::MacroRunning);
+        }
+        if self.auto_typer.is_running() {
+            return Err(LupinError::AutoTypeRunning);
+        }
+        
+        let mut engine_lock = self.automation_engine.lock().await;
+        
+        // Stop the previous engine while holding the lock so only one ever runs
+        if let Some(previous) = engine_lock.take() {
+            previous.stop().await;
+        }
+        
+        // Create new engine and start automation in background
+        let engine = Arc::new(AutomationEngine::new(config, self.services.clone()));
+        engine.start(ends_at);
+        
+        // Store engine reference
+        *engine_lock = Some(engine);
+        
+        Ok(())
+    }
+    
+    async fn stop_engine(&self) {
+        let engine_lock = self.automation_engine.lock().await;
+        if let Some(engine) = engine_lock.as_ref() {
+            engine.stop().await;
+        }
+    }
+    
+    /// Whether an engine is running or paused
+    async fn is_running(&self) -> bool {
+        let engine_lock = self.automation_engine.lock().await;
+        engine_lock
+            .as_ref()
+            .is_some_and(|engine| matches!(engine.status().state, EngineState::Running | EngineState::Paused))
+    }
+}
+
+/// Registered global hotkeys and the ones that could not be registered
+struct Hotkeys {
+    backend: Arc<dyn HotkeyBackend>,
+    conflicts: std::sync::Mutex<Vec<HotkeyConflict>>,
+}
+
+impl Hotkeys {
+    /// Replace the registered hotkeys with `config` and the snippets' hotkeys
+    fn apply(&self, config: &HotkeyConfig, snippets: &[Snippet]) -> Result<Vec<HotkeyConflict>, LupinError> {
+        let bindings = config.bindings(snippets)?;
+        let conflicts = self.backend.register(&bindings);
+        for conflict in &conflicts {
+            println!("Hotkey {} unavailable: {}", conflict.hotkey, conflict.message);
+        }
+        *self.conflicts.lock().unwrap() = conflicts.clone();
+        Ok(conflicts)
+    }
+}
+
+/// Respond to a global hotkey. Starting and stopping emit the same events as
+/// the tray so the window follows along.
+async fn hotkey_pressed(app: &AppHandle, action: HotkeyAction) {
+    println!("Hotkey pressed: {:?}", action);
+    let state = app.state::<AppState>();
+    match action {
+        HotkeyAction::PanicStop => {
+            state.macros.stop();
+            state.auto_typer.abort();
+            state.services.input.abort();
+            state.stop_engine().await;
+            let _ = app.emit("tray-stop-automation", ());
+        }
+        HotkeyAction::Toggle if state.is_running().await => {
+            state.stop_engine().await;
+            let _ = app.emit("tray-stop-automation", ());
+        }
+        HotkeyAction::Toggle => {
+            let mut config = app.state::<ConfigStore>().get().active().automation.clone();
+            config.enabled = true;
+            match state.start_engine(config, None).await {
+                Ok(()) => {
+                    let _ = app.emit("tray-start-automation", ());
+                }
+                Err(e) => println!("Failed to start automation from hotkey: {}", e),
+            }
+        }
+        HotkeyAction::AbortType => {
+            state.auto_typer.abort();
+        }
+        HotkeyAction::Snippet(_) if state.is_running().await => {
+            println!("Not typing a snippet while automation runs");
+        }
+        HotkeyAction::Snippet(_) if state.macros.is_running() => {
+            println!("Not typing a snippet while a macro runs");
+        }
+        HotkeyAction::Snippet(index) => {
+            let stored = app.state::<ConfigStore>().get();
+            let corners = stored.active().automation.fail_safe_corners.clone();
+            match stored.snippets.get(index) {
+                Some(snippet) => {
+                    if let Err(e) = snippet.type_into_focus(&state.auto_typer, corners) {
+                        println!("Failed to type snippet: {}", e);
+                    }
+                }
+                None => println!("No snippet at index {}", index),
+            }
+        }
+        HotkeyAction::Pause => {
+            let engine_lock = state.automation_engine.lock().await;
+            if let Some(engine) = engine_lock.as_ref() {
+                if engine.pause() {
+                    let _ = app.emit("automation-paused", true);
+                } else if engine.resume() {
+                    let _ = app.emit("automation-paused", false);
+                }
+            }
+        }
+    }
+}
+
+/// Start and stop profiles as the schedule says. Runs for the life of the
+/// app, whether or not the window is shown.
+async fn run_scheduler(app: AppHandle) {
+    // The run last acted on, so each run starts the engine only once
+    let mut handled: Option<(Trigger, DateTime<FixedOffset>)> = None;
+    // Whether the running engine was started by the schedule rather than the user
+    let mut owns_engine = false;
+    loop {
+        let schedule = app.state::<ConfigStore>().get().schedule;
+        let state = app.state::<AppState>();
+        let current = if schedule.enabled {
+            schedule.current(Utc::now()).unwrap_or_else(|e| {
+                println!("Schedule is invalid: {}", e);
+                None
+            })
+        } else {
+            None
+        };
+        let running = state.is_running().await;
+
+        match current {
+            Some(run) if handled.as_ref() != Some(&(run.trigger.clone(), run.start)) => {
+                handled = Some((run.trigger.clone(), run.start));
+                let action = format!("scheduled start of {}", run.profile);
+                if running && !owns_engine {
+                    // Leave automation the user started alone
+                    println!("Skipping {}, automation is already running", action);
+                } else {
+                    match tray::start_profile(&app, &run.profile).await {
+                        Ok(()) => {
+                            owns_engine = true;
+                            let _ = app.emit("tray-start-automation", ());
+                            state.services.activity.record(Activity::Performed { action });
+                        }
+                        Err(e) => {
+                            state.services.activity.record(Activity::Failed { action, error: e.to_string() });
+                        }
+                    }
+                }
+            }
+            Some(_) => {
+                // Stopped by hand during the run; stay stopped until the next one
+                if owns_engine && !running {
+                    owns_engine = false;
+                }
+            }
+            None => {
+                handled = None;
+                if owns_engine {
+                    owns_engine = false;
+                    state.stop_engine().await;
+                    let _ = app.emit("tray-stop-automation", ());
+                    state.services.activity.record(Activity::Performed { action: "scheduled stop".to_string() });
+                }
+            }
+        }
+
+        // Rules have minute resolution, so check at the top of every minute
+        let seconds = 60 - u64::from(Utc::now().second());
+        tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+    }
+}
+
+/// Keep the tray tooltip showing the engine state and the time left in a
+/// timed session
+async fn run_tray_status(app: AppHandle) {
+    loop {
+        let status = {
+            let state = app.state::<AppState>();
+            let engine_lock = state.automation_engine.lock().await;
+            engine_lock.as_ref().map(|engine| engine.status()).unwrap_or_default()
+        };
+        tray::update_tray_tooltip(&app, &status);
+        tokio::time::sleep(tray::TOOLTIP_REFRESH).await;
+    }
 }
 
 #[tauri::command]
 async fn start_automation(
     config: AutomationConfig, 
+    session: Option<SessionLimit>,
     state: tauri::State<'_, AppState>
-) -> Result<(), String> {
-    let mut engine_lock = state.automation_engine.lock().await;
-    
-    // Create new engine
-    let engine = Arc::new(AutomationEngine::new(config));
-    let engine_clone = Arc::clone(&engine);
-    
-    // Start automation in background
-    tokio::spawn(async move {
-        engine_clone.run().await;
-    });
-    
-    // Store engine reference
-    *engine_lock = Some(engine);
-    
+) -> Result<(), LupinError> {
+    state.start_engine(config, session).await
+}
+
+#[tauri::command]
+async fn stop_automation(state: tauri::State<'_, AppState>) -> Result<(), LupinError> {
+    state.stop_engine().await;
     Ok(())
 }
 
+/// Apply `config` to the running engine without restarting it
+#[tauri::command]
+async fn update_automation_config(
+    config: AutomationConfig,
+    state: tauri::State<'_, AppState>
+) -> Result<(), LupinError> {
+    config.validate()?;
+    let engine_lock = state.automation_engine.lock().await;
+    match engine_lock.as_ref() {
+        Some(engine) if engine.status().state != EngineState::Idle => {
+            engine.update_config(config).await;
+            Ok(())
+        }
+        _ => Err(LupinError::NotRunning),
+    }
+}
+
+#[tauri::command]
+async fn pause_automation(state: tauri::State<'_, AppState>) -> Result<(), LupinError> {
+    let engine_lock = state.automation_engine.lock().await;
+    match engine_lock.as_ref() {
+        Some(engine) if engine.pause() => Ok(()),
+        _ => Err(LupinError::NotRunning),
+    }
+}
+
+#[tauri::command]
+async fn resume_automation(state: tauri::State<'_, AppState>) -> Result<(), LupinError> {
+    let engine_lock = state.automation_engine.lock().await;
+    match engine_lock.as_ref() {
+        Some(engine) if engine.resume() => Ok(()),
+        _ => Err(LupinError::NotPaused),
+    }
+}
+
 #[tauri::command]
-async fn stop_automation(state: tauri::State<'_, AppState>) -> Result<(), String> {
+async fn get_automation_status(state: tauri::State<'_, AppState>) -> Result<AutomationStatus, LupinError> {
     let engine_lock = state.automation_engine.lock().await;
-    if let Some(engine) = engine_lock.as_ref() {
-        engine.stop().await;
+    Ok(engine_lock
+        .as_ref()
+        .map(|engine| engine.status())
+        .unwrap_or_default())
+}
+
+/// Apply a change to the profile list, then bring the tray menu and the
+/// frontend up to date
+pub(crate) fn change_profiles<F>(app: &AppHandle, change: F) -> Result<StoredConfig, LupinError>
+where
+    F: FnOnce(&mut StoredConfig) -> Result<(), LupinError>,
+{
+    let stored = app.state::<ConfigStore>().update(change)?;
+    if let Err(e) = tray::refresh_tray_menu(app) {
+        println!("Failed to refresh tray menu: {}", e);
     }
+    let _ = app.emit("profiles-changed", stored.profile_list());
+    Ok(stored)
+}
+
+/// Config of the active profile
+#[tauri::command]
+fn get_config(store: tauri::State<'_, ConfigStore>) -> AutomationConfig {
+    store.get().active().automation.clone()
+}
+
+/// Save `config` to `profile`, or to the active profile when none is given
+#[tauri::command]
+fn save_config(
+    config: AutomationConfig,
+    profile: Option<String>,
+    store: tauri::State<'_, ConfigStore>
+) -> Result<(), LupinError> {
+    store.update(|stored| {
+        let target = match profile {
+            Some(name) => stored.profile_mut(&name)?,
+            None => stored.active_mut(),
+        };
+        target.automation = config;
+        Ok(())
+    })?;
     Ok(())
 }
 
+#[tauri::command]
+fn list_profiles(store: tauri::State<'_, ConfigStore>) -> ProfileList {
+    store.get().profile_list()
+}
+
+#[tauri::command]
+fn create_profile(name: String, app: AppHandle) -> Result<ProfileList, LupinError> {
+    change_profiles(&app, |stored| stored.create_profile(&name)).map(|stored| stored.profile_list())
+}
+
+#[tauri::command]
+fn rename_profile(name: String, new_name: String, app: AppHandle) -> Result<ProfileList, LupinError> {
+    change_profiles(&app, |stored| stored.rename_profile(&name, &new_name)).map(|stored| stored.profile_list())
+}
+
+#[tauri::command]
+fn duplicate_profile(name: String, new_name: String, app: AppHandle) -> Result<ProfileList, LupinError> {
+    change_profiles(&app, |stored| stored.duplicate_profile(&name, &new_name)).map(|stored| stored.profile_list())
+}
+
+#[tauri::command]
+fn delete_profile(name: String, app: AppHandle) -> Result<ProfileList, LupinError> {
+    change_profiles(&app, |stored| stored.delete_profile(&name)).map(|stored| stored.profile_list())
+}
+
+#[tauri::command]
+fn set_active_profile(name: String, app: AppHandle) -> Result<ProfileList, LupinError> {
+    change_profiles(&app, |stored| stored.set_active_profile(&name)).map(|stored| stored.profile_list())
+}
+
+/// Migrate a config the frontend kept in localStorage before settings moved to the backend
+#[tauri::command]
+fn import_legacy_config(
+    legacy: serde_json::Value,
+    store: tauri::State<'_, ConfigStore>
+) -> Result<AutomationConfig, LupinError> {
+    let imported = StoredConfig::migrate(legacy)?;
+    let stored = store.update(|stored| {
+        stored.active_mut().automation = imported.active().automation.clone();
+        Ok(())
+    })?;
+    Ok(stored.active().automation.clone())
+}
+
+/// Recent engine activity, oldest first
+#[tauri::command]
+fn get_activity_history(state: tauri::State<'_, AppState>) -> Vec<ActivityRecord> {
+    state.services.activity.history()
+}
+
+#[tauri::command]
+fn get_hotkeys(store: tauri::State<'_, ConfigStore>, hotkeys: tauri::State<'_, Hotkeys>) -> HotkeyStatus {
+    HotkeyStatus {
+        config: store.get().hotkeys,
+        conflicts: hotkeys.conflicts.lock().unwrap().clone(),
+    }
+}
+
+/// Register and persist new hotkeys. Conflicts do not stop them from being
+/// saved, since the other application may be closed later.
+#[tauri::command]
+fn set_hotkeys(
+    config: HotkeyConfig,
+    store: tauri::State<'_, ConfigStore>,
+    hotkeys: tauri::State<'_, Hotkeys>
+) -> Result<HotkeyStatus, LupinError> {
+    let conflicts = hotkeys.apply(&config, &store.get().snippets)?;
+    store.update(|stored| {
+        stored.hotkeys = config.clone();
+        Ok(())
+    })?;
+    Ok(HotkeyStatus { config, conflicts })
+}
+
+#[tauri::command]
+fn get_snippets(store: tauri::State<'_, ConfigStore>) -> Vec<Snippet> {
+    store.get().snippets
+}
+
+/// Persist the snippet library and register its hotkeys, returning the
+/// hotkeys that could not be registered
+#[tauri::command]
+fn set_snippets(
+    snippets: Vec<Snippet>,
+    store: tauri::State<'_, ConfigStore>,
+    hotkeys: tauri::State<'_, Hotkeys>
+) -> Result<Vec<HotkeyConflict>, LupinError> {
+    Snippet::validate_all(&snippets)?;
+    let conflicts = hotkeys.apply(&store.get().hotkeys, &snippets)?;
+    store.update(|stored| {
+        stored.snippets = snippets;
+        Ok(())
+    })?;
+    Ok(conflicts)
+}
+
+#[tauri::command]
+fn get_schedule(store: tauri::State<'_, ConfigStore>) -> Schedule {
+    store.get().schedule
+}
+
+#[tauri::command]
+fn set_schedule(schedule: Schedule, store: tauri::State<'_, ConfigStore>) -> Result<(), LupinError> {
+    schedule.validate(&store.get().profile_list().names)?;
+    store.update(|stored| {
+        stored.schedule = schedule;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Runs of `schedule`, or of the saved schedule when none is given, over the
+/// next two weeks
+#[tauri::command]
+fn get_schedule_preview(
+    schedule: Option<Schedule>,
+    store: tauri::State<'_, ConfigStore>
+) -> Result<Vec<Activation>, LupinError> {
+    let stored = store.get();
+    let profiles = stored.profile_list().names;
+    let schedule = schedule.unwrap_or(stored.schedule);
+    schedule.validate(&profiles)?;
+    let now = Utc::now();
+    schedule
+        .activations(now, now + chrono::Duration::days(scheduler::PREVIEW_DAYS))
+        .map_err(LupinError::config)
+}
+
+/// Matching events of one calendar over the next two weeks. Unlike the
+/// schedule preview this reports a calendar that cannot be read.
+#[tauri::command]
+fn preview_calendar(source: CalendarSource) -> Result<Vec<CalendarWindow>, LupinError> {
+    let problems = source.problems(|name| name.to_string());
+    if !problems.is_empty() {
+        return Err(LupinError::InvalidConfig { problems });
+    }
+    let now = Utc::now();
+    source
+        .windows(now, now + chrono::Duration::days(scheduler::PREVIEW_DAYS))
+        .map_err(LupinError::config)
+}
+
+#[tauri::command]
+fn list_macros(library: tauri::State<'_, MacroLibrary>) -> Result<MacroList, LupinError> {
+    library.list()
+}
+
+/// Problems with a macro file, empty when it is ready to run
+#[tauri::command]
+fn validate_macro(name: String, library: tauri::State<'_, MacroLibrary>) -> Result<Vec<MacroProblem>, LupinError> {
+    library.validate(&name)
+}
+
+/// Start a macro in the background; its outcome shows up in the activity log
+#[tauri::command]
+async fn run_macro(
+    name: String,
+    library: tauri::State<'_, MacroLibrary>,
+    store: tauri::State<'_, ConfigStore>,
+    state: tauri::State<'_, AppState>
+) -> Result<(), LupinError> {
+    if state.is_running().await {
+        return Err(LupinError::AutomationRunning);
+    }
+    if state.auto_typer.is_running() {
+        return Err(LupinError::AutoTypeRunning);
+    }
+    let parsed = library.load(&name)?;
+    let corners = store.get().active().automation.fail_safe_corners.clone();
+    state.macros.start(parsed, corners)
+}
+
+#[tauri::command]
+fn stop_macro(state: tauri::State<'_, AppState>) -> Result<(), LupinError> {
+    if state.macros.stop() {
+        Ok(())
+    } else {
+        Err(LupinError::NotRunning)
+    }
+}
+
+/// Type the clipboard or a file into the focused window after a countdown.
+/// Emits `auto-type-countdown` each second and 0 when typing starts.
+#[tauri::command]
+async fn auto_type(
+    request: AutoTypeRequest,
+    app: AppHandle,
+    store: tauri::State<'_, ConfigStore>,
+    state: tauri::State<'_, AppState>
+) -> Result<(), LupinError> {
+    request.validate()?;
+    if state.is_running().await {
+        return Err(LupinError::AutomationRunning);
+    }
+    if state.macros.is_running() {
+        return Err(LupinError::MacroRunning);
+    }
+    let text = request.read_text()?;
+    let corners = store.get().active().automation.fail_safe_corners.clone();
+    state.auto_typer.start(text, &request, corners, move |remaining| {
+        let _ = app.emit("auto-type-countdown", remaining);
+    })
+}
+
+#[tauri::command]
+fn stop_auto_type(state: tauri::State<'_, AppState>) -> Result<(), LupinError> {
+    if state.auto_typer.abort() {
+        Ok(())
+    } else {
+        Err(LupinError::NotRunning)
+    }
+}
+
 #[tauri::command]
 fn get_disguises() -> Vec<ProcessDisguise> {
     ProcessDisguise::get_available_disguises()
 }
 
 #[tauri::command]
-fn set_process_name(name: String) -> Result<(), String> {
+fn set_process_name(name: String) -> Result<(), LupinError> {
     ProcessDisguise::set_process_name(&name);
     Ok(())
 }
@@ -62,8 +577,8 @@ fn get_available_apps() -> Vec<AppConfig> {
 }
 
 #[tauri::command]
-fn launch_app(app: AppConfig) -> Result<(), String> {
-    app.launch()
+fn launch_app(app: AppConfig) -> Result<(), LupinError> {
+    app.launch().map_err(|message| LupinError::Launch { message })
 }
 
 #[tauri::command]
@@ -95,16 +610,68 @@ fn show_in_dock() {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // LUPIN_DRY_RUN logs input events and keep-awake locks instead of performing them
+    let dry_run = std::env::var_os("LUPIN_DRY_RUN").is_some();
+    let input = if dry_run {
+        InputActor::spawn_with(|| Ok(Box::new(RecordingBackend::default())))
+    } else {
+        InputActor::spawn()
+    };
+    let keep_awake: Arc<dyn keep_awake::KeepAwakeBackend> = if dry_run {
+        Arc::new(RecordingKeepAwake::default())
+    } else {
+        keep_awake::platform_backend()
+    };
+    
+    let services = EngineServices {
+        input: Arc::new(input.expect("failed to spawn input thread")),
+        keep_awake,
+        focus: focus::platform_probe(),
+        idle: idle::platform_probe(),
+        activity: Arc::new(ActivityLog::default()),
+    };
     let app_state = AppState {
         automation_engine: Arc::new(Mutex::new(None)),
+        macros: Arc::new(MacroRunner::new(services.clone())),
+        auto_typer: Arc::new(AutoTyper::new(services.clone())),
+        services,
     };
     
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             start_automation,
             stop_automation,
+            update_automation_config,
+            pause_automation,
+            resume_automation,
+            get_automation_status,
+            get_activity_history,
+            get_hotkeys,
+            set_hotkeys,
+            get_snippets,
+            set_snippets,
+            get_schedule,
+            set_schedule,
+            get_schedule_preview,
+            preview_calendar,
+            list_macros,
+            validate_macro,
+            run_macro,
+            stop_macro,
+            auto_type,
+            stop_auto_type,
+            get_config,
+            save_config,
+            list_profiles,
+            create_profile,
+            rename_profile,
+            duplicate_profile,
+            delete_profile,
+            set_active_profile,
+            import_legacy_config,
             get_disguises,
             set_process_name,
             get_available_apps,
@@ -114,6 +681,47 @@ pub fn run() {
             show_in_dock
         ])
         .setup(|app| {
+            // Load persisted settings before anything can start automation
+            let config_dir = app.path().app_config_dir()?;
+            app.manage(ConfigStore::open(config_dir.join("config.toml")));
+            app.manage(MacroLibrary::new(config_dir.join("macros")));
+            
+            // Register global hotkeys from the saved config
+            let handle = app.handle().clone();
+            let hotkeys = Hotkeys {
+                backend: hotkeys::platform_backend(Arc::new(move |action| {
+                    let app = handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        hotkey_pressed(&app, action).await;
+                    });
+                })),
+                conflicts: Default::default(),
+            };
+            let stored = app.state::<ConfigStore>().get();
+            if let Err(e) = hotkeys.apply(&stored.hotkeys, &stored.snippets) {
+                println!("Saved hotkeys are invalid: {}", e);
+            }
+            app.manage(hotkeys);
+            
+            // Forward engine activity to the frontend as it happens
+            let handle = app.handle().clone();
+            app.state::<AppState>().services.activity.set_listener(move |record| {
+                let _ = handle.emit("automation-activity", record);
+                if let Activity::FailSafe { .. } | Activity::SessionEnded = record.activity {
+                    // The engine stopped itself, likely while the window was hidden
+                    let _ = handle.emit("tray-stop-automation", ());
+                    let shown = handle
+                        .notification()
+                        .builder()
+                        .title("Lupin")
+                        .body(record.activity.to_string())
+                        .show();
+                    if let Err(e) = shown {
+                        println!("Failed to show notification: {}", e);
+                    }
+                }
+            });
+            
             // Set activation policy to accessory on startup (hide from Dock)
             #[cfg(target_os = "macos")]
             {
@@ -125,7 +733,13 @@ pub fn run() {
             }
             
             // Create system tray
-            tray::create_tray(&app.handle())?;
+            tray::create_tray(app.handle())?;
+            
+            // Follow the schedule, even while the window is hidden
+            tauri::async_runtime::spawn(run_scheduler(app.handle().clone()));
+            
+            // Show the state and remaining session time in the tray tooltip
+            tauri::async_runtime::spawn(run_tray_status(app.handle().clone()));
             
             // Setup window close behavior - hide instead of exit
             if let Some(window) = app.get_webview_window("main") {
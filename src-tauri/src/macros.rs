@@ -0,0 +1,497 @@
+//! Scripted sequences of input, stored as TOML files in the macros folder.
+//!
+//! ```toml
+//! description = "Reload the dashboard every minute"
+//!
+//! [[steps]]
+//! launch = { name = "Chrome", path = "/usr/bin/google-chrome", args = ["https://example.com"] }
+//! [[steps]]
+//! wait_for_window = { title = "Dashboard", timeout_ms = 10000 }
+//! [[steps]]
+//! [[steps.repeat.steps]]
+//! key = "F5"
+//! [[steps.repeat.steps]]
+//! wait = 60000
+//! ```
+//!
+//! Each step is a table with a single key naming what it does: `move_to`,
+//! `move_by`, `click`, `key`, `type`, `scroll`, `wait`, `wait_for_window`,
+//! `launch` or `repeat`. A `repeat` without a `count` runs until stopped, and
+//! must contain a `wait` so it cannot run flat out.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+use toml::Spanned;
+use crate::actions::{ActionContext, ActionPlanner, ClickAction, ScrollAction};
+use crate::activity::Activity;
+use crate::app_launcher::AppConfig;
+use crate::automation::EngineServices;
+use crate::error::LupinError;
+use crate::failsafe::{Client, ScreenCorner};
+use crate::input::{InputCommand, MousePath};
+use crate::keep_awake::BoxFuture;
+use crate::keyspec::KeySpec;
+
+/// How long `wait_for_window` waits when the macro does not say
+const DEFAULT_WINDOW_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often `wait_for_window` looks at the focused window
+const WINDOW_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    MoveTo { x: i32, y: i32 },
+    MoveBy { dx: i32, dy: i32 },
+    Click(ClickAction),
+    /// A key or chord such as `Ctrl+Shift+T`
+    Key(String),
+    /// Text typed one character at a time
+    Type(String),
+    Scroll(ScrollAction),
+    /// Milliseconds to do nothing
+    Wait(u64),
+    /// Wait until the focused window's title or app name contains `title`
+    WaitForWindow { title: String, timeout_ms: Option<u64> },
+    Launch(AppConfig),
+    Repeat {
+        count: Option<u32>,
+        steps: Vec<Spanned<Step>>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MacroFile {
+    description: Option<String>,
+    steps: Vec<Spanned<Step>>,
+}
+
+/// A parsed macro together with its source, so errors can point at lines
+#[derive(Debug, Clone)]
+pub struct Macro {
+    pub name: String,
+    file: MacroFile,
+    source: String,
+}
+
+/// Something wrong with a macro, located in its file
+#[derive(Debug, Clone, Serialize)]
+pub struct MacroProblem {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Macro {
+    /// Parse and check `source`, reporting every problem with its position
+    pub fn parse(name: &str, source: &str) -> Result<Self, Vec<MacroProblem>> {
+        let file: MacroFile = match toml::from_str(source) {
+            Ok(file) => file,
+            Err(e) => {
+                let span = e.span().unwrap_or(0..0);
+                return Err(vec![problem_at(source, span, e.message())]);
+            }
+        };
+        let parsed = Self {
+            name: name.to_string(),
+            file,
+            source: source.to_string(),
+        };
+
+        let mut problems = Vec::new();
+        if parsed.file.steps.is_empty() {
+            problems.push(problem_at(source, 0..0, "Macro has no steps"));
+        }
+        parsed.check(&parsed.file.steps, &mut problems);
+        if problems.is_empty() {
+            Ok(parsed)
+        } else {
+            Err(problems)
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.file.description.as_deref()
+    }
+
+    fn check(&self, steps: &[Spanned<Step>], problems: &mut Vec<MacroProblem>) {
+        for step in steps {
+            let mut report = |message: String| problems.push(problem_at(&self.source, step.span(), &message));
+            match step.get_ref() {
+                Step::Click(action) => action
+                    .validate()
+                    .into_iter()
+                    .for_each(|problem| report(format!("{}: {}", problem.field, problem.message))),
+                Step::Scroll(action) => action
+                    .validate()
+                    .into_iter()
+                    .for_each(|problem| report(format!("{}: {}", problem.field, problem.message))),
+                Step::Key(spec) => {
+                    if let Err(e) = KeySpec::parse(spec) {
+                        report(e);
+                    }
+                }
+                Step::Type(text) if text.is_empty() => report("Text to type must not be empty".to_string()),
+                Step::WaitForWindow { title, .. } if title.trim().is_empty() => {
+                    report("Window title must not be empty".to_string())
+                }
+                Step::Launch(app) if app.path.trim().is_empty() => report("App path must not be empty".to_string()),
+                Step::Repeat { count, steps } => {
+                    if *count == Some(0) {
+                        report("Repeat count must be at least 1".to_string());
+                    }
+                    if steps.is_empty() {
+                        report("Repeat has no steps".to_string());
+                    } else if count.is_none() && !contains_wait(steps) {
+                        report("A repeat without a count needs a wait step".to_string());
+                    }
+                    self.check(steps, problems);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn line_of(&self, step: &Spanned<Step>) -> usize {
+        problem_at(&self.source, step.span(), "").line
+    }
+}
+
+/// Whether running `steps` always takes a while, so repeating them forever
+/// leaves the machine room to breathe
+fn contains_wait(steps: &[Spanned<Step>]) -> bool {
+    steps.iter().any(|step| match step.get_ref() {
+        Step::Wait(ms) => *ms > 0,
+        Step::Repeat { steps, .. } => contains_wait(steps),
+        _ => false,
+    })
+}
+
+/// Turn a byte range in `source` into a line and column, both counted from 1
+fn problem_at(source: &str, span: Range<usize>, message: &str) -> MacroProblem {
+    let before = &source[..span.start.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    MacroProblem {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+        message: message.to_string(),
+    }
+}
+
+/// A macro file as shown in the list
+#[derive(Debug, Clone, Serialize)]
+pub struct MacroInfo {
+    pub name: String,
+    pub description: Option<String>,
+    /// Empty when the macro is ready to run
+    pub problems: Vec<MacroProblem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MacroList {
+    /// Folder the macros are read from
+    pub dir: String,
+    pub macros: Vec<MacroInfo>,
+}
+
+/// The folder of `*.toml` macro files, named after their file stem
+pub struct MacroLibrary {
+    dir: PathBuf,
+}
+
+impl MacroLibrary {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn list(&self) -> Result<MacroList, LupinError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries.filter_map(Result::ok).collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(LupinError::config(format!("Failed to read {}: {}", self.dir.display(), e))),
+        };
+
+        let mut macros: Vec<MacroInfo> = entries
+            .iter()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_string_lossy().into_owned();
+                let info = match self.read(&name).map(|source| Macro::parse(&name, &source)) {
+                    Ok(Ok(parsed)) => MacroInfo {
+                        description: parsed.description().map(str::to_string),
+                        name,
+                        problems: Vec::new(),
+                    },
+                    Ok(Err(problems)) => MacroInfo { name, description: None, problems },
+                    Err(e) => MacroInfo {
+                        name,
+                        description: None,
+                        problems: vec![MacroProblem { line: 0, column: 0, message: e.to_string() }],
+                    },
+                };
+                Some(info)
+            })
+            .collect();
+        macros.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(MacroList {
+            dir: self.dir.display().to_string(),
+            macros,
+        })
+    }
+
+    /// Problems with the macro called `name`, empty when it is valid
+    pub fn validate(&self, name: &str) -> Result<Vec<MacroProblem>, LupinError> {
+        let source = self.read(name)?;
+        Ok(Macro::parse(name, &source).err().unwrap_or_default())
+    }
+
+    pub fn load(&self, name: &str) -> Result<Macro, LupinError> {
+        let source = self.read(name)?;
+        Macro::parse(name, &source).map_err(|problems| LupinError::InvalidMacro {
+            name: name.to_string(),
+            problems,
+        })
+    }
+
+    fn read(&self, name: &str) -> Result<String, LupinError> {
+        // Names come from the frontend, so never let them leave the folder
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(LupinError::MacroNotFound { name: name.to_string() });
+        }
+        let path = self.dir.join(format!("{}.toml", name));
+        fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => LupinError::MacroNotFound { name: name.to_string() },
+            _ => LupinError::config(format!("Failed to read {}: {}", path.display(), e)),
+        })
+    }
+}
+
+/// Why a macro run ended early
+enum Interrupted {
+    Stopped,
+    Failed { line: usize, error: String },
+}
+
+/// Runs one macro at a time through the engine's input layer
+pub struct MacroRunner {
+    services: EngineServices,
+    current: Mutex<Option<CancellationToken>>,
+}
+
+impl MacroRunner {
+    pub fn new(services: EngineServices) -> Self {
+        Self {
+            services,
+            current: Mutex::new(None),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.current.lock().unwrap().is_some()
+    }
+
+    /// Run `parsed` in the background, guarded by the fail-safe `corners`.
+    /// The outcome is recorded in the activity log.
+    pub fn start(self: &Arc<Self>, parsed: Macro, corners: Vec<ScreenCorner>) -> Result<(), LupinError> {
+        let cancel = CancellationToken::new();
+        {
+            let mut current = self.current.lock().unwrap();
+            if current.is_some() {
+                return Err(LupinError::MacroRunning);
+            }
+            *current = Some(cancel.clone());
+        }
+
+        let runner = Arc::clone(self);
+        tokio::spawn(async move {
+            let fail_safe = runner.services.input.fail_safe();
+            let mut trips = fail_safe.subscribe();
+            fail_safe.arm(Client::Macro, corners);
+
+            let action = format!("macro {}", parsed.name);
+            let mut executor = Executor {
+                services: &runner.services,
+                parsed: &parsed,
+                cancel: &cancel,
+                rng: StdRng::from_entropy(),
+                steps: 0,
+            };
+            let result = tokio::select! {
+                result = executor.run(&parsed.file.steps) => result,
+                Ok(_) = trips.recv() => Err(Interrupted::Stopped),
+            };
+            let steps = executor.steps;
+            runner.services.input.fail_safe().disarm(Client::Macro);
+            *runner.current.lock().unwrap() = None;
+
+            println!("Macro {} finished after {} steps", parsed.name, steps);
+            let activity = match result {
+                Ok(()) => Activity::Performed { action },
+                Err(Interrupted::Stopped) => Activity::Aborted { action, steps },
+                Err(Interrupted::Failed { line, error }) => Activity::Failed {
+                    action,
+                    error: format!("line {}: {}", line, error),
+                },
+            };
+            runner.services.activity.record(activity);
+        });
+        Ok(())
+    }
+
+    /// Stop the running macro. Returns false if none was running.
+    pub fn stop(&self) -> bool {
+        match self.current.lock().unwrap().as_ref() {
+            Some(cancel) => {
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+struct Executor<'a> {
+    services: &'a EngineServices,
+    parsed: &'a Macro,
+    cancel: &'a CancellationToken,
+    rng: StdRng,
+    /// Steps completed so far, counting every pass through a repeat
+    steps: usize,
+}
+
+impl Executor<'_> {
+    fn run<'s>(&'s mut self, steps: &'s [Spanned<Step>]) -> BoxFuture<'s, Result<(), Interrupted>> {
+        Box::pin(async move {
+            for step in steps {
+                if self.cancel.is_cancelled() {
+                    return Err(Interrupted::Stopped);
+                }
+                if let Step::Repeat { count, steps } = step.get_ref() {
+                    let mut pass = 0;
+                    while count.is_none_or(|count| pass < count) {
+                        self.run(steps).await?;
+                        pass += 1;
+                    }
+                    continue;
+                }
+                self.perform(step.get_ref()).await.map_err(|error| match error {
+                    Some(error) => Interrupted::Failed { line: self.parsed.line_of(step), error },
+                    None => Interrupted::Stopped,
+                })?;
+                self.steps += 1;
+            }
+            Ok(())
+        })
+    }
+
+    /// Run one step other than a repeat. `Err(None)` means the macro was stopped.
+    async fn perform(&mut self, step: &Step) -> Result<(), Option<String>> {
+        let command = match step {
+            Step::MoveTo { x, y } => InputCommand::MoveTo { x: *x, y: *y, seed: self.rng.gen() },
+            Step::MoveBy { dx, dy } => InputCommand::MovePath(MousePath::smooth(*dx, *dy, &mut self.rng)),
+            Step::Click(action) => self.plan(action).map_err(Some)?,
+            Step::Scroll(action) => self.plan(action).map_err(Some)?,
+            Step::Key(spec) => InputCommand::KeyChord(KeySpec::parse(spec).map_err(Some)?),
+            Step::Type(text) => InputCommand::type_text(text, &mut self.rng),
+            Step::Wait(ms) => return self.wait(Duration::from_millis(*ms)).await,
+            Step::WaitForWindow { title, timeout_ms } => {
+                let timeout = timeout_ms.map_or(DEFAULT_WINDOW_TIMEOUT, Duration::from_millis);
+                return self.wait_for_window(title, timeout).await;
+            }
+            Step::Launch(app) => return app.launch().map_err(Some),
+            Step::Repeat { .. } => unreachable!("repeats are unrolled by run"),
+        };
+
+        let progress = self.services.input.execute(command, self.cancel).await.map_err(Some)?;
+        if progress.aborted {
+            return Err(None);
+        }
+        Ok(())
+    }
+
+    fn plan(&mut self, action: &dyn ActionPlanner) -> Result<InputCommand, String> {
+        let mut ctx = ActionContext {
+            rng: &mut self.rng,
+            text_position: 0,
+        };
+        Ok(action.plan(&mut ctx)?.command)
+    }
+
+    async fn wait(&self, duration: Duration) -> Result<(), Option<String>> {
+        tokio::select! {
+            _ = sleep(duration) => Ok(()),
+            _ = self.cancel.cancelled() => Err(None),
+        }
+    }
+
+    async fn wait_for_window(&self, title: &str, timeout: Duration) -> Result<(), Option<String>> {
+        let wanted = title.to_lowercase();
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Ok(Some(window)) = self.services.focus.focused_window() {
+                let found = window.app.to_lowercase().contains(&wanted)
+                    || window.title.is_some_and(|title| title.to_lowercase().contains(&wanted));
+                if found {
+                    return Ok(());
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(Some(format!("No window matching \"{}\" within {:?}", title, timeout)));
+            }
+            self.wait(WINDOW_POLL).await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(source: &str) -> Vec<(usize, usize, String)> {
+        Macro::parse("test", source)
+            .unwrap_err()
+            .into_iter()
+            .map(|problem| (problem.line, problem.column, problem.message))
+            .collect()
+    }
+
+    #[test]
+    fn toml_errors_point_at_the_bad_token() {
+        let found = problems("[[steps]]\nwait = 100\n\n[[steps]]\nkey = F5\n");
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].0, found[0].1), (5, 7));
+    }
+
+    #[test]
+    fn step_problems_point_at_their_step() {
+        let source = "description = \"nested\"\n\n[[steps]]\nwait = 100\n\n[[steps]]\nrepeat = { count = 2, steps = [\n  { wait = 10 },\n  { key = \"Ctrl+Nope\" },\n] }\n";
+        assert_eq!(problems(source), [(9, 3, "Unknown key Nope in Ctrl+Nope".to_string())]);
+    }
+
+    #[test]
+    fn endless_repeats_need_a_wait() {
+        let source = "[[steps]]\nrepeat = { steps = [{ key = \"F15\" }, { wait = 0 }] }\n";
+        // A step written as its own table is reported at its header
+        assert_eq!(problems(source), [(1, 1, "A repeat without a count needs a wait step".to_string())]);
+
+        let nested = "[[steps]]\nrepeat = { steps = [{ key = \"F15\" }, { repeat = { count = 2, steps = [{ wait = 500 }] } }] }\n";
+        assert!(Macro::parse("test", nested).is_ok());
+    }
+
+    #[test]
+    fn empty_macros_are_reported_at_the_start() {
+        assert_eq!(problems("steps = []\n"), [(1, 1, "Macro has no steps".to_string())]);
+    }
+}
+
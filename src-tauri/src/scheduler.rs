@@ -0,0 +1,534 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use crate::calendar::CalendarSource;
+use crate::error::{FieldError, LupinError};
+
+/// How far ahead `get_schedule_preview` looks
+pub const PREVIEW_DAYS: i64 = 14;
+
+/// Longest run a cron rule may ask for
+const MAX_DURATION_MINUTES: u32 = 7 * 24 * 60;
+
+/// When a profile should run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleRule {
+    /// Every week on `days` from `start` to `end`, both `HH:MM` wall-clock
+    /// times. An `end` at or before `start` runs past midnight.
+    Window {
+        profile: String,
+        days: Vec<Weekday>,
+        start: String,
+        end: String,
+    },
+    /// From every minute the five-field cron `expression` matches, for
+    /// `duration_minutes`
+    Cron {
+        profile: String,
+        expression: String,
+        duration_minutes: u32,
+    },
+}
+
+impl ScheduleRule {
+    pub fn profile(&self) -> &str {
+        match self {
+            ScheduleRule::Window { profile, .. } | ScheduleRule::Cron { profile, .. } => profile,
+        }
+    }
+}
+
+/// Rules that start and stop profiles, persisted with the rest of the config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    #[serde(default)]
+    pub enabled: bool,
+    /// IANA name such as `Europe/Berlin`; the system time zone when unset
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<ScheduleRule>,
+    /// Calendars whose events add runs on top of the rules
+    #[serde(default)]
+    pub calendars: Vec<CalendarSource>,
+}
+
+/// What caused a run. Rules sort before calendars, so they win where runs overlap.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    /// Index into `Schedule::rules`
+    Rule { index: usize },
+    /// Index into `Schedule::calendars`, and the event's title
+    Calendar { index: usize, event: String },
+}
+
+/// One run of a profile. Times carry the offset in force at that moment,
+/// so runs on either side of a DST change show their own offsets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Activation {
+    pub trigger: Trigger,
+    pub profile: String,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+}
+
+impl Schedule {
+    /// Check every rule, reporting all problems at once
+    pub fn validate(&self, profiles: &[String]) -> Result<(), LupinError> {
+        let mut problems = Vec::new();
+        if let Err(message) = Zone::parse(self.timezone.as_deref()) {
+            problems.push(FieldError::new("timezone", message));
+        }
+        for (index, rule) in self.rules.iter().enumerate() {
+            let field = |name: &str| format!("rules[{}].{}", index, name);
+            if !profiles.iter().any(|profile| profile == rule.profile()) {
+                problems.push(FieldError::new(field("profile"), format!("Profile {} does not exist", rule.profile())));
+            }
+            match rule {
+                ScheduleRule::Window { days, start, end, .. } => {
+                    if days.is_empty() {
+                        problems.push(FieldError::new(field("days"), "Pick at least one day"));
+                    }
+                    let start_time = parse_time(start).map_err(|e| problems.push(FieldError::new(field("start"), e)));
+                    let end_time = parse_time(end).map_err(|e| problems.push(FieldError::new(field("end"), e)));
+                    if let (Ok(start_time), Ok(end_time)) = (start_time, end_time) {
+                        if start_time == end_time {
+                            problems.push(FieldError::new(field("end"), "Window must not start and end at the same time"));
+                        }
+                    }
+                }
+                ScheduleRule::Cron { expression, duration_minutes, .. } => {
+                    if let Err(e) = Cron::parse(expression) {
+                        problems.push(FieldError::new(field("expression"), e));
+                    }
+                    if !(1..=MAX_DURATION_MINUTES).contains(duration_minutes) {
+                        problems.push(FieldError::new(
+                            field("duration_minutes"),
+                            format!("Duration must be between 1 and {} minutes", MAX_DURATION_MINUTES),
+                        ));
+                    }
+                }
+            }
+        }
+        for (index, calendar) in self.calendars.iter().enumerate() {
+            let field = |name: &str| format!("calendars[{}].{}", index, name);
+            if !profiles.contains(&calendar.profile) {
+                problems.push(FieldError::new(field("profile"), format!("Profile {} does not exist", calendar.profile)));
+            }
+            problems.extend(calendar.problems(field));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(LupinError::InvalidConfig { problems })
+        }
+    }
+
+    /// Runs overlapping `from..until`, earliest first. A calendar that cannot
+    /// be read is left out, so one broken file does not stop the rest.
+    pub fn activations(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<Activation>, String> {
+        let zone = Zone::parse(self.timezone.as_deref())?;
+        let mut activations = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            let mut push = |start: DateTime<FixedOffset>, end: DateTime<FixedOffset>| {
+                if end > from && start < until {
+                    activations.push(Activation {
+                        trigger: Trigger::Rule { index },
+                        profile: rule.profile().to_string(),
+                        start,
+                        end,
+                    });
+                }
+            };
+            match rule {
+                ScheduleRule::Window { days, start, end, .. } => {
+                    let (start, end) = (parse_time(start)?, parse_time(end)?);
+                    // Start a day early for windows that run past midnight
+                    let mut date = zone.local(from).date() - Duration::days(1);
+                    while date <= zone.local(until).date() {
+                        if days.contains(&date.weekday()) {
+                            let end_date = if end <= start { date + Duration::days(1) } else { date };
+                            push(zone.resolve(date.and_time(start)), zone.resolve(end_date.and_time(end)));
+                        }
+                        date += Duration::days(1);
+                    }
+                }
+                ScheduleRule::Cron { expression, duration_minutes, .. } => {
+                    let cron = Cron::parse(expression)?;
+                    let duration = Duration::minutes((*duration_minutes).into());
+                    let mut minute = zone.local(from - duration).with_second(0).and_then(|time| time.with_nanosecond(0)).unwrap();
+                    let last = zone.local(until);
+                    while minute <= last {
+                        match cron.next_candidate(minute) {
+                            Some(skip_to) => minute = skip_to,
+                            None => {
+                                // Minutes skipped by a DST jump never happen, so they never fire
+                                if let Some(start) = zone.exact(minute) {
+                                    push(start, start + duration);
+                                }
+                                minute += Duration::minutes(1);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for (index, calendar) in self.calendars.iter().enumerate() {
+            match calendar.windows(from, until) {
+                Ok(windows) => activations.extend(windows.into_iter().map(|window| Activation {
+                    trigger: Trigger::Calendar { index, event: window.title },
+                    profile: calendar.profile.clone(),
+                    start: window.start,
+                    end: window.end,
+                })),
+                Err(e) => println!("Skipping calendar {}: {}", calendar.path, e),
+            }
+        }
+        activations.sort_by_key(|activation| activation.start);
+        Ok(activations)
+    }
+
+    /// The run in progress at `now`. Where runs overlap the earlier trigger wins.
+    pub fn current(&self, now: DateTime<Utc>) -> Result<Option<Activation>, String> {
+        Ok(self
+            .activations(now, now + Duration::seconds(1))?
+            .into_iter()
+            .filter(|activation| activation.start <= now)
+            .min_by(|a, b| a.trigger.cmp(&b.trigger)))
+    }
+}
+
+pub(crate) fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| format!("Invalid time {}, expected HH:MM", time))
+}
+
+/// The time zone rules are evaluated in
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Zone {
+    System,
+    Named(Tz),
+}
+
+impl Zone {
+    pub(crate) fn parse(name: Option<&str>) -> Result<Self, String> {
+        match name.map(str::trim) {
+            None | Some("") => Ok(Zone::System),
+            Some(name) => name
+                .parse::<Tz>()
+                .map(Zone::Named)
+                .map_err(|_| format!("Unknown time zone {}", name)),
+        }
+    }
+
+    /// Wall-clock time at `at`
+    pub(crate) fn local(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::System => at.with_timezone(&chrono::Local).naive_local(),
+            Zone::Named(tz) => at.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// The instant a wall-clock time names, or `None` if a DST jump skips it.
+    /// Times repeated when clocks go back take their first occurrence.
+    fn exact(&self, local: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        fn first<T: TimeZone>(result: LocalResult<DateTime<T>>) -> Option<DateTime<FixedOffset>> {
+            result.earliest().map(|time| time.fixed_offset())
+        }
+        match self {
+            Zone::System => first(chrono::Local.from_local_datetime(&local)),
+            Zone::Named(tz) => first(tz.from_local_datetime(&local)),
+        }
+    }
+
+    /// Like `exact`, but a time skipped by a DST jump moves to the first
+    /// moment after the jump, so a window starting at 02:30 still opens
+    pub(crate) fn resolve(&self, local: NaiveDateTime) -> DateTime<FixedOffset> {
+        (0..=12)
+            .find_map(|quarter| self.exact(local + Duration::minutes(15 * quarter)))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&local).fixed_offset())
+    }
+}
+
+/// A standard five-field cron expression: minute, hour, day of month, month
+/// and day of week (0 or 7 is Sunday). Fields take `*`, lists, ranges and
+/// `/` steps, e.g. `*/15 9-17 * * 1-5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether day of month and day of week were given as `*`. When both are
+    /// restricted a day matching either one counts, as in classic cron.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "Cron expression {} needs five fields: minute, hour, day of month, month and day of week",
+                expression
+            ));
+        };
+        let mut weekday_bits = Self::field(weekdays, 0, 7, "day of week")?;
+        // Sunday may be written as 7
+        if weekday_bits & 1 << 7 != 0 {
+            weekday_bits = (weekday_bits & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            minutes: Self::field(minutes, 0, 59, "minute")?,
+            hours: Self::field(hours, 0, 23, "hour")?,
+            days: Self::field(days, 1, 31, "day of month")?,
+            months: Self::field(months, 1, 12, "month")?,
+            weekdays: weekday_bits,
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+
+    /// Values one field allows, as a bit set
+    fn field(spec: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+        let invalid = || format!("Invalid {} field {}", name, spec);
+        let number = |text: &str| {
+            text.parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("{} {} is outside {}-{}", name, text, min, max))
+        };
+        let mut bits = 0u64;
+        for item in spec.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u32>().ok().filter(|&step| step > 0).ok_or_else(invalid)?),
+                None => (item, 1),
+            };
+            let (first, last) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((first, last)) => (number(first)?, number(last)?),
+                    // `5/10` runs from 5 to the end of the range
+                    None if item.contains('/') => (number(range)?, max),
+                    None => (number(range)?, number(range)?),
+                },
+            };
+            if first > last {
+                return Err(invalid());
+            }
+            for value in (first..=last).step_by(step as usize) {
+                bits |= 1 << value;
+            }
+        }
+        Ok(bits)
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let by_day = self.days & 1 << date.day() != 0;
+        let by_weekday = self.weekdays & 1 << date.weekday().num_days_from_sunday() != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => by_day || by_weekday,
+            (false, true) => by_day,
+            (true, false) => by_weekday,
+            (true, true) => true,
+        }
+    }
+
+    /// `None` if the expression matches `minute`, else a later minute to
+    /// continue searching from, skipping whole days and hours that cannot match
+    fn next_candidate(&self, minute: NaiveDateTime) -> Option<NaiveDateTime> {
+        let date = minute.date();
+        if self.months & 1 << date.month() == 0 || !self.day_matches(date) {
+            return Some((date + Duration::days(1)).and_time(NaiveTime::MIN));
+        }
+        if self.hours & 1 << minute.hour() == 0 {
+            let hour_start = minute.with_minute(0).unwrap();
+            return Some(hour_start + Duration::hours(1));
+        }
+        if self.minutes & 1 << minute.minute() == 0 {
+            return Some(minute + Duration::minutes(1));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BERLIN: Zone = Zone::Named(chrono_tz::Europe::Berlin);
+
+    fn local(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        local(value).and_utc()
+    }
+
+    fn schedule(timezone: &str, rules: Vec<ScheduleRule>) -> Schedule {
+        Schedule { enabled: true, timezone: Some(timezone.to_string()), rules, calendars: Vec::new() }
+    }
+
+    fn cron(expression: &str, duration_minutes: u32) -> ScheduleRule {
+        ScheduleRule::Cron { profile: "Work".to_string(), expression: expression.to_string(), duration_minutes }
+    }
+
+    fn window(profile: &str, days: Vec<Weekday>, start: &str, end: &str) -> ScheduleRule {
+        ScheduleRule::Window { profile: profile.to_string(), days, start: start.to_string(), end: end.to_string() }
+    }
+
+    fn starts(activations: &[Activation]) -> Vec<DateTime<Utc>> {
+        activations.iter().map(|activation| activation.start.with_timezone(&Utc)).collect()
+    }
+
+    #[test]
+    fn resolve_moves_starts_out_of_spring_forward_gaps() {
+        // Berlin skips 02:00-03:00 on 2024-03-31 and repeats 02:00-03:00 on 2024-10-27
+        let cases = [
+            ("2024-03-31 01:59", "2024-03-31 00:59", 3600),
+            ("2024-03-31 02:00", "2024-03-31 01:00", 7200),
+            ("2024-03-31 02:30", "2024-03-31 01:00", 7200),
+            ("2024-03-31 03:00", "2024-03-31 01:00", 7200),
+            ("2024-10-27 02:30", "2024-10-27 00:30", 7200),
+            ("2024-10-27 03:00", "2024-10-27 02:00", 3600),
+        ];
+        for (wall_clock, instant, offset) in cases {
+            let resolved = BERLIN.resolve(local(wall_clock));
+            assert_eq!(resolved.with_timezone(&Utc), utc(instant), "{}", wall_clock);
+            assert_eq!(resolved.offset().local_minus_utc(), offset, "{}", wall_clock);
+        }
+    }
+
+    #[test]
+    fn exact_drops_skipped_times_and_takes_the_first_repeat() {
+        let cases = [
+            ("2024-03-31 02:30", None),
+            ("2024-03-31 03:30", Some("2024-03-31 01:30")),
+            ("2024-10-27 02:30", Some("2024-10-27 00:30")),
+        ];
+        for (wall_clock, instant) in cases {
+            let exact = BERLIN.exact(local(wall_clock)).map(|time| time.with_timezone(&Utc));
+            assert_eq!(exact, instant.map(utc), "{}", wall_clock);
+        }
+    }
+
+    #[test]
+    fn cron_skips_missing_minutes_and_fires_repeated_ones_once() {
+        let nightly = schedule("Europe/Berlin", vec![cron("30 2 * * *", 10)]);
+        let cases = [
+            // The 31st has no 02:30
+            ("2024-03-30 00:00", "2024-04-02 00:00", vec!["2024-03-30 01:30", "2024-04-01 00:30"]),
+            // The 27th has two, and only the first fires
+            ("2024-10-26 00:00", "2024-10-28 02:00", vec!["2024-10-26 00:30", "2024-10-27 00:30", "2024-10-28 01:30"]),
+        ];
+        for (from, until, expected) in cases {
+            let activations = nightly.activations(utc(from), utc(until)).unwrap();
+            assert_eq!(starts(&activations), expected.into_iter().map(utc).collect::<Vec<_>>(), "{}", from);
+        }
+    }
+
+    #[test]
+    fn windows_run_past_midnight() {
+        let late = schedule("UTC", vec![window("Work", vec![Weekday::Fri], "22:00", "02:00")]);
+        // 2024-01-05 is a Friday; a search starting Saturday still sees Friday's window
+        for from in ["2024-01-05 00:00", "2024-01-06 00:00"] {
+            let activations = late.activations(utc(from), utc("2024-01-08 00:00")).unwrap();
+            assert_eq!(activations.len(), 1, "{}", from);
+            assert_eq!(activations[0].start.with_timezone(&Utc), utc("2024-01-05 22:00"));
+            assert_eq!(activations[0].end.with_timezone(&Utc), utc("2024-01-06 02:00"));
+        }
+        let cases = [("2024-01-05 21:59", false), ("2024-01-05 23:00", true), ("2024-01-06 01:59", true), ("2024-01-06 02:00", false)];
+        for (now, running) in cases {
+            assert_eq!(late.current(utc(now)).unwrap().is_some(), running, "{}", now);
+        }
+    }
+
+    #[test]
+    fn cron_parse_accepts_standard_fields() {
+        let minutes = |expression: &str| Cron::parse(expression).unwrap().minutes;
+        assert_eq!(Cron::parse("0 9 * * 7"), Cron::parse("0 9 * * 0"));
+        assert_eq!(minutes("5/10 * * * *"), [5, 15, 25, 35, 45, 55].iter().fold(0, |bits, minute| bits | 1 << minute));
+        assert_eq!(minutes("0-10/5,59 * * * *"), 1 | 1 << 5 | 1 << 10 | 1 << 59);
+        assert_eq!(minutes("*/30 * * * *"), 1 | 1 << 30);
+    }
+
+    #[test]
+    fn cron_parse_rejects_bad_expressions() {
+        let cases = [
+            ("0 9 * *", "Cron expression 0 9 * * needs five fields"),
+            ("0 9 * * 1 2", "Cron expression 0 9 * * 1 2 needs five fields"),
+            ("60 * * * *", "minute 60 is outside 0-59"),
+            ("0 24 * * *", "hour 24 is outside 0-23"),
+            ("0 0 0 * *", "day of month 0 is outside 1-31"),
+            ("0 0 * 13 *", "month 13 is outside 1-12"),
+            ("0 0 * * 8", "day of week 8 is outside 0-7"),
+            ("10-5 * * * *", "Invalid minute field 10-5"),
+            ("*/0 * * * *", "Invalid minute field */0"),
+        ];
+        for (expression, message) in cases {
+            let error = Cron::parse(expression).unwrap_err();
+            assert!(error.starts_with(message), "{}: {}", expression, error);
+        }
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either_one() {
+        let date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap();
+        // 2024-09-13 is a Friday the 13th, 2024-01-12 a Friday, 2024-01-13 a Saturday
+        let cases = [
+            ("0 0 13 * 5", [true, true, true, false]),
+            ("0 0 13 * *", [true, false, true, false]),
+            ("0 0 * * 5", [true, true, false, false]),
+            ("0 0 * * *", [true, true, true, true]),
+        ];
+        for (expression, expected) in cases {
+            let cron = Cron::parse(expression).unwrap();
+            let matches = ["2024-09-13", "2024-01-12", "2024-01-13", "2024-01-11"].map(|day| cron.day_matches(date(day)));
+            assert_eq!(matches, expected, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn current_prefers_rules_over_calendars() {
+        let folder = std::env::temp_dir().join(format!("lupin-schedule-test-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let calendar = folder.join("meetings.ics");
+        std::fs::write(
+            &calendar,
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:sync\nSUMMARY:Sync\nDTSTART:20240105T090000Z\nDTEND:20240105T120000Z\nEND:VEVENT\nEND:VCALENDAR\n",
+        )
+        .unwrap();
+
+        let mut overlapping = schedule(
+            "UTC",
+            vec![
+                window("Focus", vec![Weekday::Fri], "10:00", "11:00"),
+                window("Work", vec![Weekday::Fri], "08:00", "17:00"),
+            ],
+        );
+        overlapping.calendars.push(CalendarSource {
+            path: calendar.to_string_lossy().into_owned(),
+            profile: "Meetings".to_string(),
+            categories: Vec::new(),
+            title_pattern: None,
+        });
+        let running = |now: &str, schedule: &Schedule| schedule.current(utc(now)).unwrap().map(|activation| (activation.trigger, activation.profile));
+
+        let cases = [
+            ("2024-01-05 09:30", Trigger::Rule { index: 1 }, "Work"),
+            ("2024-01-05 10:30", Trigger::Rule { index: 0 }, "Focus"),
+        ];
+        for (now, trigger, profile) in cases {
+            assert_eq!(running(now, &overlapping), Some((trigger, profile.to_string())), "{}", now);
+        }
+        // With no rule running the calendar takes over
+        overlapping.rules.clear();
+        let calendar_only = running("2024-01-05 09:30", &overlapping);
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(calendar_only, Some((Trigger::Calendar { index: 0, event: "Sync".to_string() }, "Meetings".to_string())));
+    }
+}
@@ -0,0 +1,256 @@
+use chrono::{DateTime, Local};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::autotype::{read_clipboard, AutoTyper};
+use crate::error::{FieldError, LupinError};
+use crate::failsafe::ScreenCorner;
+use crate::input::InputCommand;
+
+/// Snippets are boilerplate the user would otherwise type by hand, so they go
+/// out much faster than the engine's human-looking typing
+const SNIPPET_WPM: u32 = 300;
+
+/// Time for the user to let go of the hotkey's modifiers, which would
+/// otherwise turn the first characters into shortcuts
+const RELEASE_DELAY: Duration = Duration::from_millis(400);
+
+/// A value filled in when the snippet is typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    /// Today as `2024-05-31`
+    Date,
+    /// The current time as `14:05`
+    Time,
+    Clipboard,
+}
+
+impl Placeholder {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "date" => Some(Placeholder::Date),
+            "time" => Some(Placeholder::Time),
+            "clipboard" => Some(Placeholder::Clipboard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// Split `body` into text and placeholders. `{{` and `}}` stand for literal braces.
+fn parse_body(body: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = body.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => {
+                            return Err(format!(
+                                "Unterminated placeholder {{{}, close it with }} or write {{{{ for a literal brace",
+                                name
+                            ))
+                        }
+                    }
+                }
+                let placeholder = Placeholder::parse(&name)
+                    .ok_or_else(|| format!("Unknown placeholder {{{}}}, use {{date}}, {{time}} or {{clipboard}}", name))?;
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Placeholder(placeholder));
+            }
+            '}' => return Err("Unmatched }, write }} for a literal brace".to_string()),
+            ch => text.push(ch),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+/// Reusable text such as a signature or ticket template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    /// May contain `{date}`, `{time}` and `{clipboard}`
+    pub body: String,
+    /// Accelerator such as `Ctrl+Alt+1` that types the snippet
+    #[serde(default)]
+    pub hotkey: Option<String>,
+}
+
+impl Snippet {
+    /// Fill in the placeholders. The clipboard is only read when the body uses it.
+    pub fn expand(&self, now: DateTime<Local>) -> Result<String, String> {
+        let mut expanded = String::new();
+        for part in parse_body(&self.body)? {
+            match part {
+                Part::Text(text) => expanded.push_str(&text),
+                Part::Placeholder(Placeholder::Date) => expanded.push_str(&now.format("%Y-%m-%d").to_string()),
+                Part::Placeholder(Placeholder::Time) => expanded.push_str(&now.format("%H:%M").to_string()),
+                Part::Placeholder(Placeholder::Clipboard) => expanded.push_str(&read_clipboard()?),
+            }
+        }
+        Ok(expanded)
+    }
+
+    /// Check names and bodies. Hotkeys are checked when they are registered,
+    /// together with the global ones.
+    pub fn validate_all(snippets: &[Snippet]) -> Result<(), LupinError> {
+        let mut problems = Vec::new();
+        for (index, snippet) in snippets.iter().enumerate() {
+            if snippet.name.trim().is_empty() {
+                problems.push(FieldError::new(format!("snippets[{}].name", index), "Snippet name must not be empty"));
+            } else if snippets[..index].iter().any(|other| other.name == snippet.name) {
+                problems.push(FieldError::new(
+                    format!("snippets[{}].name", index),
+                    format!("A snippet named {} already exists", snippet.name),
+                ));
+            }
+            if snippet.body.is_empty() {
+                problems.push(FieldError::new(format!("snippets[{}].body", index), "Snippet body must not be empty"));
+            } else if let Err(message) = parse_body(&snippet.body) {
+                problems.push(FieldError::new(format!("snippets[{}].body", index), message));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(LupinError::InvalidConfig { problems })
+        }
+    }
+
+    /// Expand the snippet and type it into the focused window through
+    /// `typer`, so abort-type stops it. The outcome is recorded in the
+    /// activity log.
+    pub fn type_into_focus(&self, typer: &Arc<AutoTyper>, corners: Vec<ScreenCorner>) -> Result<(), LupinError> {
+        let snippet = self.clone();
+        typer.start_after(format!("snippet {}", self.name), RELEASE_DELAY, corners, move || {
+            let text = snippet.expand(Local::now())?;
+            Ok(InputCommand::type_paced(&text, SNIPPET_WPM, &mut StdRng::from_entropy()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::Activity;
+    use crate::automation::tests::{fast_config, services, wait_for_activity};
+    use crate::automation::{AutomationConfig, AutomationEngine, EngineState};
+    use crate::failsafe::POLL_INTERVAL;
+    use tokio::time::sleep;
+    use crate::input::{InputEvent, RecordingBackend};
+    use chrono::TimeZone;
+
+    fn snippet(body: &str) -> Snippet {
+        Snippet {
+            name: "test".to_string(),
+            body: body.to_string(),
+            hotkey: None,
+        }
+    }
+
+    #[test]
+    fn expand_fills_in_placeholders_and_literal_braces() {
+        let now = Local.with_ymd_and_hms(2024, 5, 31, 14, 5, 0).unwrap();
+        let expanded = snippet("{{ok}} on {date} at {time}").expand(now).unwrap();
+        assert_eq!(expanded, "{ok} on 2024-05-31 at 14:05");
+    }
+
+    #[test]
+    fn parse_body_rejects_broken_placeholders() {
+        assert_eq!(
+            parse_body("Hi {date").unwrap_err(),
+            "Unterminated placeholder {date, close it with } or write {{ for a literal brace"
+        );
+        assert!(parse_body("Hi {name}").unwrap_err().starts_with("Unknown placeholder {name}"));
+        assert!(parse_body("Hi }").unwrap_err().starts_with("Unmatched }"));
+    }
+
+    #[tokio::test]
+    async fn snippets_share_the_auto_type_slot() {
+        let backend = RecordingBackend::default();
+        let services = services(&backend);
+        let activity = Arc::clone(&services.activity);
+        let typer = Arc::new(AutoTyper::new(services));
+
+        snippet("first").type_into_focus(&typer, vec![]).unwrap();
+        assert!(matches!(
+            snippet("second").type_into_focus(&typer, vec![]),
+            Err(LupinError::AutoTypeRunning)
+        ));
+        // Abort-type stops a snippet before it starts typing
+        assert!(typer.abort());
+        let history = wait_for_activity(&activity, 1).await;
+        assert!(matches!(history[0].activity, Activity::Aborted { steps: 0, .. }));
+
+        snippet("ok").type_into_focus(&typer, vec![]).unwrap();
+        let history = wait_for_activity(&activity, 2).await;
+        assert!(matches!(history[1].activity, Activity::Performed { .. }), "{}", history[1].activity);
+        let typed: String = backend
+            .events()
+            .into_iter()
+            .filter_map(|event| match event {
+                InputEvent::Text(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(typed, "ok");
+    }
+
+    #[tokio::test]
+    async fn engine_fail_safe_outlives_a_snippet() {
+        let backend = RecordingBackend::default();
+        let services = services(&backend);
+        let activity = Arc::clone(&services.activity);
+        let config = AutomationConfig {
+            min_interval_ms: 600_000,
+            max_interval_ms: 600_000,
+            fail_safe_corners: vec![ScreenCorner::TopLeft],
+            ..fast_config("[]")
+        };
+        let engine = Arc::new(AutomationEngine::with_rng(config, services.clone(), StdRng::seed_from_u64(1)));
+        let typer = Arc::new(AutoTyper::new(services));
+
+        engine.start(None);
+        while engine.status().next_fire_at.is_none() {
+            sleep(POLL_INTERVAL).await;
+        }
+        snippet("ok").type_into_focus(&typer, vec![ScreenCorner::BottomRight]).unwrap();
+        let history = wait_for_activity(&activity, 1).await;
+        assert!(matches!(history[0].activity, Activity::Performed { .. }), "{}", history[0].activity);
+
+        // The snippet disarmed only its own corner
+        backend.set_cursor(0, 0);
+        for _ in 0..100 {
+            if engine.status().state == EngineState::Idle {
+                return;
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+        panic!("engine still {:?} with the cursor in its corner", engine.status().state);
+    }
+}
@@ -1,14 +1,40 @@
 use tauri::{
-    AppHandle, Manager, Emitter,
-    menu::{Menu, MenuItemBuilder, PredefinedMenuItem},
+    AppHandle, Manager, Emitter, Wry,
+    menu::{CheckMenuItemBuilder, Menu, MenuItemBuilder, PredefinedMenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
 };
+use std::time::Duration;
+use chrono::Local;
+use crate::AppState;
+use crate::automation::{AutomationStatus, EngineState, SessionLimit};
+use crate::config::ConfigStore;
+use crate::error::LupinError;
 
 #[cfg(target_os = "macos")]
 use cocoa::appkit::NSApplication;
 
-pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
-    // Create tray menu
+/// Id of the tray icon, used to find it again when the menu changes
+const TRAY_ID: &str = "main";
+
+/// Menu ids of profile entries are this prefix followed by the profile name
+const PROFILE_ID_PREFIX: &str = "profile:";
+
+/// Menu ids of "Run for…" entries are this prefix followed by the minutes
+const RUN_FOR_ID_PREFIX: &str = "run_for:";
+
+/// Session lengths offered in the tray, in minutes
+const RUN_FOR_PRESETS: [(u32, &str); 5] = [
+    (30, "30 Minutes"),
+    (60, "1 Hour"),
+    (90, "90 Minutes"),
+    (120, "2 Hours"),
+    (240, "4 Hours"),
+];
+
+/// How often the tooltip's remaining time is brought up to date
+pub(crate) const TOOLTIP_REFRESH: Duration = Duration::from_secs(15);
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
     let menu = Menu::new(app)?;
     
     // Add menu items
@@ -21,6 +47,25 @@ pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
         .build(app)?;
     let stop_automation = MenuItemBuilder::with_id("stop_automation", "Stop Automation")
         .build(app)?;
+    
+    // Start the active profile for a fixed time
+    let run_for = Submenu::with_id(app, "run_for", "Run for…", true)?;
+    for (minutes, label) in RUN_FOR_PRESETS {
+        let item = MenuItemBuilder::with_id(format!("{}{}", RUN_FOR_ID_PREFIX, minutes), label)
+            .build(app)?;
+        run_for.append(&item)?;
+    }
+    
+    // One entry per profile; picking one makes it active and starts it
+    let profiles = Submenu::with_id(app, "profiles", "Profiles", true)?;
+    let stored = app.state::<ConfigStore>().get();
+    for profile in &stored.profiles {
+        let item = CheckMenuItemBuilder::with_id(format!("{}{}", PROFILE_ID_PREFIX, profile.name), &profile.name)
+            .checked(profile.name == stored.active_profile)
+            .build(app)?;
+        profiles.append(&item)?;
+    }
+    
     let separator2 = PredefinedMenuItem::separator(app)?;
     let quit_item = MenuItemBuilder::with_id("quit", "Quit Lupin")
         .build(app)?;
@@ -30,11 +75,42 @@ pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
     menu.append(&separator)?;
     menu.append(&start_automation)?;
     menu.append(&stop_automation)?;
+    menu.append(&run_for)?;
+    menu.append(&profiles)?;
     menu.append(&separator2)?;
     menu.append(&quit_item)?;
     
+    Ok(menu)
+}
+
+/// Rebuild the tray menu, e.g. after profiles were added or renamed
+pub fn refresh_tray_menu(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(build_menu(app)?))?;
+    }
+    Ok(())
+}
+
+/// Make `name` the active profile and start automation with it
+pub(crate) async fn start_profile(app: &AppHandle, name: &str) -> Result<(), LupinError> {
+    let stored = crate::change_profiles(app, |stored| stored.set_active_profile(name))?;
+    let mut config = stored.active().automation.clone();
+    config.enabled = true;
+    app.state::<AppState>().start_engine(config, None).await
+}
+
+/// Start automation from the persisted config so this works without the window
+async fn start_active_profile(app: &AppHandle, session: Option<SessionLimit>) -> Result<(), LupinError> {
+    let mut config = app.state::<ConfigStore>().get().active().automation.clone();
+    config.enabled = true;
+    app.state::<AppState>().start_engine(config, session).await
+}
+
+pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+    
     // Build tray with menu
-    let _tray = TrayIconBuilder::new()
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
         .menu(&menu)
         .tooltip("Lupin - Anti-surveillance Tool")
         .icon(app.default_window_icon().unwrap().clone())
@@ -72,17 +148,52 @@ pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
                     }
                 }
                 "start_automation" => {
-                    // Emit event to frontend
-                    let _ = app.emit("tray-start-automation", ());
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        match start_active_profile(&app, None).await {
+                            // Emit event to frontend
+                            Ok(()) => {
+                                let _ = app.emit("tray-start-automation", ());
+                            }
+                            Err(e) => println!("Failed to start automation from tray: {}", e),
+                        }
+                    });
                 }
                 "stop_automation" => {
-                    // Emit event to frontend
-                    let _ = app.emit("tray-stop-automation", ());
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        app.state::<AppState>().stop_engine().await;
+                        // Emit event to frontend
+                        let _ = app.emit("tray-stop-automation", ());
+                    });
                 }
                 "quit" => {
                     app.exit(0);
                 }
-                _ => {}
+                id => {
+                    if let Some(minutes) = id.strip_prefix(RUN_FOR_ID_PREFIX).and_then(|m| m.parse().ok()) {
+                        let app = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            match start_active_profile(&app, Some(SessionLimit::Duration { minutes })).await {
+                                Ok(()) => {
+                                    let _ = app.emit("tray-start-automation", ());
+                                }
+                                Err(e) => println!("Failed to run automation for {} minutes from tray: {}", minutes, e),
+                            }
+                        });
+                    } else if let Some(name) = id.strip_prefix(PROFILE_ID_PREFIX) {
+                        let app = app.clone();
+                        let name = name.to_string();
+                        tauri::async_runtime::spawn(async move {
+                            match start_profile(&app, &name).await {
+                                Ok(()) => {
+                                    let _ = app.emit("tray-start-automation", ());
+                                }
+                                Err(e) => println!("Failed to start profile {} from tray: {}", name, e),
+                            }
+                        });
+                    }
+                }
             }
         })
         .on_tray_icon_event(|tray, event| {
@@ -142,12 +253,28 @@ pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
     Ok(())
 }
 
-#[allow(dead_code)]
-pub fn update_tray_tooltip(_app: &AppHandle, is_active: bool) {
-    let status = if is_active { "Active" } else { "Inactive" };
-    let _tooltip = format!("Lupin - {}", status);
-    
-    // Note: In Tauri v2, we need to get the tray instance
-    // This is a simplified version - in production you'd store the tray reference
-    // and update it directly
+/// Show the engine state in the tray tooltip, with the time left in a timed session
+pub fn update_tray_tooltip(app: &AppHandle, status: &AutomationStatus) {
+    let state = match status.state {
+        EngineState::Running => "Active",
+        EngineState::Paused => "Paused",
+        EngineState::Idle | EngineState::Stopping => "Inactive",
+    };
+    let tooltip = match status.ends_at {
+        Some(ends_at) => {
+            // Round up so the last minute reads "1m left" rather than "0m left"
+            let minutes = ((ends_at - Local::now()).num_seconds().max(0) + 59) / 60;
+            if minutes >= 60 {
+                format!("Lupin - {}, {}h {}m left", state, minutes / 60, minutes % 60)
+            } else {
+                format!("Lupin - {}, {}m left", state, minutes)
+            }
+        }
+        None => format!("Lupin - {}", state),
+    };
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+            println!("Failed to update tray tooltip: {}", e);
+        }
+    }
 }
\ No newline at end of file
@@ -3,6 +3,11 @@ import "./App.css";
 import AutomationPanel from "./components/AutomationPanel";
 // import ProcessDisguise from "./components/ProcessDisguise";  // TODO: Implement actual process disguise functionality
 import AppLauncher from "./components/AppLauncher";
+import HotkeySettings from "./components/HotkeySettings";
+import MacroPanel from "./components/MacroPanel";
+import AutoTypePanel from "./components/AutoTypePanel";
+import SnippetPanel from "./components/SnippetPanel";
+import SchedulePanel from "./components/SchedulePanel";
 import StatusBar from "./components/StatusBar";
 import { useTranslation } from "./hooks/useTranslation";
 import { useTrayEvents } from "./hooks/useTrayEvents";
@@ -36,48 +41,10 @@ function App() {
     checkFirstLaunch();
   }, []);
 
-  // Listen for tray events
+  // Listen for tray and hotkey events; the backend has already started or stopped the engine
   useTrayEvents(
-    async () => {
-      // Start automation from tray using saved config
-      try {
-        // Load saved config from localStorage
-        const savedConfig = localStorage.getItem('lupin-automation-config');
-        let config = {
-          enabled: true,
-          min_interval_ms: 30000,
-          max_interval_ms: 840000,
-          mouse_movement_range: 300,
-          enable_clicks: false,
-          enable_keyboard: false,
-          keyboard_text: "Working on the project...",
-          active_apps: []
-        };
-        
-        if (savedConfig) {
-          try {
-            const parsed = JSON.parse(savedConfig);
-            config = { ...parsed, enabled: true };
-          } catch (e) {
-            console.error('Failed to parse saved config:', e);
-          }
-        }
-        
-        await invoke("start_automation", { config });
-        setIsAutomationActive(true);
-      } catch (error) {
-        console.error("Failed to start automation from tray:", error);
-      }
-    },
-    async () => {
-      // Stop automation from tray
-      try {
-        await invoke("stop_automation");
-        setIsAutomationActive(false);
-      } catch (error) {
-        console.error("Failed to stop automation from tray:", error);
-      }
-    }
+    () => setIsAutomationActive(true),
+    () => setIsAutomationActive(false)
   );
 
   return (
@@ -103,6 +70,11 @@ function App() {
           
           <div className="right-panel">
             <AppLauncher />
+            <HotkeySettings />
+            <MacroPanel />
+            <AutoTypePanel />
+            <SnippetPanel />
+            <SchedulePanel />
           </div>
           
           {/* TODO: Implement actual process disguise functionality
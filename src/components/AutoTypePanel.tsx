@@ -0,0 +1,133 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useTranslation } from "../hooks/useTranslation";
+import { describeError } from "../errors";
+
+// Mirrors AutoTypeRequest in src-tauri/src/autotype.rs
+type AutoTypeSource = { type: 'clipboard' } | { type: 'file'; path: string };
+
+interface ActivityRecord {
+  kind: string;
+  action?: string;
+  error?: string;
+}
+
+export default function AutoTypePanel() {
+  const { t } = useTranslation();
+  const [sourceType, setSourceType] = useState<'clipboard' | 'file'>('clipboard');
+  const [path, setPath] = useState('');
+  const [wpm, setWpm] = useState(60);
+  const [countdown, setCountdown] = useState(5);
+  const [running, setRunning] = useState(false);
+  const [remaining, setRemaining] = useState<number | null>(null);
+  const [outcome, setOutcome] = useState<string | null>(null);
+
+  useEffect(() => {
+    const unsubscribe = listen<number>('auto-type-countdown', (event) => {
+      setRemaining(event.payload > 0 ? event.payload : null);
+    });
+    return () => {
+      unsubscribe.then(fn => fn());
+    };
+  }, []);
+
+  // Typing reports how it ended through the activity log
+  useEffect(() => {
+    const unsubscribe = listen<ActivityRecord>('automation-activity', (event) => {
+      const record = event.payload;
+      if (!record.action?.startsWith('auto-type')) {
+        return;
+      }
+      setRunning(false);
+      setRemaining(null);
+      setOutcome(record.kind === 'failed'
+        ? `${record.action}: ${record.error}`
+        : t(`autoType.outcome.${record.kind}`));
+    });
+    return () => {
+      unsubscribe.then(fn => fn());
+    };
+  }, [t]);
+
+  const handleStart = async () => {
+    const source: AutoTypeSource = sourceType === 'file' ? { type: 'file', path } : { type: 'clipboard' };
+    try {
+      await invoke("auto_type", { request: { source, wpm, countdown_secs: countdown } });
+      setRunning(true);
+      setOutcome(null);
+    } catch (error) {
+      console.error("Failed to start auto-type:", error);
+      alert(describeError(error, t));
+    }
+  };
+
+  const handleStop = async () => {
+    try {
+      await invoke("stop_auto_type");
+    } catch (error) {
+      console.error("Failed to stop auto-type:", error);
+    }
+  };
+
+  return (
+    <div className="panel auto-type-panel">
+      <h2>{t('autoType.title')}</h2>
+      <p className="section-description">{t('autoType.description')}</p>
+      <select
+        value={sourceType}
+        onChange={(e) => setSourceType(e.target.value as 'clipboard' | 'file')}
+        disabled={running}
+        style={{ width: '100%', padding: '6px', marginBottom: '8px' }}
+      >
+        <option value="clipboard">{t('autoType.clipboard')}</option>
+        <option value="file">{t('autoType.file')}</option>
+      </select>
+      {sourceType === 'file' && (
+        <input
+          type="text"
+          placeholder={t('autoType.pathPlaceholder')}
+          value={path}
+          onChange={(e) => setPath(e.target.value)}
+          disabled={running}
+          style={{ width: '100%', marginBottom: '8px' }}
+        />
+      )}
+      <div style={{ display: 'flex', gap: '8px', marginBottom: '8px' }}>
+        <label style={{ flex: 1, fontSize: '12px' }}>
+          {t('autoType.wpm')}
+          <input
+            type="number"
+            min="10"
+            max="400"
+            value={wpm}
+            onChange={(e) => setWpm(Number(e.target.value))}
+            disabled={running}
+            style={{ width: '100%' }}
+          />
+        </label>
+        <label style={{ flex: 1, fontSize: '12px' }}>
+          {t('autoType.countdown')}
+          <input
+            type="number"
+            min="0"
+            max="60"
+            value={countdown}
+            onChange={(e) => setCountdown(Number(e.target.value))}
+            disabled={running}
+            style={{ width: '100%' }}
+          />
+        </label>
+      </div>
+      {running ? (
+        <button onClick={handleStop}>{t('autoType.stop')}</button>
+      ) : (
+        <button onClick={handleStart}>{t('autoType.start')}</button>
+      )}
+      {remaining !== null && (
+        <p style={{ fontSize: '13px' }}>{t('autoType.startsIn').replace('{seconds}', String(remaining))}</p>
+      )}
+      {outcome && <p style={{ fontSize: '13px' }}>{outcome}</p>}
+    </div>
+  );
+}
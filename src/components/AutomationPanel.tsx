@@ -1,16 +1,77 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useTranslation } from "../hooks/useTranslation";
+import ProfileSelector, { ProfileList } from "./ProfileSelector";
+import { FieldError, isLupinError } from "../errors";
+
+interface Action {
+  type: string;
+  [param: string]: any;
+}
+
+interface WeightedAction {
+  enabled: boolean;
+  weight: number;
+  action: Action;
+}
+
+type AutomationMode = 'simulate' | 'keep_awake' | 'both';
+
+type ScreenCorner = 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right';
+
+const SCREEN_CORNERS: ScreenCorner[] = ['top_left', 'top_right', 'bottom_left', 'bottom_right'];
+
+interface Rect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+// Where mouse moves may take the cursor
+type MoveBounds = { type: 'current_monitor' } | ({ type: 'region' } & Rect);
+
+type MouseButton = 'left' | 'middle' | 'right';
+
+// Where to move before clicking; null clicks where the cursor is
+type ClickTarget = { type: 'point'; x: number; y: number } | ({ type: 'region' } & Rect);
+
+const MOUSE_BUTTONS: MouseButton[] = ['left', 'middle', 'right'];
+const DEFAULT_ZONE: Rect = { x: 0, y: 0, width: 200, height: 50 };
+
+type ScrollAxis = 'vertical' | 'horizontal';
+type ScrollPattern = 'random' | 'down' | 'up' | 'down_then_up';
+
+const SCROLL_AXES: ScrollAxis[] = ['vertical', 'horizontal'];
+const SCROLL_PATTERNS: ScrollPattern[] = ['random', 'down', 'up', 'down_then_up'];
+
+const DEFAULT_REGION: MoveBounds = { type: 'region', x: 0, y: 0, width: 1920, height: 1080 };
+const REGION_FIELDS: (keyof Rect)[] = ['x', 'y', 'width', 'height'];
 
 interface AutomationConfig {
   enabled: boolean;
+  mode: AutomationMode;
   min_interval_ms: number;
   max_interval_ms: number;
-  mouse_movement_range: number;
-  enable_clicks: boolean;
-  enable_keyboard: boolean;
-  keyboard_text: string;
+  idle_threshold_ms: number | null;
+  actions: WeightedAction[];
   active_apps: string[];
+  fail_safe_corners: ScreenCorner[];
+}
+
+// Mirrors SessionLimit in src-tauri/src/automation.rs
+type SessionLimit =
+  | { type: 'duration'; minutes: number }
+  | { type: 'until'; time: string };
+
+interface ActivityRecord {
+  at: string;
+  kind: 'performed' | 'aborted' | 'failed' | 'skipped' | 'fail_safe' | 'session_ended';
+  action?: string;
+  reason?: string;
+  app?: string | null;
+  corner?: ScreenCorner;
 }
 
 interface AutomationPanelProps {
@@ -21,56 +82,185 @@ interface AutomationPanelProps {
 export default function AutomationPanel({ isActive, onToggle }: AutomationPanelProps) {
   const { t } = useTranslation();
   
-  // Load saved config from localStorage or use defaults
-  const loadSavedConfig = (): AutomationConfig => {
-    const saved = localStorage.getItem('lupin-automation-config');
-    if (saved) {
+  const [config, setConfig] = useState<AutomationConfig>({
+    enabled: false,
+    mode: 'simulate',
+    min_interval_ms: 30000,
+    max_interval_ms: 840000,
+    idle_threshold_ms: null,
+    actions: [],
+    active_apps: [],
+    fail_safe_corners: ['top_left']
+  });
+  const [isLoaded, setIsLoaded] = useState(false);
+  const [profiles, setProfiles] = useState<ProfileList>({ active: '', names: [] });
+  // Profile the current config belongs to, so edits are never saved into another one
+  const [profile, setProfile] = useState<string | null>(null);
+  const profileRef = useRef<string | null>(null);
+  const [isPaused, setIsPaused] = useState(false);
+  const [problems, setProblems] = useState<FieldError[]>([]);
+  const [lastActivity, setLastActivity] = useState<ActivityRecord | null>(null);
+  // Only applies to the next start; null runs until stopped
+  const [session, setSession] = useState<SessionLimit | null>(null);
+  
+  const findAction = (type: string) => config.actions.find(entry => entry.action.type === type);
+  
+  const updateAction = (type: string, patch: Partial<WeightedAction>, params: Partial<Action> = {}) => {
+    setConfig(prev => ({
+      ...prev,
+      actions: prev.actions.map(entry =>
+        entry.action.type === type
+          ? { ...entry, ...patch, action: { ...entry.action, ...params } }
+          : entry
+      )
+    }));
+  };
+  
+  const mouseRange = findAction('mouse_move')?.action.range ?? 300;
+  const mouseBounds: MoveBounds = findAction('mouse_move')?.action.bounds ?? { type: 'current_monitor' };
+  const restoreCursor: boolean = findAction('mouse_move')?.action.restore_cursor ?? false;
+  const keyboardText = findAction('text')?.action.text ?? '';
+  const scroll = findAction('scroll');
+  const click = findAction('click');
+  const clickTarget: ClickTarget | null = click?.action.target ?? null;
+  const noClickZones: Rect[] = click?.action.no_click_zones ?? [];
+  
+  const rectFields = (rect: Rect, onChange: (rect: Rect) => void) => (
+    <div style={{ display: 'flex', gap: '8px', marginTop: '8px' }}>
+      {REGION_FIELDS.map(field => (
+        <label key={field} style={{ flex: 1, fontSize: '12px' }}>
+          {t(`automation.region.${field}`)}
+          <input
+            type="number"
+            value={rect[field]}
+            onChange={(e) => onChange({ ...rect, [field]: Number(e.target.value) })}
+            style={{ width: '100%', padding: '4px' }}
+          />
+        </label>
+      ))}
+    </div>
+  );
+  
+  const showProfile = (list: ProfileList, loaded: AutomationConfig) => {
+    profileRef.current = list.active;
+    setProfiles(list);
+    setProfile(list.active);
+    setConfig(loaded);
+  };
+  
+  // Load the config persisted by the backend, importing any copy left in localStorage
+  useEffect(() => {
+    const loadConfig = async () => {
       try {
-        return JSON.parse(saved);
-      } catch (e) {
-        console.error('Failed to parse saved config:', e);
+        const legacy = localStorage.getItem('lupin-automation-config');
+        let loaded: AutomationConfig;
+        if (legacy) {
+          loaded = await invoke<AutomationConfig>("import_legacy_config", { legacy: JSON.parse(legacy) });
+          localStorage.removeItem('lupin-automation-config');
+        } else {
+          loaded = await invoke<AutomationConfig>("get_config");
+        }
+        showProfile(await invoke<ProfileList>("list_profiles"), loaded);
+      } catch (error) {
+        console.error("Failed to load config:", error);
       }
-    }
-    // Default config
-    return {
-      enabled: false,
-      min_interval_ms: 30000,
-      max_interval_ms: 840000,
-      mouse_movement_range: 300,
-      enable_clicks: false,
-      enable_keyboard: false,
-      keyboard_text: "I'm working on the project documentation. Need to review the latest changes and update the specifications accordingly. The implementation looks good so far.",
-      active_apps: []
+      setIsLoaded(true);
     };
-  };
+    loadConfig();
+  }, []);
   
-  const [config, setConfig] = useState<AutomationConfig>(loadSavedConfig());
+  // Show what the engine last did, including ticks it skipped
+  useEffect(() => {
+    const unsubscribe = listen<ActivityRecord>('automation-activity', (event) => {
+      setLastActivity(event.payload);
+    });
+    return () => {
+      unsubscribe.then(fn => fn());
+    };
+  }, []);
   
-  // Save config whenever it changes
+  // Follow pause and resume from the global hotkey
   useEffect(() => {
-    localStorage.setItem('lupin-automation-config', JSON.stringify(config));
-  }, [config]);
+    const unsubscribe = listen<boolean>('automation-paused', (event) => {
+      setIsPaused(event.payload);
+    });
+    return () => {
+      unsubscribe.then(fn => fn());
+    };
+  }, []);
+  
+  // Follow profile changes made here or from the tray
+  useEffect(() => {
+    const unsubscribe = listen<ProfileList>('profiles-changed', async (event) => {
+      const list = event.payload;
+      if (list.active === profileRef.current) {
+        setProfiles(list);
+        return;
+      }
+      try {
+        showProfile(list, await invoke<AutomationConfig>("get_config"));
+      } catch (error) {
+        console.error("Failed to load profile:", error);
+      }
+    });
+    return () => {
+      unsubscribe.then(fn => fn());
+    };
+  }, []);
+  
+  // Save config shortly after it stops changing, and apply it to the running engine
+  useEffect(() => {
+    if (!isLoaded || profile === null) {
+      return;
+    }
+    const timer = setTimeout(() => {
+      invoke("save_config", { config, profile }).catch(error => console.error("Failed to save config:", error));
+      if (isActive) {
+        invoke("update_automation_config", { config })
+          .then(() => setProblems([]))
+          .catch(error => {
+            console.error("Failed to update running automation:", error);
+            if (isLupinError(error) && error.kind === 'invalid_config') {
+              setProblems(error.problems);
+            }
+          });
+      }
+    }, 500);
+    return () => clearTimeout(timer);
+  }, [config, profile, isLoaded, isActive]);
 
   const handleToggle = async () => {
     const newState = !isActive;
     const newConfig = { 
       ...config, 
-      enabled: newState,
-      // Ensure keyboard_text is sent as Some(String) to Rust
-      keyboard_text: config.keyboard_text || "Working on the project..."
+      enabled: newState
     };
     
     try {
       if (newState) {
         console.log("Starting automation with config:", newConfig);
-        await invoke("start_automation", { config: newConfig });
+        await invoke("start_automation", { config: newConfig, session });
       } else {
         await invoke("stop_automation");
       }
       setConfig(newConfig);
+      setIsPaused(false);
+      setProblems([]);
       onToggle(newState);
     } catch (error) {
       console.error("Failed to toggle automation:", error);
+      if (isLupinError(error) && error.kind === 'invalid_config') {
+        setProblems(error.problems);
+      }
+    }
+  };
+
+  const handlePauseToggle = async () => {
+    try {
+      await invoke(isPaused ? "resume_automation" : "pause_automation");
+      setIsPaused(!isPaused);
+    } catch (error) {
+      console.error("Failed to pause/resume automation:", error);
     }
   };
 
@@ -96,8 +286,94 @@ export default function AutomationPanel({ isActive, onToggle }: AutomationPanelP
         >
           {isActive ? t('automation.stopButton') : t('automation.startButton')} Automation
         </button>
+        {isActive && (
+          <button 
+            className={`toggle-button ${isPaused ? '' : 'active'}`}
+            onClick={handlePauseToggle}
+            style={{ marginTop: '8px' }}
+          >
+            {isPaused ? t('automation.resumeButton') : t('automation.pauseButton')}
+          </button>
+        )}
+        {!isActive && (
+          <div style={{ display: 'flex', gap: '8px', marginTop: '8px', alignItems: 'center', fontSize: '13px' }}>
+            <select
+              value={session?.type ?? 'none'}
+              onChange={(e) => setSession(
+                e.target.value === 'duration' ? { type: 'duration', minutes: 90 }
+                  : e.target.value === 'until' ? { type: 'until', time: '17:30' }
+                  : null
+              )}
+              style={{ flex: 1, padding: '6px' }}
+            >
+              <option value="none">{t('automation.sessionNone')}</option>
+              <option value="duration">{t('automation.sessionDuration')}</option>
+              <option value="until">{t('automation.sessionUntil')}</option>
+            </select>
+            {session?.type === 'duration' && (
+              <input
+                type="number"
+                min="1"
+                value={session.minutes}
+                onChange={(e) => setSession({ type: 'duration', minutes: Number(e.target.value) })}
+                style={{ flex: 1, padding: '6px' }}
+                title={t('automation.sessionMinutes')}
+              />
+            )}
+            {session?.type === 'until' && (
+              <input
+                type="time"
+                value={session.time}
+                onChange={(e) => setSession({ type: 'until', time: e.target.value })}
+                style={{ flex: 1, padding: '6px' }}
+              />
+            )}
+          </div>
+        )}
+        {/* A fail-safe or session-end stop stays visible after automation has stopped */}
+        {lastActivity && (isActive || lastActivity.kind === 'fail_safe' || lastActivity.kind === 'session_ended') && (
+          <div className="last-activity" style={{ marginTop: '8px', fontSize: '12px', opacity: 0.7 }}>
+            {new Date(lastActivity.at).toLocaleTimeString()}{' '}
+            {lastActivity.kind === 'skipped'
+              ? `${t('automation.skipped')}: ${t(`automation.skipReason.${lastActivity.reason}`)}${lastActivity.app ? ` (${lastActivity.app})` : ''}`
+              : lastActivity.kind === 'fail_safe'
+                ? t('automation.failSafeTriggered').replace('{corner}', t(`automation.corners.${lastActivity.corner}`))
+                : lastActivity.kind === 'session_ended'
+                  ? t('automation.sessionEnded')
+                  : `${lastActivity.kind}: ${lastActivity.action}`}
+          </div>
+        )}
+        {problems.length > 0 && (
+          <div className="validation-errors" style={{ marginTop: '8px', fontSize: '13px', color: '#ff8a80' }}>
+            {t('errors.invalidConfig')}
+            <ul style={{ margin: '4px 0 0', paddingLeft: '20px' }}>
+              {problems.map(problem => (
+                <li key={problem.field}>{problem.message} <code>{problem.field}</code></li>
+              ))}
+            </ul>
+          </div>
+        )}
       </div>
 
+      <ProfileSelector profiles={profiles} disabled={isActive} />
+
+      <div className="config-section">
+        <h3>{t('automation.mode')}</h3>
+        <p className="subsection-description">
+          {t('automation.modeDescription')}
+        </p>
+        <select
+          value={config.mode}
+          onChange={(e) => setConfig(prev => ({ ...prev, mode: e.target.value as AutomationMode }))}
+          style={{ width: '100%', padding: '6px' }}
+        >
+          <option value="simulate">{t('automation.modeSimulate')}</option>
+          <option value="keep_awake">{t('automation.modeKeepAwake')}</option>
+          <option value="both">{t('automation.modeBoth')}</option>
+        </select>
+      </div>
+
+      {config.mode !== 'keep_awake' && (
       <div className="config-section">
         <h3>{t('automation.intervalSettings')}</h3>
         <p className="subsection-description">
@@ -112,7 +388,6 @@ export default function AutomationPanel({ isActive, onToggle }: AutomationPanelP
               max="600"
               value={config.min_interval_ms / 1000}
               onChange={(e) => updateInterval(Number(e.target.value), config.max_interval_ms / 1000)}
-              disabled={isActive}
             />
           </label>
           <label>
@@ -123,7 +398,6 @@ export default function AutomationPanel({ isActive, onToggle }: AutomationPanelP
               max="900"
               value={config.max_interval_ms / 1000}
               onChange={(e) => updateInterval(config.min_interval_ms / 1000, Number(e.target.value))}
-              disabled={isActive}
             />
           </label>
         </div>
@@ -136,29 +410,163 @@ export default function AutomationPanel({ isActive, onToggle }: AutomationPanelP
           <label className="checkbox-label">
             <input 
               type="checkbox"
-              checked={config.enable_clicks}
-              onChange={(e) => setConfig(prev => ({ ...prev, enable_clicks: e.target.checked }))}
-              disabled={isActive}
+              checked={findAction('click')?.enabled ?? false}
+              onChange={(e) => updateAction('click', { enabled: e.target.checked })}
             />
             {t('automation.enableClicks')}
           </label>
+          {click?.enabled && (
+            <div style={{ margin: '8px 0 12px' }}>
+              <div style={{ display: 'flex', gap: '8px' }}>
+                <label style={{ flex: 1, fontSize: '12px' }}>
+                  {t('automation.clickButton')}
+                  <select
+                    value={click.action.button ?? 'left'}
+                    onChange={(e) => updateAction('click', {}, { button: e.target.value as MouseButton })}
+                    style={{ width: '100%', padding: '4px' }}
+                  >
+                    {MOUSE_BUTTONS.map(button => (
+                      <option key={button} value={button}>{t(`automation.mouseButtons.${button}`)}</option>
+                    ))}
+                  </select>
+                </label>
+                <label style={{ flex: 1, fontSize: '12px' }}>
+                  {t('automation.clickCount')}
+                  <input
+                    type="number"
+                    min="1"
+                    max="3"
+                    value={click.action.count ?? 1}
+                    onChange={(e) => updateAction('click', {}, { count: Number(e.target.value) })}
+                    style={{ width: '100%', padding: '4px' }}
+                  />
+                </label>
+                <label style={{ flex: 2, fontSize: '12px' }}>
+                  {t('automation.clickTarget')}
+                  <select
+                    value={clickTarget?.type ?? 'cursor'}
+                    onChange={(e) => updateAction('click', {}, {
+                      target: e.target.value === 'point' ? { type: 'point', x: 0, y: 0 }
+                        : e.target.value === 'region' ? DEFAULT_REGION
+                        : null
+                    })}
+                    style={{ width: '100%', padding: '4px' }}
+                  >
+                    <option value="cursor">{t('automation.clickTargets.cursor')}</option>
+                    <option value="point">{t('automation.clickTargets.point')}</option>
+                    <option value="region">{t('automation.clickTargets.region')}</option>
+                  </select>
+                </label>
+              </div>
+              {clickTarget?.type === 'point' && (
+                <div style={{ display: 'flex', gap: '8px', marginTop: '8px' }}>
+                  {(['x', 'y'] as const).map(field => (
+                    <label key={field} style={{ flex: 1, fontSize: '12px' }}>
+                      {t(`automation.region.${field}`)}
+                      <input
+                        type="number"
+                        value={clickTarget[field]}
+                        onChange={(e) => updateAction('click', {}, {
+                          target: { ...clickTarget, [field]: Number(e.target.value) }
+                        })}
+                        style={{ width: '100%', padding: '4px' }}
+                      />
+                    </label>
+                  ))}
+                </div>
+              )}
+              {clickTarget?.type === 'region' && rectFields(clickTarget, (rect) =>
+                updateAction('click', {}, { target: { type: 'region', ...rect } })
+              )}
+              <div style={{ fontSize: '12px', marginTop: '12px' }}>
+                {t('automation.noClickZones')}
+                <div style={{ opacity: 0.7 }}>{t('automation.noClickZonesHint')}</div>
+              </div>
+              {noClickZones.map((zone, index) => (
+                <div key={index} style={{ display: 'flex', alignItems: 'flex-end', gap: '8px' }}>
+                  <div style={{ flex: 1 }}>
+                    {rectFields(zone, (rect) => updateAction('click', {}, {
+                      no_click_zones: noClickZones.map((other, i) => i === index ? rect : other)
+                    }))}
+                  </div>
+                  <button
+                    onClick={() => updateAction('click', {}, {
+                      no_click_zones: noClickZones.filter((_, i) => i !== index)
+                    })}
+                  >
+                    {t('automation.removeZone')}
+                  </button>
+                </div>
+              ))}
+              <button
+                onClick={() => updateAction('click', {}, { no_click_zones: [...noClickZones, DEFAULT_ZONE] })}
+                style={{ marginTop: '8px' }}
+              >
+                {t('automation.addZone')}
+              </button>
+            </div>
+          )}
+          <label className="checkbox-label">
+            <input
+              type="checkbox"
+              checked={scroll?.enabled ?? false}
+              onChange={(e) => updateAction('scroll', { enabled: e.target.checked })}
+            />
+            {t('automation.enableScroll')}
+          </label>
+          {scroll?.enabled && (
+            <div style={{ display: 'flex', gap: '8px', margin: '8px 0 12px' }}>
+              <label style={{ flex: 1, fontSize: '12px' }}>
+                {t('automation.scrollAmount')}
+                <input
+                  type="number"
+                  min="1"
+                  value={scroll.action.amount}
+                  onChange={(e) => updateAction('scroll', {}, { amount: Number(e.target.value) })}
+                  style={{ width: '100%', padding: '4px' }}
+                />
+              </label>
+              <label style={{ flex: 1, fontSize: '12px' }}>
+                {t('automation.scrollAxis')}
+                <select
+                  value={scroll.action.axis ?? 'vertical'}
+                  onChange={(e) => updateAction('scroll', {}, { axis: e.target.value as ScrollAxis })}
+                  style={{ width: '100%', padding: '4px' }}
+                >
+                  {SCROLL_AXES.map(axis => (
+                    <option key={axis} value={axis}>{t(`automation.scrollAxes.${axis}`)}</option>
+                  ))}
+                </select>
+              </label>
+              <label style={{ flex: 2, fontSize: '12px' }}>
+                {t('automation.scrollPattern')}
+                <select
+                  value={scroll.action.pattern ?? 'random'}
+                  onChange={(e) => updateAction('scroll', {}, { pattern: e.target.value as ScrollPattern })}
+                  style={{ width: '100%', padding: '4px' }}
+                >
+                  {SCROLL_PATTERNS.map(pattern => (
+                    <option key={pattern} value={pattern}>{t(`automation.scrollPatterns.${pattern}`)}</option>
+                  ))}
+                </select>
+              </label>
+            </div>
+          )}
           <label className="checkbox-label">
             <input 
               type="checkbox"
-              checked={config.enable_keyboard}
-              onChange={(e) => setConfig(prev => ({ ...prev, enable_keyboard: e.target.checked }))}
-              disabled={isActive}
+              checked={findAction('text')?.enabled ?? false}
+              onChange={(e) => updateAction('text', { enabled: e.target.checked })}
             />
             {t('automation.enableKeyboard')}
           </label>
-          {config.enable_keyboard && (
+          {findAction('text')?.enabled && (
             <div style={{ marginTop: '12px' }}>
               <label>
                 {t('automation.textToType')}
                 <textarea
-                  value={config.keyboard_text}
-                  onChange={(e) => setConfig(prev => ({ ...prev, keyboard_text: e.target.value }))}
-                  disabled={isActive}
+                  value={keyboardText}
+                  onChange={(e) => updateAction('text', {}, { text: e.target.value })}
                   style={{
                     width: '100%',
                     minHeight: '80px',
@@ -187,14 +595,13 @@ export default function AutomationPanel({ isActive, onToggle }: AutomationPanelP
             {t('automation.mouseDescription')}
           </p>
           <label style={{ display: 'block' }}>
-            {t('automation.movementRange')}: {config.mouse_movement_range}px
+            {t('automation.movementRange')}: {mouseRange}px
             <input 
               type="range"
               min="5"
               max="300"
-              value={config.mouse_movement_range}
-              onChange={(e) => setConfig(prev => ({ ...prev, mouse_movement_range: Number(e.target.value) }))}
-              disabled={isActive}
+              value={mouseRange}
+              onChange={(e) => updateAction('mouse_move', {}, { range: Number(e.target.value) })}
               style={{ width: '100%', marginTop: '8px' }}
             />
             <div style={{ display: 'flex', justifyContent: 'space-between', fontSize: '12px', opacity: 0.7, marginTop: '4px' }}>
@@ -203,8 +610,92 @@ export default function AutomationPanel({ isActive, onToggle }: AutomationPanelP
               <span>300px</span>
             </div>
           </label>
+          <label style={{ display: 'block', marginTop: '12px' }}>
+            {t('automation.moveBounds')}
+            <select
+              value={mouseBounds.type}
+              onChange={(e) => updateAction('mouse_move', {}, {
+                bounds: e.target.value === 'region' ? DEFAULT_REGION : { type: 'current_monitor' }
+              })}
+              style={{ width: '100%', padding: '6px', marginTop: '4px' }}
+            >
+              <option value="current_monitor">{t('automation.boundsCurrentMonitor')}</option>
+              <option value="region">{t('automation.boundsRegion')}</option>
+            </select>
+          </label>
+          {mouseBounds.type === 'region' && rectFields(mouseBounds, (rect) =>
+            updateAction('mouse_move', {}, { bounds: { type: 'region', ...rect } })
+          )}
+          <label className="checkbox-label" style={{ marginTop: '8px' }}>
+            <input
+              type="checkbox"
+              checked={restoreCursor}
+              onChange={(e) => updateAction('mouse_move', {}, { restore_cursor: e.target.checked })}
+            />
+            {t('automation.restoreCursor')}
+          </label>
         </div>
       </div>
+      )}
+
+      {config.mode !== 'keep_awake' && (
+      <div className="config-section">
+        <h3>{t('automation.idleThreshold')}</h3>
+        <p className="subsection-description">
+          {t('automation.idleThresholdDescription')}
+        </p>
+        <input
+          type="number"
+          min="1"
+          value={config.idle_threshold_ms === null ? '' : config.idle_threshold_ms / 1000}
+          onChange={(e) => setConfig(prev => ({
+            ...prev,
+            idle_threshold_ms: e.target.value === '' ? null : Number(e.target.value) * 1000
+          }))}
+          placeholder={t('automation.idleThresholdPlaceholder')}
+          style={{ width: '100%', padding: '6px' }}
+        />
+      </div>
+      )}
+
+      <div className="config-section">
+        <h3>{t('automation.activeApps')}</h3>
+        <p className="subsection-description">
+          {t('automation.activeAppsDescription')}
+        </p>
+        <input
+          type="text"
+          value={config.active_apps.join(', ')}
+          onChange={(e) => setConfig(prev => ({
+            ...prev,
+            active_apps: e.target.value.split(',').map(app => app.trim()).filter(app => app !== '')
+          }))}
+          placeholder={t('automation.activeAppsPlaceholder')}
+          style={{ width: '100%', padding: '6px' }}
+        />
+      </div>
+
+      <div className="config-section">
+        <h3>{t('automation.failSafe')}</h3>
+        <p className="subsection-description">
+          {t('automation.failSafeDescription')}
+        </p>
+        {SCREEN_CORNERS.map(corner => (
+          <label key={corner} className="checkbox-label">
+            <input
+              type="checkbox"
+              checked={config.fail_safe_corners.includes(corner)}
+              onChange={(e) => setConfig(prev => ({
+                ...prev,
+                fail_safe_corners: e.target.checked
+                  ? [...prev.fail_safe_corners, corner]
+                  : prev.fail_safe_corners.filter(selected => selected !== corner)
+              }))}
+            />
+            {t(`automation.corners.${corner}`)}
+          </label>
+        ))}
+      </div>
 
       <div className="info-section">
         <p className="info-text">
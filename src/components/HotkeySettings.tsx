@@ -0,0 +1,94 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useTranslation } from "../hooks/useTranslation";
+import { FieldError, isLupinError, describeError } from "../errors";
+
+type HotkeyAction = 'toggle' | 'pause' | 'panic_stop' | 'abort_type';
+
+// Accelerators such as "Ctrl+Alt+L"; null leaves the action unbound
+type HotkeyConfig = Record<HotkeyAction, string | null>;
+
+interface HotkeyConflict {
+  // Snippet hotkeys are reported as { snippet: index }
+  action: HotkeyAction | { snippet: number };
+  hotkey: string;
+  message: string;
+}
+
+interface HotkeyStatus {
+  config: HotkeyConfig;
+  conflicts: HotkeyConflict[];
+}
+
+const ACTIONS: HotkeyAction[] = ['toggle', 'pause', 'panic_stop', 'abort_type'];
+
+export default function HotkeySettings() {
+  const { t } = useTranslation();
+  const [config, setConfig] = useState<HotkeyConfig>({ toggle: null, pause: null, panic_stop: null, abort_type: null });
+  const [conflicts, setConflicts] = useState<HotkeyConflict[]>([]);
+  const [problems, setProblems] = useState<FieldError[]>([]);
+  const [isDirty, setIsDirty] = useState(false);
+
+  const showStatus = (status: HotkeyStatus) => {
+    setConfig(status.config);
+    setConflicts(status.conflicts);
+    setProblems([]);
+    setIsDirty(false);
+  };
+
+  useEffect(() => {
+    invoke<HotkeyStatus>("get_hotkeys")
+      .then(showStatus)
+      .catch(error => console.error("Failed to load hotkeys:", error));
+  }, []);
+
+  const handleChange = (action: HotkeyAction, value: string) => {
+    setConfig(prev => ({ ...prev, [action]: value.trim() === '' ? null : value }));
+    setIsDirty(true);
+  };
+
+  const handleSave = async () => {
+    try {
+      showStatus(await invoke<HotkeyStatus>("set_hotkeys", { config }));
+    } catch (error) {
+      console.error("Failed to save hotkeys:", error);
+      if (isLupinError(error) && error.kind === 'invalid_config') {
+        setProblems(error.problems);
+      } else {
+        alert(describeError(error, t));
+      }
+    }
+  };
+
+  return (
+    <div className="panel hotkeys-panel">
+      <h2>{t('hotkeys.title')}</h2>
+      <p className="section-description">
+        {t('hotkeys.description')}
+      </p>
+      {ACTIONS.map(action => {
+        const conflict = conflicts.find(entry => entry.action === action);
+        const problem = problems.find(entry => entry.field === action);
+        return (
+          <label key={action} style={{ display: 'block', marginBottom: '8px' }}>
+            {t(`hotkeys.${action}`)}
+            <input
+              type="text"
+              value={config[action] ?? ''}
+              onChange={(e) => handleChange(action, e.target.value)}
+              placeholder={t('hotkeys.unbound')}
+              style={{ width: '100%', padding: '6px' }}
+            />
+            {problem && <span style={{ display: 'block', fontSize: '13px', color: '#ff8a80' }}>{problem.message}</span>}
+            {!problem && conflict && (
+              <span style={{ display: 'block', fontSize: '13px', color: '#ff8a80' }}>
+                {t('hotkeys.conflict').replace('{hotkey}', conflict.hotkey)}: {conflict.message}
+              </span>
+            )}
+          </label>
+        );
+      })}
+      <button onClick={handleSave} disabled={!isDirty}>{t('hotkeys.save')}</button>
+    </div>
+  );
+}
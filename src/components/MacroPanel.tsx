@@ -0,0 +1,113 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useTranslation } from "../hooks/useTranslation";
+import { MacroProblem, describeError } from "../errors";
+
+interface MacroInfo {
+  name: string;
+  description: string | null;
+  problems: MacroProblem[];
+}
+
+interface MacroList {
+  dir: string;
+  macros: MacroInfo[];
+}
+
+interface ActivityRecord {
+  kind: string;
+  action?: string;
+  error?: string;
+}
+
+export default function MacroPanel() {
+  const { t } = useTranslation();
+  const [list, setList] = useState<MacroList>({ dir: '', macros: [] });
+  const [running, setRunning] = useState<string | null>(null);
+  const [outcome, setOutcome] = useState<string | null>(null);
+
+  const refresh = () => {
+    invoke<MacroList>("list_macros")
+      .then(setList)
+      .catch(error => console.error("Failed to list macros:", error));
+  };
+
+  useEffect(refresh, []);
+
+  // A macro reports how it ended through the activity log
+  useEffect(() => {
+    const unsubscribe = listen<ActivityRecord>('automation-activity', (event) => {
+      const record = event.payload;
+      if (!record.action?.startsWith('macro ')) {
+        return;
+      }
+      setRunning(null);
+      setOutcome(record.kind === 'failed'
+        ? `${record.action}: ${record.error}`
+        : t(`macros.outcome.${record.kind}`).replace('{name}', record.action.slice('macro '.length)));
+    });
+    return () => {
+      unsubscribe.then(fn => fn());
+    };
+  }, [t]);
+
+  const handleRun = async (name: string) => {
+    try {
+      await invoke("run_macro", { name });
+      setRunning(name);
+      setOutcome(null);
+    } catch (error) {
+      console.error("Failed to run macro:", error);
+      alert(describeError(error, t));
+      refresh();
+    }
+  };
+
+  const handleStop = async () => {
+    try {
+      await invoke("stop_macro");
+    } catch (error) {
+      console.error("Failed to stop macro:", error);
+    }
+  };
+
+  return (
+    <div className="panel macros-panel">
+      <h2>{t('macros.title')}</h2>
+      <p className="section-description">
+        {t('macros.description').replace('{dir}', list.dir)}
+      </p>
+      {list.macros.length === 0 && (
+        <p style={{ fontSize: '13px', opacity: 0.7 }}>{t('macros.empty')}</p>
+      )}
+      {list.macros.map(entry => (
+        <div key={entry.name} style={{ marginBottom: '8px' }}>
+          <div style={{ display: 'flex', alignItems: 'center', gap: '8px' }}>
+            <strong style={{ flex: 1 }}>{entry.name}</strong>
+            {running === entry.name ? (
+              <button onClick={handleStop}>{t('macros.stop')}</button>
+            ) : (
+              <button
+                onClick={() => handleRun(entry.name)}
+                disabled={running !== null || entry.problems.length > 0}
+              >
+                {t('macros.run')}
+              </button>
+            )}
+          </div>
+          {entry.description && (
+            <div style={{ fontSize: '12px', opacity: 0.7 }}>{entry.description}</div>
+          )}
+          {entry.problems.map((problem, index) => (
+            <span key={index} style={{ display: 'block', fontSize: '13px', color: '#ff8a80' }}>
+              {t('macros.line').replace('{line}', String(problem.line))}: {problem.message}
+            </span>
+          ))}
+        </div>
+      ))}
+      {outcome && <p style={{ fontSize: '13px' }}>{outcome}</p>}
+      <button onClick={refresh}>{t('macros.refresh')}</button>
+    </div>
+  );
+}
@@ -0,0 +1,79 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useTranslation } from "../hooks/useTranslation";
+import { describeError } from "../errors";
+
+export interface ProfileList {
+  active: string;
+  names: string[];
+}
+
+interface ProfileSelectorProps {
+  profiles: ProfileList;
+  disabled: boolean;
+}
+
+// The backend emits "profiles-changed" after every command here, so the
+// panel picks up the new list and active profile from that event
+export default function ProfileSelector({ profiles, disabled }: ProfileSelectorProps) {
+  const { t } = useTranslation();
+
+  const run = async (command: string, args: Record<string, string>) => {
+    try {
+      await invoke(command, args);
+    } catch (error) {
+      console.error(`Failed to ${command}:`, error);
+      alert(describeError(error, t));
+    }
+  };
+
+  const askName = (initial: string) => {
+    const name = prompt(t('profiles.namePrompt'), initial);
+    return name && name.trim() ? name.trim() : null;
+  };
+
+  const handleCreate = () => {
+    const name = askName('');
+    if (name) run("create_profile", { name });
+  };
+
+  const handleRename = () => {
+    const newName = askName(profiles.active);
+    if (newName && newName !== profiles.active) run("rename_profile", { name: profiles.active, newName });
+  };
+
+  const handleDuplicate = () => {
+    const newName = askName(`${profiles.active} copy`);
+    if (newName) run("duplicate_profile", { name: profiles.active, newName });
+  };
+
+  const handleDelete = () => {
+    if (confirm(t('profiles.deleteConfirm').replace('{name}', profiles.active))) {
+      run("delete_profile", { name: profiles.active });
+    }
+  };
+
+  return (
+    <div className="config-section">
+      <h3>{t('profiles.title')}</h3>
+      <p className="subsection-description">
+        {t('profiles.description')}
+      </p>
+      <select
+        value={profiles.active}
+        onChange={(e) => run("set_active_profile", { name: e.target.value })}
+        disabled={disabled}
+        style={{ width: '100%', padding: '6px', marginBottom: '8px' }}
+      >
+        {profiles.names.map(name => (
+          <option key={name} value={name}>{name}</option>
+        ))}
+      </select>
+      <div style={{ display: 'flex', gap: '8px', flexWrap: 'wrap' }}>
+        <button onClick={handleCreate} disabled={disabled}>{t('profiles.create')}</button>
+        <button onClick={handleRename} disabled={disabled}>{t('profiles.rename')}</button>
+        <button onClick={handleDuplicate} disabled={disabled}>{t('profiles.duplicate')}</button>
+        <button onClick={handleDelete} disabled={disabled || profiles.names.length < 2}>{t('profiles.delete')}</button>
+      </div>
+    </div>
+  );
+}
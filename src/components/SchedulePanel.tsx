@@ -0,0 +1,335 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useTranslation } from "../hooks/useTranslation";
+import { FieldError, isLupinError, describeError } from "../errors";
+import { ProfileList } from "./ProfileSelector";
+
+// Mirrors Schedule in src-tauri/src/scheduler.rs
+type Weekday = 'Mon' | 'Tue' | 'Wed' | 'Thu' | 'Fri' | 'Sat' | 'Sun';
+
+type ScheduleRule =
+  | { type: 'window'; profile: string; days: Weekday[]; start: string; end: string }
+  | { type: 'cron'; profile: string; expression: string; duration_minutes: number };
+
+// Mirrors CalendarSource in src-tauri/src/calendar.rs
+interface CalendarSource {
+  path: string;
+  profile: string;
+  categories: string[];
+  title_pattern: string | null;
+}
+
+interface CalendarWindow {
+  title: string;
+  start: string;
+  end: string;
+}
+
+interface Schedule {
+  enabled: boolean;
+  timezone: string | null;
+  rules: ScheduleRule[];
+  calendars: CalendarSource[];
+}
+
+type Trigger =
+  | { type: 'rule'; index: number }
+  | { type: 'calendar'; index: number; event: string };
+
+interface Activation {
+  trigger: Trigger;
+  profile: string;
+  start: string;
+  end: string;
+}
+
+const WEEKDAYS: Weekday[] = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun'];
+
+export default function SchedulePanel() {
+  const { t } = useTranslation();
+  const [schedule, setSchedule] = useState<Schedule>({ enabled: false, timezone: null, rules: [], calendars: [] });
+  const [profiles, setProfiles] = useState<string[]>([]);
+  const [preview, setPreview] = useState<Activation[]>([]);
+  const [problems, setProblems] = useState<FieldError[]>([]);
+  const [isDirty, setIsDirty] = useState(false);
+  const [calendarPreviews, setCalendarPreviews] = useState<Record<number, CalendarWindow[] | undefined>>({});
+
+  useEffect(() => {
+    invoke<Schedule>("get_schedule")
+      .then(setSchedule)
+      .catch(error => console.error("Failed to load schedule:", error));
+    invoke<ProfileList>("list_profiles")
+      .then(list => setProfiles(list.names))
+      .catch(error => console.error("Failed to list profiles:", error));
+  }, []);
+
+  // Preview the schedule as edited, before it is saved
+  useEffect(() => {
+    const timer = setTimeout(() => {
+      invoke<Activation[]>("get_schedule_preview", { schedule })
+        .then(activations => {
+          setPreview(activations);
+          setProblems([]);
+        })
+        .catch(error => {
+          setPreview([]);
+          if (isLupinError(error) && error.kind === 'invalid_config') {
+            setProblems(error.problems);
+          }
+        });
+    }, 300);
+    return () => clearTimeout(timer);
+  }, [schedule]);
+
+  const change = (update: (prev: Schedule) => Schedule) => {
+    setSchedule(update);
+    setIsDirty(true);
+  };
+
+  const updateRule = (index: number, rule: ScheduleRule) =>
+    change(prev => ({ ...prev, rules: prev.rules.map((entry, i) => i === index ? rule : entry) }));
+
+  const addRule = (type: ScheduleRule['type']) => {
+    const profile = profiles[0] ?? '';
+    const rule: ScheduleRule = type === 'window'
+      ? { type, profile, days: ['Mon', 'Tue', 'Wed', 'Thu', 'Fri'], start: '09:00', end: '17:00' }
+      : { type, profile, expression: '0 22 * * *', duration_minutes: 480 };
+    change(prev => ({ ...prev, rules: [...prev.rules, rule] }));
+  };
+
+  const updateCalendar = (index: number, calendar: CalendarSource) => {
+    change(prev => ({ ...prev, calendars: prev.calendars.map((entry, i) => i === index ? calendar : entry) }));
+    setCalendarPreviews(prev => ({ ...prev, [index]: undefined }));
+  };
+
+  const addCalendar = () => {
+    const calendar: CalendarSource = { path: '', profile: profiles[0] ?? '', categories: [], title_pattern: null };
+    change(prev => ({ ...prev, calendars: [...prev.calendars, calendar] }));
+  };
+
+  const removeCalendar = (index: number) => {
+    change(prev => ({ ...prev, calendars: prev.calendars.filter((_, i) => i !== index) }));
+    setCalendarPreviews({});
+  };
+
+  const previewCalendar = async (index: number) => {
+    try {
+      const windows = await invoke<CalendarWindow[]>("preview_calendar", { source: schedule.calendars[index] });
+      setCalendarPreviews(prev => ({ ...prev, [index]: windows }));
+    } catch (error) {
+      console.error("Failed to preview calendar:", error);
+      if (isLupinError(error) && error.kind === 'invalid_config') {
+        setProblems(error.problems.map(entry => ({ ...entry, field: `calendars[${index}].${entry.field}` })));
+      } else {
+        alert(describeError(error, t));
+      }
+    }
+  };
+
+  const describeTrigger = (trigger: Trigger) => trigger.type === 'rule'
+    ? t('schedule.rule').replace('{index}', String(trigger.index + 1))
+    : trigger.event;
+
+  const handleSave = async () => {
+    try {
+      await invoke("set_schedule", { schedule });
+      setIsDirty(false);
+    } catch (error) {
+      console.error("Failed to save schedule:", error);
+      if (isLupinError(error) && error.kind === 'invalid_config') {
+        setProblems(error.problems);
+      } else {
+        alert(describeError(error, t));
+      }
+    }
+  };
+
+  const problemsFor = (prefix: string) => problems
+    .filter(entry => entry.field === prefix || entry.field.startsWith(`${prefix}.`))
+    .map((entry, index) => (
+      <span key={index} style={{ display: 'block', fontSize: '13px', color: '#ff8a80' }}>{entry.message}</span>
+    ));
+
+  const formatTime = (time: string) => new Date(time).toLocaleString([], {
+    weekday: 'short', month: 'short', day: 'numeric', hour: '2-digit', minute: '2-digit'
+  });
+
+  return (
+    <div className="panel schedule-panel">
+      <h2>{t('schedule.title')}</h2>
+      <p className="section-description">{t('schedule.description')}</p>
+      <label className="checkbox-label">
+        <input
+          type="checkbox"
+          checked={schedule.enabled}
+          onChange={(e) => change(prev => ({ ...prev, enabled: e.target.checked }))}
+        />
+        {t('schedule.enabled')}
+      </label>
+      <input
+        type="text"
+        value={schedule.timezone ?? ''}
+        onChange={(e) => change(prev => ({ ...prev, timezone: e.target.value.trim() === '' ? null : e.target.value }))}
+        placeholder={t('schedule.timezone')}
+        style={{ width: '100%', padding: '6px', margin: '8px 0' }}
+      />
+      {problemsFor('timezone')}
+
+      {schedule.rules.map((rule, index) => (
+        <div key={index} style={{ marginBottom: '12px' }}>
+          <div style={{ display: 'flex', gap: '8px', marginBottom: '4px' }}>
+            <select
+              value={rule.profile}
+              onChange={(e) => updateRule(index, { ...rule, profile: e.target.value })}
+              style={{ flex: 1, padding: '6px' }}
+            >
+              {!profiles.includes(rule.profile) && <option value={rule.profile}>{rule.profile}</option>}
+              {profiles.map(name => <option key={name} value={name}>{name}</option>)}
+            </select>
+            <button onClick={() => change(prev => ({ ...prev, rules: prev.rules.filter((_, i) => i !== index) }))}>
+              {t('schedule.remove')}
+            </button>
+          </div>
+          {rule.type === 'window' ? (
+            <>
+              <div style={{ display: 'flex', gap: '4px', flexWrap: 'wrap', marginBottom: '4px' }}>
+                {WEEKDAYS.map(day => (
+                  <label key={day} className="checkbox-label" style={{ fontSize: '12px' }}>
+                    <input
+                      type="checkbox"
+                      checked={rule.days.includes(day)}
+                      onChange={(e) => updateRule(index, {
+                        ...rule,
+                        days: e.target.checked
+                          ? WEEKDAYS.filter(d => d === day || rule.days.includes(d))
+                          : rule.days.filter(d => d !== day)
+                      })}
+                    />
+                    {t(`schedule.days.${day}`)}
+                  </label>
+                ))}
+              </div>
+              <div style={{ display: 'flex', gap: '8px' }}>
+                <label style={{ flex: 1, fontSize: '12px' }}>
+                  {t('schedule.start')}
+                  <input
+                    type="time"
+                    value={rule.start}
+                    onChange={(e) => updateRule(index, { ...rule, start: e.target.value })}
+                    style={{ width: '100%' }}
+                  />
+                </label>
+                <label style={{ flex: 1, fontSize: '12px' }}>
+                  {t('schedule.end')}
+                  <input
+                    type="time"
+                    value={rule.end}
+                    onChange={(e) => updateRule(index, { ...rule, end: e.target.value })}
+                    style={{ width: '100%' }}
+                  />
+                </label>
+              </div>
+            </>
+          ) : (
+            <div style={{ display: 'flex', gap: '8px' }}>
+              <label style={{ flex: 2, fontSize: '12px' }}>
+                {t('schedule.expression')}
+                <input
+                  type="text"
+                  value={rule.expression}
+                  onChange={(e) => updateRule(index, { ...rule, expression: e.target.value })}
+                  style={{ width: '100%' }}
+                />
+              </label>
+              <label style={{ flex: 1, fontSize: '12px' }}>
+                {t('schedule.duration')}
+                <input
+                  type="number"
+                  min="1"
+                  value={rule.duration_minutes}
+                  onChange={(e) => updateRule(index, { ...rule, duration_minutes: Number(e.target.value) })}
+                  style={{ width: '100%' }}
+                />
+              </label>
+            </div>
+          )}
+          {problemsFor(`rules[${index}]`)}
+        </div>
+      ))}
+
+      <div style={{ display: 'flex', gap: '8px', marginBottom: '8px' }}>
+        <button onClick={() => addRule('window')}>{t('schedule.addWindow')}</button>
+        <button onClick={() => addRule('cron')}>{t('schedule.addCron')}</button>
+        <button onClick={handleSave} disabled={!isDirty}>{t('schedule.save')}</button>
+      </div>
+
+      <h3>{t('schedule.calendars')}</h3>
+      <p className="section-description">{t('schedule.calendarsDescription')}</p>
+      {schedule.calendars.map((calendar, index) => (
+        <div key={index} style={{ marginBottom: '12px' }}>
+          <div style={{ display: 'flex', gap: '8px', marginBottom: '4px' }}>
+            <input
+              type="text"
+              value={calendar.path}
+              onChange={(e) => updateCalendar(index, { ...calendar, path: e.target.value })}
+              placeholder={t('schedule.calendarPath')}
+              style={{ flex: 2, padding: '6px' }}
+            />
+            <select
+              value={calendar.profile}
+              onChange={(e) => updateCalendar(index, { ...calendar, profile: e.target.value })}
+              style={{ flex: 1, padding: '6px' }}
+            >
+              {!profiles.includes(calendar.profile) && <option value={calendar.profile}>{calendar.profile}</option>}
+              {profiles.map(name => <option key={name} value={name}>{name}</option>)}
+            </select>
+            <button onClick={() => removeCalendar(index)}>{t('schedule.remove')}</button>
+          </div>
+          <div style={{ display: 'flex', gap: '8px' }}>
+            <input
+              type="text"
+              value={calendar.categories.join(',')}
+              onChange={(e) => updateCalendar(index, {
+                ...calendar,
+                categories: e.target.value === '' ? [] : e.target.value.split(',')
+              })}
+              placeholder={t('schedule.categories')}
+              style={{ flex: 1, padding: '6px' }}
+            />
+            <input
+              type="text"
+              value={calendar.title_pattern ?? ''}
+              onChange={(e) => updateCalendar(index, {
+                ...calendar,
+                title_pattern: e.target.value === '' ? null : e.target.value
+              })}
+              placeholder={t('schedule.titlePattern')}
+              style={{ flex: 1, padding: '6px' }}
+            />
+            <button onClick={() => previewCalendar(index)}>{t('schedule.previewCalendar')}</button>
+          </div>
+          {problemsFor(`calendars[${index}]`)}
+          {calendarPreviews[index]?.length === 0 && (
+            <p style={{ fontSize: '13px', opacity: 0.7 }}>{t('schedule.noEvents')}</p>
+          )}
+          {calendarPreviews[index]?.slice(0, 10).map((event, i) => (
+            <div key={i} style={{ fontSize: '13px' }}>
+              {formatTime(event.start)} – {formatTime(event.end)}: {event.title}
+            </div>
+          ))}
+        </div>
+      ))}
+      <div style={{ marginBottom: '8px' }}>
+        <button onClick={addCalendar}>{t('schedule.addCalendar')}</button>
+      </div>
+
+      <h3>{t('schedule.upcoming')}</h3>
+      {preview.length === 0 && <p style={{ fontSize: '13px', opacity: 0.7 }}>{t('schedule.none')}</p>}
+      {preview.slice(0, 10).map((activation, index) => (
+        <div key={index} style={{ fontSize: '13px' }}>
+          {formatTime(activation.start)} – {formatTime(activation.end)}: {activation.profile} ({describeTrigger(activation.trigger)})
+        </div>
+      ))}
+    </div>
+  );
+}
@@ -0,0 +1,127 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useTranslation } from "../hooks/useTranslation";
+import { FieldError, isLupinError, describeError } from "../errors";
+
+// Mirrors Snippet in src-tauri/src/snippets.rs
+interface Snippet {
+  name: string;
+  body: string;
+  hotkey: string | null;
+}
+
+interface HotkeyConflict {
+  action: string | { snippet: number };
+  hotkey: string;
+  message: string;
+}
+
+interface HotkeyStatus {
+  conflicts: HotkeyConflict[];
+}
+
+const isSnippetConflict = (conflict: HotkeyConflict, index: number) =>
+  typeof conflict.action === 'object' && conflict.action.snippet === index;
+
+export default function SnippetPanel() {
+  const { t } = useTranslation();
+  const [snippets, setSnippets] = useState<Snippet[]>([]);
+  const [conflicts, setConflicts] = useState<HotkeyConflict[]>([]);
+  const [problems, setProblems] = useState<FieldError[]>([]);
+  const [isDirty, setIsDirty] = useState(false);
+
+  useEffect(() => {
+    invoke<Snippet[]>("get_snippets")
+      .then(setSnippets)
+      .catch(error => console.error("Failed to load snippets:", error));
+    invoke<HotkeyStatus>("get_hotkeys")
+      .then(status => setConflicts(status.conflicts))
+      .catch(error => console.error("Failed to load hotkeys:", error));
+  }, []);
+
+  const update = (index: number, change: Partial<Snippet>) => {
+    setSnippets(prev => prev.map((snippet, i) => i === index ? { ...snippet, ...change } : snippet));
+    setIsDirty(true);
+  };
+
+  const handleAdd = () => {
+    setSnippets(prev => [...prev, { name: '', body: '', hotkey: null }]);
+    setIsDirty(true);
+  };
+
+  const handleRemove = (index: number) => {
+    setSnippets(prev => prev.filter((_, i) => i !== index));
+    setIsDirty(true);
+  };
+
+  const handleSave = async () => {
+    try {
+      setConflicts(await invoke<HotkeyConflict[]>("set_snippets", { snippets }));
+      setProblems([]);
+      setIsDirty(false);
+    } catch (error) {
+      console.error("Failed to save snippets:", error);
+      if (isLupinError(error) && error.kind === 'invalid_config') {
+        setProblems(error.problems);
+      } else {
+        alert(describeError(error, t));
+      }
+    }
+  };
+
+  const problemFor = (index: number, field: string) =>
+    problems.find(entry => entry.field === `snippets[${index}].${field}`);
+
+  const errorText = (message: string) => (
+    <span style={{ display: 'block', fontSize: '13px', color: '#ff8a80' }}>{message}</span>
+  );
+
+  return (
+    <div className="panel snippets-panel">
+      <h2>{t('snippets.title')}</h2>
+      <p className="section-description">{t('snippets.description')}</p>
+      {snippets.map((snippet, index) => {
+        const conflict = conflicts.find(entry => isSnippetConflict(entry, index));
+        const hotkeyProblem = problemFor(index, 'hotkey');
+        return (
+          <div key={index} style={{ marginBottom: '12px' }}>
+            <div style={{ display: 'flex', gap: '8px', marginBottom: '4px' }}>
+              <input
+                type="text"
+                value={snippet.name}
+                onChange={(e) => update(index, { name: e.target.value })}
+                placeholder={t('snippets.name')}
+                style={{ flex: 2, padding: '6px' }}
+              />
+              <input
+                type="text"
+                value={snippet.hotkey ?? ''}
+                onChange={(e) => update(index, { hotkey: e.target.value.trim() === '' ? null : e.target.value })}
+                placeholder={t('snippets.hotkey')}
+                style={{ flex: 1, padding: '6px' }}
+              />
+              <button onClick={() => handleRemove(index)}>{t('snippets.remove')}</button>
+            </div>
+            {problemFor(index, 'name') && errorText(problemFor(index, 'name')!.message)}
+            {hotkeyProblem && errorText(hotkeyProblem.message)}
+            {!hotkeyProblem && conflict && errorText(
+              `${t('hotkeys.conflict').replace('{hotkey}', conflict.hotkey)}: ${conflict.message}`
+            )}
+            <textarea
+              value={snippet.body}
+              onChange={(e) => update(index, { body: e.target.value })}
+              placeholder={t('snippets.body')}
+              rows={3}
+              style={{ width: '100%', padding: '6px' }}
+            />
+            {problemFor(index, 'body') && errorText(problemFor(index, 'body')!.message)}
+          </div>
+        );
+      })}
+      <div style={{ display: 'flex', gap: '8px' }}>
+        <button onClick={handleAdd}>{t('snippets.add')}</button>
+        <button onClick={handleSave} disabled={!isDirty}>{t('snippets.save')}</button>
+      </div>
+    </div>
+  );
+}
@@ -1,4 +1,5 @@
 import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { useTranslation } from "../hooks/useTranslation";
 
 interface StatusBarProps {
@@ -9,15 +10,25 @@ interface StatusBarProps {
 export default function StatusBar({ isActive }: StatusBarProps) {
   const { t } = useTranslation();
   const [uptime, setUptime] = useState(0);
+  // End of a timed session, which may have been started from the tray
+  const [endsAt, setEndsAt] = useState<string | null>(null);
+  const [now, setNow] = useState(Date.now());
 
   useEffect(() => {
     if (isActive) {
+      const refresh = () => invoke<{ ends_at: string | null }>("get_automation_status")
+        .then(status => setEndsAt(status.ends_at))
+        .catch(error => console.error("Failed to get automation status:", error));
+      refresh();
       const interval = setInterval(() => {
         setUptime(prev => prev + 1);
+        setNow(Date.now());
+        refresh();
       }, 1000);
       return () => clearInterval(interval);
     } else {
       setUptime(0);
+      setEndsAt(null);
     }
   }, [isActive]);
 
@@ -51,6 +62,15 @@ export default function StatusBar({ isActive }: StatusBarProps) {
         </div>
       )}
       
+      {isActive && endsAt && (
+        <div className="status-item">
+          <span className="status-label">Remaining:</span>
+          <span className="status-value">
+            {formatUptime(Math.max(0, Math.ceil((new Date(endsAt).getTime() - now) / 1000)))}
+          </span>
+        </div>
+      )}
+      
       <div className="status-item">
         <span className="status-label">Mode:</span>
         <span className="status-value stealth">{t('status.stealth').toUpperCase()}</span>
@@ -0,0 +1,58 @@
+// Mirrors LupinError in src-tauri/src/error.rs
+export interface FieldError {
+  field: string;
+  message: string;
+}
+
+// Where a macro file went wrong, see MacroProblem in src-tauri/src/macros.rs
+export interface MacroProblem {
+  line: number;
+  column: number;
+  message: string;
+}
+
+export type LupinError =
+  | { kind: 'invalid_config'; problems: FieldError[] }
+  | { kind: 'not_running' }
+  | { kind: 'not_paused' }
+  | { kind: 'profile_not_found'; name: string }
+  | { kind: 'profile_exists'; name: string }
+  | { kind: 'empty_profile_name' }
+  | { kind: 'last_profile' }
+  | { kind: 'unsupported_schema'; version: number; supported: number }
+  | { kind: 'config'; message: string }
+  | { kind: 'launch'; message: string }
+  | { kind: 'macro_not_found'; name: string }
+  | { kind: 'invalid_macro'; name: string; problems: MacroProblem[] }
+  | { kind: 'macro_running' }
+  | { kind: 'automation_running' }
+  | { kind: 'auto_type'; message: string }
+  | { kind: 'auto_type_running' };
+
+export const isLupinError = (error: unknown): error is LupinError =>
+  typeof error === 'object' && error !== null && 'kind' in error;
+
+// Translate a command error, falling back to the raw value for anything unexpected
+export const describeError = (error: unknown, t: (key: string) => string): string => {
+  if (!isLupinError(error)) {
+    return String(error);
+  }
+  switch (error.kind) {
+    case 'invalid_config':
+      return error.problems.map(problem => `${problem.field}: ${problem.message}`).join('\n');
+    case 'invalid_macro':
+      return error.problems.map(problem => `${error.name}:${problem.line}: ${problem.message}`).join('\n');
+    case 'profile_not_found':
+    case 'profile_exists':
+    case 'macro_not_found':
+      return t(`errors.${error.kind}`).replace('{name}', error.name);
+    case 'unsupported_schema':
+      return t('errors.unsupported_schema').replace('{version}', String(error.version));
+    case 'config':
+    case 'launch':
+    case 'auto_type':
+      return error.message;
+    default:
+      return t(`errors.${error.kind}`);
+  }
+};
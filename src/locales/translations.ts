@@ -15,6 +15,40 @@ export const translations = {
       description: "Automatically moves mouse and keyboard to simulate computer usage. Useful for preventing screen savers and avoiding idle status.",
       startButton: "▶️ Start",
       stopButton: "🛑 Stop",
+      pauseButton: "⏸️ Pause",
+      resumeButton: "⏯️ Resume",
+      mode: "🔋 Mode",
+      modeDescription: "Keep-awake asks the OS to keep the screen on and the machine from sleeping, without moving the mouse or typing.",
+      modeSimulate: "Simulate activity",
+      modeKeepAwake: "Keep awake only",
+      modeBoth: "Keep awake and simulate activity",
+      activeApps: "🪟 Allowed Apps",
+      activeAppsDescription: "Only act while one of these apps has focus (comma separated, e.g. firefox, code). Leave empty to act in any window.",
+      activeAppsPlaceholder: "Any app",
+      idleThreshold: "💤 Idle Threshold",
+      idleThresholdDescription: "Only act after you have left the mouse and keyboard alone for this many seconds, and stop immediately when you touch them. Leave empty to act regardless.",
+      idleThresholdPlaceholder: "Off",
+      failSafe: "🛑 Fail-safe Corners",
+      failSafeDescription: "Slam the mouse into a checked screen corner to stop automation immediately, even without hotkeys.",
+      failSafeTriggered: "Fail-safe triggered in the {corner} corner, automation stopped",
+      sessionNone: "Run until stopped",
+      sessionDuration: "Run for a number of minutes",
+      sessionUntil: "Run until a time",
+      sessionMinutes: "Minutes",
+      sessionEnded: "Session time is up, automation stopped",
+      corners: {
+        top_left: "top-left",
+        top_right: "top-right",
+        bottom_left: "bottom-left",
+        bottom_right: "bottom-right"
+      },
+      skipped: "Skipped",
+      skipReason: {
+        focus_not_allowed: "focus not allowed",
+        focus_unknown: "focused window unknown",
+        user_active: "you are using the computer",
+        idle_unknown: "idle time unknown"
+      },
       intervalSettings: "⏱️ Interval Settings",
       intervalDescription: "Set action intervals. Actions occur at random intervals for natural behavior.",
       minInterval: "Min Interval (seconds):",
@@ -23,6 +57,37 @@ export const translations = {
       actionsDescription: "Select actions to perform. Mouse movement is enabled by default.",
       mouseRange: "Mouse Movement Range:",
       enableClicks: "Enable Random Clicks",
+      clickButton: "Button",
+      mouseButtons: {
+        left: "Left",
+        middle: "Middle",
+        right: "Right"
+      },
+      clickCount: "Clicks",
+      clickTarget: "Click at",
+      clickTargets: {
+        cursor: "Wherever the cursor is",
+        point: "A fixed point",
+        region: "A random point in a region"
+      },
+      noClickZones: "No-click zones",
+      noClickZonesHint: "Clicks that would land in one of these areas, such as a taskbar or a close button, are refused.",
+      addZone: "Add zone",
+      removeZone: "Remove",
+      enableScroll: "Enable Scrolling",
+      scrollAmount: "Notches",
+      scrollAxis: "Axis",
+      scrollAxes: {
+        vertical: "Vertical",
+        horizontal: "Horizontal"
+      },
+      scrollPattern: "Direction",
+      scrollPatterns: {
+        random: "Random",
+        down: "Down / right",
+        up: "Up / left",
+        down_then_up: "Down, then back up"
+      },
       enableKeyboard: "Enable Keyboard Activity",
       textToType: "Text to Type:",
       textPlaceholder: "Enter text that will be typed automatically...",
@@ -30,9 +95,127 @@ export const translations = {
       mouseMovement: "🖱️ Mouse Movement",
       mouseDescription: "Set mouse movement range. Higher values mean larger movements.",
       movementRange: "Movement Range:",
+      moveBounds: "Keep the cursor inside:",
+      boundsCurrentMonitor: "The current monitor",
+      boundsRegion: "A custom region",
+      region: {
+        x: "Left",
+        y: "Top",
+        width: "Width",
+        height: "Height"
+      },
+      restoreCursor: "Return the cursor to where it started after each move",
       tip: "💡 Tip: Keep intervals random to avoid detection patterns.",
       currentInterval: "Current:"
     },
+    profiles: {
+      title: "📁 Profiles",
+      description: "Keep separate setups for different situations and switch between them here or from the tray.",
+      create: "New",
+      rename: "Rename",
+      duplicate: "Duplicate",
+      delete: "Delete",
+      namePrompt: "Profile name:",
+      deleteConfirm: "Delete profile \"{name}\"?"
+    },
+    hotkeys: {
+      title: "⌨️ Global Hotkeys",
+      description: "Control automation from any app, e.g. Ctrl+Alt+L. Leave a field empty to turn that hotkey off.",
+      toggle: "Start / Stop",
+      pause: "Pause / Resume",
+      panic_stop: "Panic Stop",
+      abort_type: "Abort Auto-Type",
+      unbound: "Off",
+      conflict: "{hotkey} is unavailable",
+      save: "Save Hotkeys"
+    },
+    macros: {
+      title: "📜 Macros",
+      description: "Scripted step sequences, read from the .toml files in {dir}.",
+      empty: "No macros yet. Add a .toml file to the folder above and refresh.",
+      run: "Run",
+      stop: "Stop",
+      refresh: "Refresh",
+      line: "Line {line}",
+      outcome: {
+        performed: "Macro {name} finished",
+        aborted: "Macro {name} was stopped"
+      }
+    },
+    autoType: {
+      title: "🔤 Auto-Type",
+      description: "Types the clipboard or a file into the focused window, for consoles and remote desktops that block pasting. Switch to the target window during the countdown.",
+      clipboard: "Clipboard",
+      file: "File",
+      pathPlaceholder: "Path to a text file",
+      wpm: "Speed (WPM)",
+      countdown: "Countdown (s)",
+      start: "Type",
+      stop: "Abort",
+      startsIn: "Typing starts in {seconds} s",
+      outcome: {
+        performed: "Finished typing",
+        aborted: "Typing was aborted"
+      }
+    },
+    snippets: {
+      title: "✂️ Snippets",
+      description: "Text typed into the focused window by a hotkey. The body may use {date}, {time} and {clipboard}; write {{ or }} for a brace.",
+      name: "Name",
+      hotkey: "Hotkey, e.g. Ctrl+Alt+1",
+      body: "Text to type",
+      add: "Add Snippet",
+      remove: "Remove",
+      save: "Save Snippets"
+    },
+    schedule: {
+      title: "🗓️ Schedule",
+      description: "Start a profile during weekly time windows or from cron expressions, and stop it when the run ends. Keeps working while the window is hidden.",
+      enabled: "Follow the schedule",
+      timezone: "Time zone, e.g. Europe/Berlin (default: system)",
+      remove: "Remove",
+      start: "Start",
+      end: "End",
+      expression: "Cron (min hour day month weekday)",
+      duration: "Minutes",
+      addWindow: "Add Time Window",
+      addCron: "Add Cron Rule",
+      save: "Save Schedule",
+      upcoming: "Upcoming runs",
+      none: "No runs in the next two weeks",
+      calendars: "Calendars",
+      calendarsDescription: "Run a profile during the events of an .ics file, or of every .ics file in a folder. Recurring events, exceptions and moved instances are followed.",
+      calendarPath: "Path to an .ics file or folder",
+      categories: "Categories, comma separated (default: all)",
+      titlePattern: "Title pattern (regular expression)",
+      addCalendar: "Add Calendar",
+      previewCalendar: "Preview",
+      noEvents: "No matching events in the next two weeks",
+      rule: "Rule {index}",
+      days: {
+        Mon: "Mon",
+        Tue: "Tue",
+        Wed: "Wed",
+        Thu: "Thu",
+        Fri: "Fri",
+        Sat: "Sat",
+        Sun: "Sun"
+      }
+    },
+    errors: {
+      not_running: "Automation is not running",
+      not_paused: "Automation is not paused",
+      profile_not_found: "Profile \"{name}\" does not exist",
+      profile_exists: "A profile named \"{name}\" already exists",
+      empty_profile_name: "Profile name must not be empty",
+      last_profile: "The only profile cannot be deleted",
+      unsupported_schema: "The config file was written by a newer version (schema {version})",
+      invalidConfig: "⚠️ Please fix these settings before starting:",
+      macro_not_found: "Macro \"{name}\" does not exist",
+      macro_running: "A macro is already running",
+      automation_running: "Stop automation before running a macro or auto-type",
+      auto_type_running: "Auto-type is already running"
+    },
     process: {
       title: "🎭 Process Disguise",
       description: "Changes how this application appears in the system process list.",
@@ -75,6 +258,40 @@ export const translations = {
       description: "자동으로 마우스와 키보드를 움직여 컴퓨터 사용 중인 것처럼 보이게 합니다. 화면 보호기 방지, 자리 비움 상태 회피 등에 유용합니다.",
       startButton: "▶️ 시작",
       stopButton: "🛑 정지",
+      pauseButton: "⏸️ 일시정지",
+      resumeButton: "⏯️ 재개",
+      mode: "🔋 모드",
+      modeDescription: "절전 방지는 마우스나 키보드를 움직이지 않고 운영체제에 화면 꺼짐과 절전 모드를 막도록 요청합니다.",
+      modeSimulate: "활동 시뮬레이션",
+      modeKeepAwake: "절전 방지만",
+      modeBoth: "절전 방지 + 활동 시뮬레이션",
+      activeApps: "🪟 허용된 앱",
+      activeAppsDescription: "이 앱들 중 하나에 포커스가 있을 때만 동작합니다 (쉼표로 구분, 예: firefox, code). 비워 두면 모든 창에서 동작합니다.",
+      activeAppsPlaceholder: "모든 앱",
+      idleThreshold: "💤 유휴 시간",
+      idleThresholdDescription: "마우스와 키보드를 이 시간(초) 동안 사용하지 않았을 때만 동작하고, 다시 사용하면 즉시 멈춥니다. 비워 두면 항상 동작합니다.",
+      idleThresholdPlaceholder: "끔",
+      failSafe: "🛑 긴급 중지 모서리",
+      failSafeDescription: "체크한 화면 모서리로 마우스를 밀어 넣으면 단축키 없이도 자동화가 즉시 멈춥니다.",
+      failSafeTriggered: "{corner} 모서리에서 긴급 중지되어 자동화를 멈췄습니다",
+      sessionNone: "중지할 때까지 실행",
+      sessionDuration: "정해진 시간(분) 동안 실행",
+      sessionUntil: "지정한 시각까지 실행",
+      sessionMinutes: "분",
+      sessionEnded: "세션 시간이 끝나 자동화를 멈췄습니다",
+      corners: {
+        top_left: "왼쪽 위",
+        top_right: "오른쪽 위",
+        bottom_left: "왼쪽 아래",
+        bottom_right: "오른쪽 아래"
+      },
+      skipped: "건너뜀",
+      skipReason: {
+        focus_not_allowed: "허용되지 않은 창",
+        focus_unknown: "포커스된 창을 확인할 수 없음",
+        user_active: "사용자가 컴퓨터를 사용 중",
+        idle_unknown: "유휴 시간을 확인할 수 없음"
+      },
       intervalSettings: "⏱️ 간격 설정",
       intervalDescription: "동작 간격을 설정합니다. 랜덤한 간격으로 자연스럽게 동작합니다.",
       minInterval: "최소 간격 (초):",
@@ -83,6 +300,37 @@ export const translations = {
       actionsDescription: "수행할 동작을 선택합니다. 마우스 움직임은 기본으로 활성화됩니다.",
       mouseRange: "마우스 이동 범위:",
       enableClicks: "랜덤 클릭 활성화",
+      clickButton: "버튼",
+      mouseButtons: {
+        left: "왼쪽",
+        middle: "가운데",
+        right: "오른쪽"
+      },
+      clickCount: "클릭 횟수",
+      clickTarget: "클릭 위치",
+      clickTargets: {
+        cursor: "현재 커서 위치",
+        point: "고정된 지점",
+        region: "영역 안의 임의 지점"
+      },
+      noClickZones: "클릭 금지 영역",
+      noClickZonesHint: "작업 표시줄이나 닫기 버튼처럼 이 영역 안에 떨어지는 클릭은 거부됩니다.",
+      addZone: "영역 추가",
+      removeZone: "삭제",
+      enableScroll: "스크롤 활성화",
+      scrollAmount: "칸 수",
+      scrollAxis: "방향 축",
+      scrollAxes: {
+        vertical: "세로",
+        horizontal: "가로"
+      },
+      scrollPattern: "방향",
+      scrollPatterns: {
+        random: "무작위",
+        down: "아래 / 오른쪽",
+        up: "위 / 왼쪽",
+        down_then_up: "아래로 갔다가 다시 위로"
+      },
       enableKeyboard: "키보드 활동 활성화",
       textToType: "입력할 텍스트:",
       textPlaceholder: "자동으로 입력될 텍스트를 입력하세요...",
@@ -90,9 +338,127 @@ export const translations = {
       mouseMovement: "🖱️ 마우스 움직임",
       mouseDescription: "마우스 움직임의 범위를 설정합니다. 값이 클수록 더 크게 움직입니다.",
       movementRange: "움직임 범위:",
+      moveBounds: "커서가 머무를 영역:",
+      boundsCurrentMonitor: "현재 모니터",
+      boundsRegion: "사용자 지정 영역",
+      region: {
+        x: "왼쪽",
+        y: "위쪽",
+        width: "너비",
+        height: "높이"
+      },
+      restoreCursor: "움직인 후 커서를 원래 위치로 되돌리기",
       tip: "💡 팁: 탐지 패턴을 피하기 위해 간격을 랜덤하게 유지하세요.",
       currentInterval: "현재:"
     },
+    profiles: {
+      title: "📁 프로필",
+      description: "상황별로 설정을 따로 저장하고 여기나 트레이에서 전환합니다.",
+      create: "새로 만들기",
+      rename: "이름 변경",
+      duplicate: "복제",
+      delete: "삭제",
+      namePrompt: "프로필 이름:",
+      deleteConfirm: "\"{name}\" 프로필을 삭제할까요?"
+    },
+    hotkeys: {
+      title: "⌨️ 전역 단축키",
+      description: "어떤 앱에서든 자동화를 제어합니다 (예: Ctrl+Alt+L). 비워 두면 해당 단축키를 끕니다.",
+      toggle: "시작 / 중지",
+      pause: "일시정지 / 재개",
+      panic_stop: "긴급 중지",
+      abort_type: "자동 입력 중단",
+      unbound: "끔",
+      conflict: "{hotkey}을(를) 사용할 수 없음",
+      save: "단축키 저장"
+    },
+    macros: {
+      title: "📜 매크로",
+      description: "{dir} 폴더의 .toml 파일에서 읽어 온 단계별 스크립트입니다.",
+      empty: "아직 매크로가 없습니다. 위 폴더에 .toml 파일을 추가한 뒤 새로고침하세요.",
+      run: "실행",
+      stop: "중지",
+      refresh: "새로고침",
+      line: "{line}번째 줄",
+      outcome: {
+        performed: "{name} 매크로를 마쳤습니다",
+        aborted: "{name} 매크로를 중지했습니다"
+      }
+    },
+    autoType: {
+      title: "🔤 자동 입력",
+      description: "붙여넣기가 막힌 콘솔이나 원격 데스크톱에 클립보드 또는 파일 내용을 타이핑합니다. 카운트다운 동안 대상 창으로 전환하세요.",
+      clipboard: "클립보드",
+      file: "파일",
+      pathPlaceholder: "텍스트 파일 경로",
+      wpm: "속도 (WPM)",
+      countdown: "카운트다운 (초)",
+      start: "입력",
+      stop: "중단",
+      startsIn: "{seconds}초 후 입력을 시작합니다",
+      outcome: {
+        performed: "입력을 마쳤습니다",
+        aborted: "입력을 중단했습니다"
+      }
+    },
+    snippets: {
+      title: "✂️ 스니펫",
+      description: "단축키를 누르면 현재 창에 입력되는 텍스트입니다. 본문에 {date}, {time}, {clipboard}를 쓸 수 있으며 중괄호 자체는 {{ 또는 }}로 씁니다.",
+      name: "이름",
+      hotkey: "단축키 (예: Ctrl+Alt+1)",
+      body: "입력할 텍스트",
+      add: "스니펫 추가",
+      remove: "삭제",
+      save: "스니펫 저장"
+    },
+    schedule: {
+      title: "🗓️ 일정",
+      description: "매주 정해진 시간대나 cron 표현식에 따라 프로필을 시작하고, 실행 시간이 끝나면 중지합니다. 창을 숨겨도 계속 동작합니다.",
+      enabled: "일정 따르기",
+      timezone: "시간대 (예: Asia/Seoul, 기본값: 시스템)",
+      remove: "삭제",
+      start: "시작",
+      end: "종료",
+      expression: "Cron (분 시 일 월 요일)",
+      duration: "분",
+      addWindow: "시간대 추가",
+      addCron: "Cron 규칙 추가",
+      save: "일정 저장",
+      upcoming: "예정된 실행",
+      none: "앞으로 2주 동안 예정된 실행이 없습니다",
+      calendars: "캘린더",
+      calendarsDescription: ".ics 파일이나 폴더 안의 모든 .ics 파일에 있는 일정 동안 프로필을 실행합니다. 반복 일정, 예외, 변경된 일정도 반영합니다.",
+      calendarPath: ".ics 파일 또는 폴더 경로",
+      categories: "카테고리, 쉼표로 구분 (기본값: 전체)",
+      titlePattern: "제목 패턴 (정규 표현식)",
+      addCalendar: "캘린더 추가",
+      previewCalendar: "미리 보기",
+      noEvents: "앞으로 2주 동안 해당하는 일정이 없습니다",
+      rule: "규칙 {index}",
+      days: {
+        Mon: "월",
+        Tue: "화",
+        Wed: "수",
+        Thu: "목",
+        Fri: "금",
+        Sat: "토",
+        Sun: "일"
+      }
+    },
+    errors: {
+      not_running: "자동화가 실행 중이 아닙니다",
+      not_paused: "자동화가 일시정지 상태가 아닙니다",
+      profile_not_found: "\"{name}\" 프로필이 없습니다",
+      profile_exists: "\"{name}\" 프로필이 이미 있습니다",
+      empty_profile_name: "프로필 이름을 입력하세요",
+      last_profile: "마지막 프로필은 삭제할 수 없습니다",
+      unsupported_schema: "더 최신 버전에서 저장한 설정 파일입니다 (스키마 {version})",
+      invalidConfig: "⚠️ 시작하기 전에 다음 설정을 수정하세요:",
+      macro_not_found: "\"{name}\" 매크로가 없습니다",
+      macro_running: "이미 실행 중인 매크로가 있습니다",
+      automation_running: "매크로나 자동 입력을 실행하려면 먼저 자동화를 중지하세요",
+      auto_type_running: "이미 자동 입력이 실행 중입니다"
+    },
     process: {
       title: "🎭 프로세스 위장",
       description: "시스템 프로세스 목록에서 이 애플리케이션이 표시되는 방식을 변경합니다.",